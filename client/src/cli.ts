@@ -54,6 +54,7 @@ program
     .option('-a, --arbiter <pubkey>', 'Optional arbiter public key')
     .requiredOption('-m, --amount <lamports>', 'Amount in lamports')
     .requiredOption('-t, --timeout <seconds>', 'Timeout period in seconds')
+    .option('-l, --label <label>', 'Optional support label, e.g. order-4821 (max 16 bytes)')
     .option('-c, --cluster <cluster>', 'Cluster (localnet/devnet/testnet/mainnet)', 'devnet')
     .option('-p, --program-id <pubkey>', 'Program ID', PROGRAM_ID.toString())
     .action(async (options) => {
@@ -83,6 +84,7 @@ program
                 arbiter,
                 amount,
                 timeoutPeriod,
+                label: options.label,
             });
 
             console.log(`\n✨ Success!`);
@@ -130,6 +132,7 @@ program
     .description('Release funds to seller')
     .requiredOption('-e, --escrow <pubkey>', 'Escrow PDA public key')
     .requiredOption('-k, --keypair <path>', 'Path to caller keypair (buyer/arbiter/seller)')
+    .option('-m, --memo <memo>', 'Optional memo for reconciliation (max 128 bytes)')
     .option('-c, --cluster <cluster>', 'Cluster (localnet/devnet/testnet/mainnet)', 'devnet')
     .option('-p, --program-id <pubkey>', 'Program ID', PROGRAM_ID.toString())
     .action(async (options) => {
@@ -144,7 +147,7 @@ program
 
             console.log(`\n🎯 Releasing funds to seller...\n`);
 
-            const signature = await client.releaseToSeller(escrowPda, callerKeypair);
+            const signature = await client.releaseToSeller(escrowPda, callerKeypair, options.memo);
 
             console.log(`\n✨ Success!`);
         } catch (error) {
@@ -188,6 +191,7 @@ program
     .description('Cancel an unfunded escrow')
     .requiredOption('-e, --escrow <pubkey>', 'Escrow PDA public key')
     .requiredOption('-k, --keypair <path>', 'Path to caller keypair (buyer/seller)')
+    .option('-r, --reason <code>', 'Reason code: 0 buyer withdrew, 1 seller declined, 2 expired, 3 mutual agreement', '0')
     .option('-c, --cluster <cluster>', 'Cluster (localnet/devnet/testnet/mainnet)', 'devnet')
     .option('-p, --program-id <pubkey>', 'Program ID', PROGRAM_ID.toString())
     .action(async (options) => {
@@ -202,7 +206,7 @@ program
 
             console.log(`\n❌ Cancelling escrow...\n`);
 
-            const signature = await client.cancelEscrow(escrowPda, callerKeypair);
+            const signature = await client.cancelEscrow(escrowPda, callerKeypair, Number(options.reason));
 
             console.log(`\n✨ Success!`);
         } catch (error) {
@@ -7,6 +7,7 @@ import {
 } from '@coral-xyz/anchor';
 import { PublicKey, SystemProgram, Keypair, Connection } from '@solana/web3.js';
 import * as fs from 'fs';
+import { createHash } from 'crypto';
 
 // Program ID (will be updated after deployment)
 export const PROGRAM_ID = new PublicKey('11111111111111111111111111111111');
@@ -20,6 +21,17 @@ export enum EscrowState {
     Released = 'Released',
     Refunded = 'Refunded',
     Cancelled = 'Cancelled',
+    PartiallyFunded = 'PartiallyFunded',
+}
+
+/**
+ * Dispute state enum matching Rust definition, tracked alongside EscrowState
+ */
+export enum DisputeState {
+    None = 'None',
+    Open = 'Open',
+    UnderAppeal = 'UnderAppeal',
+    Resolved = 'Resolved',
 }
 
 /**
@@ -32,6 +44,28 @@ export interface Escrow {
     amount: BN;
     createdAt: BN;
     timeoutPeriod: BN;
+    startTime: BN;
+    vesting: boolean;
+    vestedClaimed: BN;
+    label: number[];
+    disputeState: DisputeState;
+    referrer: PublicKey | null;
+    referralFeeBps: number;
+    releaseOnGoal: boolean;
+    accruedArbiterFee: BN;
+    arbiterWindow: BN;
+    mintReceipt: boolean;
+    receiptMint: PublicKey | null;
+    sellerAutoclaimDisabled: boolean;
+    dependsOn: PublicKey | null;
+    requiresArbiter: boolean;
+    disputeFeePayer: PublicKey | null;
+    disputeFee: BN;
+    expiry: BN;
+    expiryDefault: Record<string, Record<string, never>>;
+    releaseVoucher: number[] | null;
+    category: Record<string, Record<string, never>>;
+    fundedSlot: BN;
     state: EscrowState;
     bump: number;
 }
@@ -45,6 +79,49 @@ export interface InitEscrowParams {
     arbiter?: PublicKey;
     amount: BN;
     timeoutPeriod: BN;
+    startTime?: BN;
+    vesting?: boolean;
+    requiresTerms?: boolean;
+    label?: string;
+    fundingDeadline?: BN;
+    recurring?: boolean;
+    periodCount?: number;
+    refundPolicy?: Record<string, Record<string, never>>;
+    softDeadline?: BN;
+    penaltyRateBpsPerDay?: number;
+    referrer?: PublicKey;
+    referralFeeBps?: number;
+    releaseOnGoal?: boolean;
+    arbiterWindow?: BN;
+    mintReceipt?: boolean;
+    checkBuyerBalance?: boolean;
+    sellerAutoclaimDisabled?: boolean;
+    dependsOn?: PublicKey;
+    requiresArbiter?: boolean;
+    expiry?: BN;
+    expiryDefault?: Record<string, Record<string, never>>;
+    /** 0 Goods, 1 Services, 2 Rental, 3 Other */
+    category?: number;
+    /** Arbiter's keypair, to list the escrow in its caseload index */
+    arbiterSigner?: Keypair;
+}
+
+/**
+ * Encode a support label (e.g. "order-4821") as the 16 null-padded bytes stored on-chain
+ */
+export function encodeLabel(label: string = ''): number[] {
+    const bytes = Buffer.from(label, 'utf8');
+    if (bytes.length > 16) {
+        throw new Error(`Label is ${bytes.length} bytes, max is 16`);
+    }
+    return Array.from(Buffer.concat([bytes, Buffer.alloc(16 - bytes.length)]));
+}
+
+/**
+ * Decode an on-chain label, dropping the null padding
+ */
+export function decodeLabel(label: number[]): string {
+    return Buffer.from(label).toString('utf8').replace(/\0+$/, '');
 }
 
 /**
@@ -92,6 +169,94 @@ export class EscrowClient {
         );
     }
 
+    /**
+     * Derive the program-wide stats PDA address
+     */
+    deriveGlobalStatsPda(): [PublicKey, number] {
+        return PublicKey.findProgramAddressSync(
+            [Buffer.from('global_stats')],
+            this.program.programId
+        );
+    }
+
+    /**
+     * Derive the program config PDA address
+     */
+    deriveConfigPda(): [PublicKey, number] {
+        return PublicKey.findProgramAddressSync(
+            [Buffer.from('config')],
+            this.program.programId
+        );
+    }
+
+    /**
+     * Derive the cancel cooldown marker PDA for a buyer/seller pair
+     */
+    deriveCooldownPda(buyer: PublicKey, seller: PublicKey): [PublicKey, number] {
+        return PublicKey.findProgramAddressSync(
+            [Buffer.from('cooldown'), buyer.toBuffer(), seller.toBuffer()],
+            this.program.programId
+        );
+    }
+
+    /**
+     * Derive the protocol fee vault PDA
+     */
+    deriveFeeVaultPda(): [PublicKey, number] {
+        return PublicKey.findProgramAddressSync(
+            [Buffer.from('fee_vault')],
+            this.program.programId
+        );
+    }
+
+    /**
+     * Derive the archive PDA for the escrow created at `createdAt`
+     */
+    deriveArchivePda(escrowPda: PublicKey, createdAt: BN): [PublicKey, number] {
+        return PublicKey.findProgramAddressSync(
+            [Buffer.from('archive'), escrowPda.toBuffer(), createdAt.toArrayLike(Buffer, 'le', 8)],
+            this.program.programId
+        );
+    }
+
+    /**
+     * `address` if an account exists there, otherwise null (for optional accounts)
+     */
+    async optionalAccount(address: PublicKey | null): Promise<PublicKey | null> {
+        if (!address) return null;
+        return (await this.provider.connection.getAccountInfo(address)) ? address : null;
+    }
+
+    /**
+     * Derive an arbiter's stake PDA
+     */
+    deriveArbiterStakePda(arbiter: PublicKey): [PublicKey, number] {
+        return PublicKey.findProgramAddressSync(
+            [Buffer.from('arbiter_stake'), arbiter.toBuffer()],
+            this.program.programId
+        );
+    }
+
+    /**
+     * Derive an arbiter's caseload index PDA
+     */
+    deriveArbiterIndexPda(arbiter: PublicKey): [PublicKey, number] {
+        return PublicKey.findProgramAddressSync(
+            [Buffer.from('arbiter_index'), arbiter.toBuffer()],
+            this.program.programId
+        );
+    }
+
+    /**
+     * Derive an escrow's tranche schedule PDA
+     */
+    deriveTrancheSchedulePda(escrowPda: PublicKey): [PublicKey, number] {
+        return PublicKey.findProgramAddressSync(
+            [Buffer.from('tranches'), escrowPda.toBuffer()],
+            this.program.programId
+        );
+    }
+
     /**
      * Initialize a new escrow
      */
@@ -99,19 +264,56 @@ export class EscrowClient {
         signature: string;
         escrowPda: PublicKey;
     }> {
-        const { buyer, seller, arbiter, amount, timeoutPeriod } = params;
+        const { buyer, seller, arbiter, amount, timeoutPeriod, startTime, vesting, requiresTerms, label, fundingDeadline, recurring, periodCount, refundPolicy, softDeadline, penaltyRateBpsPerDay, referrer, referralFeeBps, releaseOnGoal, arbiterWindow, mintReceipt, checkBuyerBalance, sellerAutoclaimDisabled, dependsOn, requiresArbiter, expiry, expiryDefault, category, arbiterSigner } = params;
 
         const [escrowPda, bump] = this.deriveEscrowPda(buyer, seller);
 
+        // Pass the arbiter's stake along when it has one, for configs that require it
+        const arbiterStake = await this.optionalAccount(
+            arbiter ? this.deriveArbiterStakePda(arbiter)[0] : null
+        );
+        // Listing in the arbiter's caseload index needs the arbiter to sign
+        const arbiterIndex = await this.optionalAccount(
+            arbiter && arbiterSigner ? this.deriveArbiterIndexPda(arbiter)[0] : null
+        );
+
         const tx = await this.program.methods
-            .initializeEscrow(amount, timeoutPeriod)
+            .initializeEscrow(amount, timeoutPeriod, {
+                startTime: startTime || new BN(0),
+                vesting: vesting || false,
+                requiresTerms: requiresTerms || false,
+                label: encodeLabel(label),
+                fundingDeadline: fundingDeadline || new BN(0),
+                recurring: recurring || false,
+                periodCount: periodCount || 0,
+                refundPolicy: refundPolicy || { any: {} },
+                softDeadline: softDeadline || new BN(0),
+                penaltyRateBpsPerDay: penaltyRateBpsPerDay || 0,
+                referrer: referrer || null,
+                referralFeeBps: referralFeeBps || 0,
+                releaseOnGoal: releaseOnGoal || false,
+                arbiterWindow: arbiterWindow || new BN(0),
+                mintReceipt: mintReceipt || false,
+                checkBuyerBalance: checkBuyerBalance || false,
+                sellerAutoclaimDisabled: sellerAutoclaimDisabled || false,
+                dependsOn: dependsOn || null,
+                requiresArbiter: requiresArbiter || false,
+                expiry: expiry || new BN(0),
+                expiryDefault: expiryDefault || { buyer: {} },
+                category: category || 0,
+            })
             .accounts({
                 escrow: escrowPda,
                 buyer: buyer,
                 seller: seller,
                 arbiter: arbiter || buyer, // Use buyer as arbiter if none specified
+                cooldown: this.deriveCooldownPda(buyer, seller)[0],
+                config: this.deriveConfigPda()[0],
+                arbiterStake,
+                arbiterIndex,
                 systemProgram: SystemProgram.programId,
             })
+            .signers(arbiterIndex && arbiterSigner ? [arbiterSigner] : [])
             .rpc();
 
         console.log(`✅ Escrow initialized: ${escrowPda.toString()}`);
@@ -133,6 +335,7 @@ export class EscrowClient {
             .fundEscrow()
             .accounts({
                 escrow: escrowPda,
+                globalStats: this.deriveGlobalStatsPda()[0],
                 buyer: buyer.publicKey,
                 systemProgram: SystemProgram.programId,
             })
@@ -145,23 +348,55 @@ export class EscrowClient {
         return tx;
     }
 
+    /**
+     * Mint the buyer's receipt token for a funded escrow created with mintReceipt
+     */
+    async mintReceipt(
+        escrowPda: PublicKey,
+        buyer: Keypair
+    ): Promise<string> {
+        const tx = await this.program.methods
+            .mintReceipt()
+            .accounts({
+                escrow: escrowPda,
+                buyer: buyer.publicKey,
+            })
+            .signers([buyer])
+            .rpc();
+
+        console.log(`✅ Receipt minted to buyer`);
+        console.log(`📝 Transaction: ${tx}`);
+
+        return tx;
+    }
+
     /**
      * Release funds to seller
      */
     async releaseToSeller(
         escrowPda: PublicKey,
-        caller: Keypair
+        caller: Keypair,
+        memo?: string,
+        cosigner?: Keypair
     ): Promise<string> {
         const escrow = await this.getEscrowState(escrowPda);
 
         const tx = await this.program.methods
-            .releaseToSeller()
+            .releaseToSeller(memo ?? null)
             .accounts({
                 escrow: escrowPda,
+                globalStats: this.deriveGlobalStatsPda()[0],
+                config: this.deriveConfigPda()[0],
+                cosigner: cosigner ? cosigner.publicKey : null,
+                sellerReputation: null,
+                buyer: escrow.buyer,
+                referrer: escrow.referrer,
+                feeVault: await this.optionalAccount(this.deriveFeeVaultPda()[0]),
+                dependency: escrow.dependsOn,
                 seller: escrow.seller,
                 caller: caller.publicKey,
             })
-            .signers([caller])
+            .signers(cosigner ? [caller, cosigner] : [caller])
             .rpc();
 
         console.log(`✅ Funds released to seller`);
@@ -170,12 +405,167 @@ export class EscrowClient {
         return tx;
     }
 
+    /**
+     * Buyer confirms delivery, releasing funds to the seller
+     */
+    async confirmDelivery(
+        escrowPda: PublicKey,
+        buyer: Keypair,
+        cosigner?: Keypair
+    ): Promise<string> {
+        const escrow = await this.getEscrowState(escrowPda);
+
+        const tx = await this.program.methods
+            .confirmDelivery()
+            .accounts({
+                escrow: escrowPda,
+                globalStats: this.deriveGlobalStatsPda()[0],
+                config: this.deriveConfigPda()[0],
+                cosigner: cosigner ? cosigner.publicKey : null,
+                sellerReputation: null,
+                buyer: escrow.buyer,
+                referrer: escrow.referrer,
+                feeVault: await this.optionalAccount(this.deriveFeeVaultPda()[0]),
+                dependency: escrow.dependsOn,
+                seller: escrow.seller,
+                caller: buyer.publicKey,
+            })
+            .signers(cosigner ? [buyer, cosigner] : [buyer])
+            .rpc();
+
+        console.log(`✅ Delivery confirmed, funds released to seller`);
+        console.log(`📝 Transaction: ${tx}`);
+
+        return tx;
+    }
+
+    /**
+     * Buyer and seller agree to release the escrow in tranches, before funding
+     */
+    async setTrancheSchedule(
+        escrowPda: PublicKey,
+        buyer: Keypair,
+        seller: Keypair,
+        tranches: { amount: BN; unlockTime: BN }[]
+    ): Promise<string> {
+        const tx = await this.program.methods
+            .setTrancheSchedule(tranches)
+            .accounts({
+                escrow: escrowPda,
+                trancheSchedule: this.deriveTrancheSchedulePda(escrowPda)[0],
+                buyer: buyer.publicKey,
+                seller: seller.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([buyer, seller])
+            .rpc();
+
+        console.log(`✅ Tranche schedule set: ${tranches.length} tranches`);
+        console.log(`📝 Transaction: ${tx}`);
+
+        return tx;
+    }
+
+    /**
+     * Seller claims a funded, unlocked tranche
+     */
+    async claimTranche(escrowPda: PublicKey, seller: Keypair, index: number): Promise<string> {
+        const tx = await this.program.methods
+            .claimTranche(index)
+            .accounts({
+                escrow: escrowPda,
+                trancheSchedule: this.deriveTrancheSchedulePda(escrowPda)[0],
+                seller: seller.publicKey,
+                globalStats: this.deriveGlobalStatsPda()[0],
+            })
+            .signers([seller])
+            .rpc();
+
+        console.log(`✅ Tranche ${index} claimed`);
+        console.log(`📝 Transaction: ${tx}`);
+
+        return tx;
+    }
+
+    /**
+     * Fetch an escrow's tranche schedule
+     */
+    async getTrancheSchedule(escrowPda: PublicKey): Promise<any> {
+        return await this.program.account.trancheSchedule.fetch(
+            this.deriveTrancheSchedulePda(escrowPda)[0]
+        );
+    }
+
+    /**
+     * Buyer or arbiter hash-locks a release; the seller redeems it with the
+     * preimage. Returns the voucher hash that was stored.
+     */
+    async issueVoucher(
+        escrowPda: PublicKey,
+        issuer: Keypair,
+        preimage: Buffer
+    ): Promise<Buffer> {
+        const voucherHash = createHash('sha256').update(preimage).digest();
+
+        const tx = await this.program.methods
+            .issueVoucher([...voucherHash])
+            .accounts({
+                escrow: escrowPda,
+                issuer: issuer.publicKey,
+            })
+            .signers([issuer])
+            .rpc();
+
+        console.log(`✅ Release voucher issued`);
+        console.log(`📝 Transaction: ${tx}`);
+
+        return voucherHash;
+    }
+
+    /**
+     * Seller redeems a release voucher, releasing funds to itself
+     */
+    async redeemVoucher(
+        escrowPda: PublicKey,
+        seller: Keypair,
+        preimage: Buffer,
+        cosigner?: Keypair
+    ): Promise<string> {
+        const escrow = await this.getEscrowState(escrowPda);
+
+        const tx = await this.program.methods
+            .redeemVoucher(preimage)
+            .accounts({
+                release: {
+                    escrow: escrowPda,
+                    globalStats: this.deriveGlobalStatsPda()[0],
+                    config: this.deriveConfigPda()[0],
+                    cosigner: cosigner ? cosigner.publicKey : null,
+                    sellerReputation: null,
+                    buyer: escrow.buyer,
+                    referrer: escrow.referrer,
+                    feeVault: await this.optionalAccount(this.deriveFeeVaultPda()[0]),
+                    dependency: escrow.dependsOn,
+                    seller: escrow.seller,
+                    caller: seller.publicKey,
+                },
+            })
+            .signers(cosigner ? [seller, cosigner] : [seller])
+            .rpc();
+
+        console.log(`✅ Release voucher redeemed, funds released to seller`);
+        console.log(`📝 Transaction: ${tx}`);
+
+        return tx;
+    }
+
     /**
      * Refund funds to buyer
      */
     async refundToBuyer(
         escrowPda: PublicKey,
-        caller: Keypair
+        caller: Keypair,
+        cosigner?: Keypair
     ): Promise<string> {
         const escrow = await this.getEscrowState(escrowPda);
 
@@ -183,10 +573,16 @@ export class EscrowClient {
             .refundToBuyer()
             .accounts({
                 escrow: escrowPda,
+                globalStats: this.deriveGlobalStatsPda()[0],
+                config: this.deriveConfigPda()[0],
+                cosigner: cosigner ? cosigner.publicKey : null,
+                counterparty: null,
+                receipt: null,
                 buyer: escrow.buyer,
+                seller: escrow.seller,
                 caller: caller.publicKey,
             })
-            .signers([caller])
+            .signers(cosigner ? [caller, cosigner] : [caller])
             .rpc();
 
         console.log(`✅ Funds refunded to buyer`);
@@ -200,16 +596,20 @@ export class EscrowClient {
      */
     async cancelEscrow(
         escrowPda: PublicKey,
-        caller: Keypair
+        caller: Keypair,
+        reasonCode: number = 0
     ): Promise<string> {
         const escrow = await this.getEscrowState(escrowPda);
 
         const tx = await this.program.methods
-            .cancelEscrow()
+            .cancelEscrow(reasonCode)
             .accounts({
                 escrow: escrowPda,
                 buyer: escrow.buyer,
+                cooldown: this.deriveCooldownPda(escrow.buyer, escrow.seller)[0],
+                config: this.deriveConfigPda()[0],
                 caller: caller.publicKey,
+                systemProgram: SystemProgram.programId,
             })
             .signers([caller])
             .rpc();
@@ -220,6 +620,55 @@ export class EscrowClient {
         return tx;
     }
 
+    /**
+     * Arbiter withdraws the fee accrued when resolving the escrow
+     */
+    async claimArbiterFee(
+        escrowPda: PublicKey,
+        arbiter: Keypair
+    ): Promise<string> {
+        const tx = await this.program.methods
+            .claimArbiterFee()
+            .accounts({
+                escrow: escrowPda,
+                arbiter: arbiter.publicKey,
+            })
+            .signers([arbiter])
+            .rpc();
+
+        console.log(`✅ Arbiter fee claimed`);
+        console.log(`📝 Transaction: ${tx}`);
+
+        return tx;
+    }
+
+    /**
+     * Buyer archives a settled escrow's summary and closes the escrow
+     */
+    async archiveEscrow(
+        escrowPda: PublicKey,
+        buyer: Keypair
+    ): Promise<{ signature: string; archivePda: PublicKey }> {
+        const escrow = await this.getEscrowState(escrowPda);
+        const [archivePda] = this.deriveArchivePda(escrowPda, escrow.createdAt);
+
+        const tx = await this.program.methods
+            .archiveEscrow()
+            .accounts({
+                escrow: escrowPda,
+                archive: archivePda,
+                buyer: buyer.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([buyer])
+            .rpc();
+
+        console.log(`✅ Escrow archived at ${archivePda.toString()}`);
+        console.log(`📝 Transaction: ${tx}`);
+
+        return { signature: tx, archivePda };
+    }
+
     /**
      * Get escrow account state
      */
@@ -228,6 +677,60 @@ export class EscrowClient {
         return escrowAccount as Escrow;
     }
 
+    /**
+     * The arbiter that can act on the escrow, with the buyer sentinel resolved
+     */
+    async getEffectiveArbiter(escrowPda: PublicKey): Promise<PublicKey | null> {
+        return await this.program.methods
+            .effectiveArbiter()
+            .accounts({ escrow: escrowPda })
+            .view();
+    }
+
+    /**
+     * The program's operator config, read through get_config
+     */
+    async getConfig(): Promise<any> {
+        return await this.program.methods
+            .getConfig()
+            .accounts({ config: this.deriveConfigPda()[0] })
+            .view();
+    }
+
+    /**
+     * Escrows listed in an arbiter's caseload index
+     */
+    async getArbiterIndex(arbiter: PublicKey): Promise<PublicKey[]> {
+        return await this.program.methods
+            .getArbiterIndex()
+            .accounts({ arbiterIndex: this.deriveArbiterIndexPda(arbiter)[0] })
+            .view();
+    }
+
+    /**
+     * Lamports the seller of a vesting escrow could claim right now
+     */
+    async getVestedClaimable(escrowPda: PublicKey): Promise<BN> {
+        return await this.program.methods
+            .vestedClaimable()
+            .accounts({ escrow: escrowPda })
+            .view();
+    }
+
+    /**
+     * Whether `caller` could cancel the escrow right now
+     */
+    async isCancellable(escrowPda: PublicKey, caller: PublicKey): Promise<boolean> {
+        return await this.program.methods
+            .isCancellable()
+            .accounts({
+                escrow: escrowPda,
+                config: this.deriveConfigPda()[0],
+                caller,
+            })
+            .view();
+    }
+
     /**
      * Display escrow state in a readable format
      */
@@ -243,7 +746,9 @@ export class EscrowClient {
         console.log(`Escrow Address:  ${escrowPda.toString()}`);
         console.log(`Buyer:           ${escrow.buyer.toString()}`);
         console.log(`Seller:          ${escrow.seller.toString()}`);
-        console.log(`Arbiter:         ${escrow.arbiter ? escrow.arbiter.toString() : 'None'}`);
+        const arbiter = await this.getEffectiveArbiter(escrowPda);
+        console.log(`Arbiter:         ${arbiter ? arbiter.toString() : 'None'}`);
+        console.log(`Label:           ${decodeLabel(escrow.label) || 'None'}`);
         console.log(`Amount:          ${escrow.amount.toString()} lamports (${(escrow.amount.toNumber() / web3.LAMPORTS_PER_SOL).toFixed(4)} SOL)`);
         console.log(`State:           ${escrow.state}`);
         console.log(`Created:         ${new Date(escrow.createdAt.toNumber() * 1000).toISOString()}`);
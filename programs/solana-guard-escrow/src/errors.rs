@@ -23,4 +23,244 @@ pub enum EscrowError {
     
     #[msg("Invalid timeout period (must be greater than 0)")]
     InvalidTimeout,
+    
+    #[msg("Invalid start time (must not be negative)")]
+    InvalidStartTime,
+    
+    #[msg("Escrow is not active yet, start time has not been reached")]
+    NotYetActive,
+    
+    #[msg("Arithmetic overflow in counter update")]
+    ArithmeticOverflow,
+    
+    #[msg("Escrow holds no funds beyond the agreed amount")]
+    NoExcessFunds,
+    
+    #[msg("Memo exceeds the maximum allowed length")]
+    MemoTooLong,
+    
+    #[msg("High-value escrow requires the configured cosigner")]
+    CosignerRequired,
+    
+    #[msg("Recipient account does not match the escrow")]
+    InvalidRecipient,
+    
+    #[msg("Invalid fee (must not exceed 10000 bps and needs an arbiter)")]
+    InvalidFee,
+    
+    #[msg("Invalid cooldown (must not be negative)")]
+    InvalidCooldown,
+    
+    #[msg("This buyer/seller pair cancelled recently, cooldown still active")]
+    CooldownActive,
+    
+    #[msg("Account is not owned by this program")]
+    InvalidAccountOwner,
+    
+    #[msg("Escrow is not a vesting escrow")]
+    NotVesting,
+    
+    #[msg("Nothing is claimable right now")]
+    NothingToClaim,
+    
+    #[msg("Both parties must bind the terms before funding")]
+    TermsNotBound,
+    
+    #[msg("Different terms are already bound to this escrow")]
+    TermsAlreadyBound,
+    
+    #[msg("Refund recipient can't receive funds (closed or below rent-exempt minimum)")]
+    RefundRecipientUnusable,
+    
+    #[msg("Batch must be 1-10 (escrow, buyer) account pairs")]
+    InvalidBatch,
+    
+    #[msg("Funding deadline must be in the future")]
+    InvalidFundingDeadline,
+    
+    #[msg("Crowdfunded escrows are funded through contribute")]
+    ContributionsOnly,
+    
+    #[msg("Funding deadline has passed")]
+    FundingDeadlinePassed,
+    
+    #[msg("Not supported on crowdfunded escrows")]
+    CrowdfundingUnsupported,
+    
+    #[msg("Escrow is not crowdfunded")]
+    NotCrowdfunded,
+    
+    #[msg("Fee exceeds the configured arbiter fee cap")]
+    FeeAboveCap,
+    
+    #[msg("Mint is not an NFT (needs 0 decimals and a supply of 1)")]
+    InvalidNft,
+    
+    #[msg("Escrow account size doesn't match the expected layout")]
+    UnexpectedAccountSize,
+    
+    #[msg("No default arbiter is configured")]
+    NoDefaultArbiter,
+    
+    #[msg("Appeal window must not be negative")]
+    InvalidAppealWindow,
+    
+    #[msg("Appeal window has closed")]
+    AppealWindowClosed,
+    
+    #[msg("Appeal window is still open")]
+    AppealWindowOpen,
+    
+    #[msg("An arbiter decision is pending its appeal window")]
+    DecisionPending,
+    
+    #[msg("Escrow is under appeal")]
+    AppealPending,
+    
+    #[msg("Recurring escrows need at least one period and can't be crowdfunded")]
+    InvalidRecurrence,
+    
+    #[msg("Escrow holds nothing above rent to transfer")]
+    NothingToTransfer,
+    
+    #[msg("Payout destination is not owned by the expected program")]
+    DestinationNotProgramOwned,
+    
+    #[msg("New escrows are disabled while the deployment winds down")]
+    NewEscrowsDisabled,
+    
+    #[msg("Missing or invalid off-chain signature for this authorization")]
+    InvalidAuthorization,
+    
+    #[msg("Authorization nonce was already used or is out of order")]
+    StaleAuthorizationNonce,
+    
+    #[msg("Escrow already holds its rent-exempt minimum")]
+    NoRentShortfall,
+    
+    #[msg("Escrow has no arbiter to hear a dispute")]
+    NoArbiter,
+    
+    #[msg("Late penalty needs a soft deadline, a rate of at most 10000 bps, and a one-off, non-vesting escrow")]
+    InvalidPenalty,
+    
+    #[msg("Transfer exceeds the configured per-instruction cap")]
+    TransferCapExceeded,
+    
+    #[msg("Installment would fund the escrow beyond its amount")]
+    InstallmentExceedsTarget,
+    
+    #[msg("Timeout period is below the 60 second minimum")]
+    TimeoutTooShort,
+    
+    #[msg("Unknown cancel reason code")]
+    InvalidCancelReason,
+    
+    #[msg("Arbiter and referral fees together exceed 100%")]
+    FeesExceedTotal,
+    
+    #[msg("Release would leave the seller account below the rent-exempt minimum")]
+    SellerBelowRent,
+    
+    #[msg("No arbiter fee to claim")]
+    NoFeeAccrued,
+    
+    #[msg("Arbiter fee has not been claimed yet")]
+    ArbiterFeeUnclaimed,
+    
+    #[msg("Escrow balance is below the amount its state records as held")]
+    InsufficientEscrowBalance,
+    
+    #[msg("Escrow was not created with a receipt")]
+    ReceiptNotEnabled,
+    
+    #[msg("Receipt has already been minted")]
+    ReceiptAlreadyMinted,
+    
+    #[msg("Refund requires the escrow's receipt token account")]
+    ReceiptRequired,
+    
+    #[msg("Buyer balance cannot cover the escrow amount and fees")]
+    InsufficientBuyerBalance,
+    
+    #[msg("Arbiter has not staked the configured minimum")]
+    InsufficientArbiterStake,
+    
+    #[msg("Releases with a protocol fee require the fee vault")]
+    FeeVaultRequired,
+    
+    #[msg("Withdrawal exceeds the fees held in the vault")]
+    InsufficientFeeBalance,
+    
+    #[msg("Signer is neither the escrow's buyer nor its operator")]
+    NotEscrowBuyer,
+    
+    #[msg("The escrow this one depends on has not released")]
+    DependencyNotSatisfied,
+    
+    #[msg("Fee discount tiers must have positive, strictly increasing windows")]
+    InvalidFeeTiers,
+    
+    #[msg("This escrow requires an arbiter before it can be funded")]
+    ArbiterRequired,
+    
+    #[msg("Vesting and recurring payouts can't be combined on one escrow")]
+    ConflictingPayoutModes,
+    
+    #[msg("Buyer, seller and arbiter must be three different keys")]
+    DuplicateRole,
+    
+    #[msg("Escrow has no expiry, or it hasn't passed yet")]
+    NotExpired,
+    
+    #[msg("A release voucher is already outstanding on this escrow")]
+    VoucherAlreadyIssued,
+    
+    #[msg("No release voucher has been issued on this escrow")]
+    NoVoucherIssued,
+    
+    #[msg("Preimage does not hash to the release voucher")]
+    VoucherMismatch,
+    
+    #[msg("Voucher preimage exceeds the maximum length")]
+    VoucherPreimageTooLong,
+    
+    #[msg("Unknown escrow category code")]
+    InvalidCategory,
+    
+    #[msg("Release must wait the configured number of slots after funding")]
+    TooSoonAfterFunding,
+    
+    #[msg("Arbiter index belongs to a different arbiter")]
+    ArbiterIndexMismatch,
+    
+    #[msg("Partial release is below the configured minimum")]
+    BelowMinimumRelease,
+    
+    #[msg("Escrow isn't at its canonical address, so its bump can't be repaired")]
+    BumpRepairMismatch,
+    
+    #[msg("Tranches must be 1 to 8 non-zero amounts with non-decreasing unlock times")]
+    InvalidTrancheSchedule,
+    
+    #[msg("Tranche amounts must add up to the escrow amount")]
+    TrancheSumMismatch,
+    
+    #[msg("Tranche schedule was set for different terms of this escrow")]
+    StaleTrancheSchedule,
+    
+    #[msg("No tranche at this index")]
+    InvalidTrancheIndex,
+    
+    #[msg("Tranche already claimed")]
+    TrancheAlreadyClaimed,
+    
+    #[msg("Tranche hasn't unlocked yet")]
+    TrancheLocked,
+    
+    #[msg("Buyer hasn't funded this tranche yet")]
+    TrancheNotFunded,
+    
+    #[msg("The arbiter must sign to list an escrow in its index")]
+    ArbiterConsentRequired,
 }
@@ -0,0 +1,334 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{ArbiterDecision, CancelReason, EscrowCategory, EscrowState, ExpiryDefault};
+
+/// Maximum byte length of the optional release memo
+pub const MAX_MEMO_LEN: usize = 128;
+
+/// Emitted when an escrow is created, carrying its category for indexers
+#[event]
+pub struct EscrowInitialized {
+    pub escrow: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub amount: u64,
+    pub category: EscrowCategory,
+    pub label: [u8; 16],
+}
+
+/// Emitted when escrowed funds are released to the seller
+#[event]
+pub struct EscrowReleased {
+    pub escrow: Pubkey,
+    pub seller: Pubkey,
+    pub amount: u64,
+    /// Off-chain reference (e.g. invoice id), not stored on the account
+    pub memo: Option<String>,
+    pub label: [u8; 16],
+}
+
+/// Emitted when funds go back to the buyer but the escrow returns to Created
+#[event]
+pub struct EscrowSoftRefunded {
+    pub escrow: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub label: [u8; 16],
+}
+
+/// Emitted when a partly paid installment escrow is abandoned and refunded
+#[event]
+pub struct InstallmentsRefunded {
+    pub escrow: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub refunded_by: Pubkey,
+    pub label: [u8; 16],
+}
+
+/// Emitted when buyer and seller refund a funded escrow to renegotiate it
+#[event]
+pub struct EscrowReopened {
+    pub escrow: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub label: [u8; 16],
+}
+
+/// Emitted when buyer and seller change the amount or timeout before funding
+#[event]
+pub struct TermsAmended {
+    pub escrow: Pubkey,
+    pub old_amount: u64,
+    pub new_amount: u64,
+    pub old_timeout_period: i64,
+    pub new_timeout_period: i64,
+    pub label: [u8; 16],
+}
+
+/// Emitted when the seller claims an unlocked, funded tranche
+#[event]
+pub struct TrancheClaimed {
+    pub escrow: Pubkey,
+    pub seller: Pubkey,
+    pub index: u8,
+    pub amount: u64,
+    /// Lamports the seller has claimed from the escrow so far
+    pub total_claimed: u64,
+    pub label: [u8; 16],
+}
+
+/// Emitted when the config authority corrects an escrow's stored bump
+#[event]
+pub struct BumpRepaired {
+    pub escrow: Pubkey,
+    pub old_bump: u8,
+    pub new_bump: u8,
+    pub label: [u8; 16],
+}
+
+/// Emitted when the config authority withdraws protocol fees
+#[event]
+pub struct FeesWithdrawn {
+    pub treasury: Pubkey,
+    pub amount: u64,
+    /// Fees left in the vault
+    pub remaining: u64,
+}
+
+/// Emitted when an arbiter adds to its stake
+#[event]
+pub struct ArbiterStaked {
+    pub arbiter: Pubkey,
+    pub amount: u64,
+    /// Stake after this deposit
+    pub total: u64,
+}
+
+/// Emitted when the config authority slashes an arbiter's stake
+#[event]
+pub struct ArbiterSlashed {
+    pub arbiter: Pubkey,
+    pub amount: u64,
+    /// Stake left after the slash
+    pub remaining: u64,
+}
+
+/// Emitted when the buyer's receipt token is minted
+#[event]
+pub struct ReceiptMinted {
+    pub escrow: Pubkey,
+    pub mint: Pubkey,
+    pub buyer: Pubkey,
+    pub label: [u8; 16],
+}
+
+/// Emitted when the arbiter withdraws its accrued resolution fee
+#[event]
+pub struct ArbiterFeeClaimed {
+    pub escrow: Pubkey,
+    pub arbiter: Pubkey,
+    pub amount: u64,
+    pub label: [u8; 16],
+}
+
+/// Emitted when settle_goal resolves a joint purchase
+#[event]
+pub struct GoalSettled {
+    pub escrow: Pubkey,
+    /// Target met by the deadline and released, otherwise open for reclaims
+    pub succeeded: bool,
+    /// Lamports paid to the seller (0 on failure)
+    pub amount: u64,
+    pub label: [u8; 16],
+}
+
+/// Emitted when the buyer confirms delivery through confirm_delivery
+#[event]
+pub struct DeliveryConfirmed {
+    pub escrow: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub label: [u8; 16],
+}
+
+/// Emitted when a release pays the referrer its cut
+#[event]
+pub struct ReferralPaid {
+    pub escrow: Pubkey,
+    pub referrer: Pubkey,
+    pub amount: u64,
+    pub label: [u8; 16],
+}
+
+/// Emitted when an unfunded escrow is cancelled, the account closes right after
+#[event]
+pub struct EscrowCancelled {
+    pub escrow: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub reason: CancelReason,
+    pub label: [u8; 16],
+}
+
+/// Emitted when the arbiter settles an escrow through arbiter_resolve
+#[event]
+pub struct ArbiterResolved {
+    pub escrow: Pubkey,
+    pub arbiter: Pubkey,
+    pub decision: ArbiterDecision,
+    /// Hash of the off-chain reasoning document
+    pub reason_hash: [u8; 32],
+    /// Lamports paid to the winning party
+    pub amount: u64,
+    /// Accrued on the escrow until claim_arbiter_fee
+    pub arbiter_fee: u64,
+    pub label: [u8; 16],
+}
+
+/// Emitted when buyer and seller bind a terms hash to the escrow
+#[event]
+pub struct TermsBound {
+    pub escrow: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub terms_hash: [u8; 32],
+    pub label: [u8; 16],
+}
+
+/// Emitted when the arbiter fee is renegotiated before funding
+#[event]
+pub struct ArbiterFeeUpdated {
+    pub escrow: Pubkey,
+    pub old_fee_bps: u16,
+    pub new_fee_bps: u16,
+    pub label: [u8; 16],
+}
+
+/// Emitted when buyer and seller fall back to the config's default arbiter
+#[event]
+pub struct ArbiterReassigned {
+    pub escrow: Pubkey,
+    pub old_arbiter: Option<Pubkey>,
+    pub new_arbiter: Pubkey,
+    pub label: [u8; 16],
+}
+
+/// Emitted when a party pings an escrow to show it is still in use.
+/// Deliberately small, since pings can be frequent.
+#[event]
+pub struct EscrowPinged {
+    pub escrow: Pubkey,
+    pub caller: Pubkey,
+    pub last_activity: i64,
+    pub label: [u8; 16],
+}
+
+/// Emitted when a late release returns part of the escrow to the buyer
+#[event]
+pub struct LatePenaltyRefunded {
+    pub escrow: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub label: [u8; 16],
+}
+
+/// Emitted when the buyer or seller opens a dispute for the arbiter
+#[event]
+pub struct DisputeOpened {
+    pub escrow: Pubkey,
+    pub opened_by: Pubkey,
+    /// Lamports the opener paid into the escrow
+    pub fee: u64,
+    pub label: [u8; 16],
+}
+
+/// Emitted when a resolution pays the dispute fee to the winning party
+#[event]
+pub struct DisputeFeeSettled {
+    pub escrow: Pubkey,
+    pub payer: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub label: [u8; 16],
+}
+
+/// Emitted when the arbiter steps down, leaving the escrow without one
+#[event]
+pub struct ArbiterRenounced {
+    pub escrow: Pubkey,
+    pub arbiter: Pubkey,
+    pub label: [u8; 16],
+}
+
+/// Emitted when the losing party appeals a pending arbiter decision
+#[event]
+pub struct AppealOpened {
+    pub escrow: Pubkey,
+    pub appellant: Pubkey,
+    /// The decision being appealed
+    pub decision: ArbiterDecision,
+    pub label: [u8; 16],
+}
+
+/// Emitted when the appeal arbiter makes the final decision
+#[event]
+pub struct AppealResolved {
+    pub escrow: Pubkey,
+    pub appeal_arbiter: Pubkey,
+    pub original_decision: ArbiterDecision,
+    pub decision: ArbiterDecision,
+    /// Hash of the appeal arbiter's off-chain reasoning document
+    pub reason_hash: [u8; 32],
+    /// Lamports paid to the winning party
+    pub amount: u64,
+    pub label: [u8; 16],
+}
+
+/// Emitted when a settled escrow is summarized into an archive and closed
+#[event]
+pub struct EscrowArchived {
+    pub escrow: Pubkey,
+    pub archive: Pubkey,
+    pub final_state: EscrowState,
+    pub label: [u8; 16],
+}
+
+/// Emitted when settle_expired settles an abandoned escrow
+#[event]
+pub struct ExpirySettled {
+    pub escrow: Pubkey,
+    /// Direction of the settlement
+    pub settled_to: ExpiryDefault,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub label: [u8; 16],
+}
+
+/// Emitted for every period a recurring escrow pays out
+#[event]
+pub struct RecurringPeriodReleased {
+    pub escrow: Pubkey,
+    pub seller: Pubkey,
+    pub amount: u64,
+    /// Periods still funded after this release (0 once the escrow is Released)
+    pub periods_remaining: u32,
+    pub label: [u8; 16],
+}
+
+/// Emitted when the buyer or arbiter hash-locks a release to the seller
+#[event]
+pub struct VoucherIssued {
+    pub escrow: Pubkey,
+    pub issuer: Pubkey,
+    pub voucher_hash: [u8; 32],
+    pub label: [u8; 16],
+}
+
+/// Emitted when the seller redeems a release voucher, right before the release
+#[event]
+pub struct VoucherRedeemed {
+    pub escrow: Pubkey,
+    pub seller: Pubkey,
+    pub voucher_hash: [u8; 32],
+    pub label: [u8; 16],
+}
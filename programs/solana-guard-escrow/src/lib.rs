@@ -1,11 +1,21 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::sysvar::instructions::{
+    self as sysvar_instructions, load_current_index_checked, load_instruction_at_checked,
+};
 use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::spl_token::instruction::AuthorityType;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount};
 
 pub mod state;
 pub mod errors;
+pub mod events;
 
 use state::*;
 use errors::*;
+use events::*;
 
 declare_id!("11111111111111111111111111111111");
 
@@ -18,195 +28,4768 @@ pub mod solana_guard_escrow {
         ctx: Context<InitializeEscrow>,
         amount: u64,
         timeout_period: i64,
+        options: EscrowOptions,
     ) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow;
         let clock = Clock::get()?;
 
+        require!(!ctx.accounts.config.winddown, EscrowError::NewEscrowsDisabled);
+
         // Basic validation - amount and timeout must be positive
-        require!(amount > 0, EscrowError::InvalidAmount);
+        validate_amount(amount)?;
         require!(timeout_period > 0, EscrowError::InvalidTimeout);
+        require!(timeout_period >= MIN_TIMEOUT_PERIOD, EscrowError::TimeoutTooShort);
+        // Zero means the escrow can be funded right away
+        require!(options.start_time >= 0, EscrowError::InvalidStartTime);
+        validate_schedule(
+            options.start_time,
+            options.funding_deadline,
+            options.expiry,
+            clock.unix_timestamp,
+        )?;
+        if options.funding_deadline != 0 {
+            // Vested payouts would leave less than the contributors put in
+            require!(!options.vesting, EscrowError::CrowdfundingUnsupported);
+            // There's no single buyer position to hand a receipt for
+            require!(!options.mint_receipt, EscrowError::CrowdfundingUnsupported);
+        }
+        // Vesting tracks vested_claimed, recurring tracks periods_remaining;
+        // both paying out of one escrow would double-count what was released
+        require!(
+            !(options.vesting && options.recurring),
+            EscrowError::ConflictingPayoutModes
+        );
+        if options.recurring {
+            require!(
+                options.period_count >= 1 && options.funding_deadline == 0,
+                EscrowError::InvalidRecurrence
+            );
+        }
+        // A referral fee needs someone to pay it to
+        require!(
+            options.referrer.is_some() || options.referral_fee_bps == 0,
+            EscrowError::InvalidFee
+        );
+        require!(
+            options.referral_fee_bps as u32 + ctx.accounts.config.protocol_fee_bps as u32
+                <= MAX_BPS as u32,
+            EscrowError::FeesExceedTotal
+        );
+        require!(
+            !options.release_on_goal || options.funding_deadline != 0,
+            EscrowError::NotCrowdfunded
+        );
+        require!(options.arbiter_window >= 0, EscrowError::InvalidTimeout);
+        if options.expiry != 0 {
+            // Contributions are reclaimed one by one, not settled in bulk
+            require!(options.funding_deadline == 0, EscrowError::CrowdfundingUnsupported);
+        }
+        if options.penalty_rate_bps_per_day != 0 {
+            require!(
+                options.soft_deadline > 0
+                    && options.penalty_rate_bps_per_day <= MAX_BPS
+                    && !options.vesting
+                    && !options.recurring,
+                EscrowError::InvalidPenalty
+            );
+        }
+
+        // A recently cancelled pair has to wait out the config cooldown
+        require_cooldown_elapsed(
+            &ctx.accounts.cooldown,
+            ctx.accounts.config.reinit_cooldown,
+            clock.unix_timestamp,
+        )?;
 
+        // Fields not set below start zeroed even when this address held an
+        // earlier escrow: every close hands the PDA back to the system program
+        // with no data and no lamports, so `init` always allocates afresh
         escrow.buyer = ctx.accounts.buyer.key();
         escrow.seller = ctx.accounts.seller.key();
         
-        // If arbiter is same as buyer, treat it as None (no arbiter)
+        // If arbiter is same as buyer, no arbiter was chosen: fall back to the
+        // config's default arbiter (if any, and not a party to this escrow).
+        // An explicit arbiter always wins.
         escrow.arbiter = if ctx.accounts.arbiter.key() == ctx.accounts.buyer.key() {
-            None
+            ctx.accounts
+                .config
+                .default_arbiter
+                .filter(|a| *a != escrow.buyer && *a != escrow.seller)
         } else {
             Some(ctx.accounts.arbiter.key())
         };
+        validate_roles(&escrow.buyer, &escrow.seller, escrow.arbiter)?;
+        
+        // Arbiters need skin in the game before they can be assigned
+        if let Some(arbiter) = escrow.arbiter {
+            require_arbiter_stake(
+                &ctx.accounts.config,
+                ctx.accounts.arbiter_stake.as_ref(),
+                &arbiter,
+            )?;
+        }
+
+        // Add the escrow to the arbiter's caseload when its index is passed in.
+        // Only with the arbiter's signature, or anyone could fill its slots.
+        if let Some(index) = ctx.accounts.arbiter_index.as_mut() {
+            let arbiter = escrow.arbiter.ok_or(EscrowError::NoArbiter)?;
+            require_keys_eq!(index.arbiter, arbiter, EscrowError::ArbiterIndexMismatch);
+            require!(
+                ctx.accounts.arbiter.is_signer && ctx.accounts.arbiter.key() == arbiter,
+                EscrowError::ArbiterConsentRequired
+            );
+            if !index.track(escrow.key())? {
+                msg!("Arbiter index full, escrow not listed ({} untracked)", index.untracked);
+            }
+        }
         
         escrow.amount = amount;
         escrow.created_at = clock.unix_timestamp;
         escrow.timeout_period = timeout_period;
+        escrow.start_time = options.start_time;
+        escrow.arbiter_fee_bps = 0;
+        escrow.vesting = options.vesting;
+        escrow.vested_claimed = 0;
+        escrow.requires_terms = options.requires_terms;
+        escrow.terms_bound = false;
+        // Opaque bytes, no instruction writes the label after this
+        escrow.label = options.label;
+        escrow.funding_deadline = options.funding_deadline;
+        escrow.funded_amount = 0;
+        escrow.recurring = options.recurring;
+        // A one-off escrow is a single period
+        escrow.periods_remaining = if options.recurring { options.period_count } else { 1 };
+        escrow.buyer_operator = None;
+        escrow.funded_at = 0;
+        escrow.funded_slot = 0;
+        escrow.last_activity = clock.unix_timestamp;
+        escrow.refund_policy = options.refund_policy;
+        escrow.authorization_nonce = 0;
+        escrow.dispute_opened_at = 0;
+        escrow.soft_deadline = options.soft_deadline;
+        escrow.penalty_rate_bps_per_day = options.penalty_rate_bps_per_day;
+        escrow.dispute_state = DisputeState::None;
+        escrow.referrer = options.referrer;
+        escrow.referral_fee_bps = options.referral_fee_bps;
+        escrow.release_on_goal = options.release_on_goal;
+        escrow.arbiter_window = options.arbiter_window;
+        escrow.mint_receipt = options.mint_receipt;
+        escrow.receipt_mint = None;
+        escrow.seller_autoclaim_disabled = options.seller_autoclaim_disabled;
+        escrow.depends_on = options.depends_on;
+        escrow.requires_arbiter = options.requires_arbiter;
+        escrow.dispute_fee_payer = None;
+        escrow.dispute_fee = 0;
+        escrow.expiry = options.expiry;
+        escrow.expiry_default = options.expiry_default;
+        escrow.release_voucher = None;
+        escrow.category = EscrowCategory::try_from(options.category)?;
         escrow.state = EscrowState::Created;
         escrow.bump = ctx.bumps.escrow;
 
+        // Pre-flight: the escrow's rent was already taken from the buyer by
+        // init, what's left has to cover every period plus the funding fee
+        if options.check_buyer_balance {
+            let required = escrow
+                .locked_amount()?
+                .checked_add(FUNDING_FEE_ALLOWANCE)
+                .ok_or(EscrowError::ArithmeticOverflow)?;
+            require!(
+                ctx.accounts.buyer.lamports() >= required,
+                EscrowError::InsufficientBuyerBalance
+            );
+        }
+
         msg!("Escrow initialized: {} lamports, timeout: {} seconds", amount, timeout_period);
 
+        emit!(EscrowInitialized {
+            escrow: escrow.key(),
+            buyer: escrow.buyer,
+            seller: escrow.seller,
+            amount,
+            category: escrow.category,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::Initialized, clock.unix_timestamp);
+
+        Ok(())
+    }
+
+    /// Define reusable escrow parameters that escrows can be created from
+    pub fn create_template(
+        ctx: Context<CreateTemplate>,
+        template_id: u64,
+        timeout_period: i64,
+        arbiter: Option<Pubkey>,
+        arbiter_fee_bps: u16,
+    ) -> Result<()> {
+        require!(timeout_period > 0, EscrowError::InvalidTimeout);
+        require!(timeout_period >= MIN_TIMEOUT_PERIOD, EscrowError::TimeoutTooShort);
+        require!(arbiter_fee_bps <= MAX_BPS, EscrowError::InvalidFee);
+        // A fee only makes sense if there's an arbiter to earn it
+        require!(
+            arbiter.is_some() || arbiter_fee_bps == 0,
+            EscrowError::InvalidFee
+        );
+
+        let template = &mut ctx.accounts.template;
+        template.creator = ctx.accounts.creator.key();
+        template.template_id = template_id;
+        template.timeout_period = timeout_period;
+        template.arbiter = arbiter;
+        template.arbiter_fee_bps = arbiter_fee_bps;
+        template.bump = ctx.bumps.template;
+
+        msg!(
+            "Template {} created: timeout {} seconds, arbiter fee {} bps",
+            template_id,
+            timeout_period,
+            arbiter_fee_bps
+        );
+
+        Ok(())
+    }
+
+    /// Create an escrow using a template's timeout, arbiter and fee
+    pub fn initialize_from_template(ctx: Context<InitializeFromTemplate>, amount: u64) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let template = &ctx.accounts.template;
+        let clock = Clock::get()?;
+
+        require!(!ctx.accounts.config.winddown, EscrowError::NewEscrowsDisabled);
+        validate_amount(amount)?;
+        // Templates from before the floor existed may carry a shorter timeout
+        require!(
+            template.timeout_period >= MIN_TIMEOUT_PERIOD,
+            EscrowError::TimeoutTooShort
+        );
+
+        require_cooldown_elapsed(
+            &ctx.accounts.cooldown,
+            ctx.accounts.config.reinit_cooldown,
+            clock.unix_timestamp,
+        )?;
+
+        escrow.buyer = ctx.accounts.buyer.key();
+        escrow.seller = ctx.accounts.seller.key();
+
+        // Same sentinel rule as initialize_escrow: the buyer can't arbitrate
+        escrow.arbiter = template.arbiter.filter(|a| *a != escrow.buyer);
+        validate_roles(&escrow.buyer, &escrow.seller, escrow.arbiter)?;
+
+        // A template can't name an arbiter initialize_escrow would refuse
+        if let Some(arbiter) = escrow.arbiter {
+            require_arbiter_stake(
+                &ctx.accounts.config,
+                ctx.accounts.arbiter_stake.as_ref(),
+                &arbiter,
+            )?;
+        }
+
+        escrow.amount = amount;
+        escrow.created_at = clock.unix_timestamp;
+        escrow.timeout_period = template.timeout_period;
+        escrow.start_time = 0;
+        escrow.arbiter_fee_bps = if escrow.arbiter.is_some() {
+            template.arbiter_fee_bps
+        } else {
+            0
+        };
+        // The template's fee was fixed before the current cap, check it again
+        require_arbiter_fee_allowed(escrow, &ctx.accounts.config, escrow.arbiter_fee_bps)?;
+        escrow.periods_remaining = 1;
+        escrow.last_activity = clock.unix_timestamp;
+        escrow.refund_policy = RefundPolicy::Any;
+        // Templates don't carry a category
+        escrow.category = EscrowCategory::Other;
+        escrow.state = EscrowState::Created;
+        escrow.bump = ctx.bumps.escrow;
+
+        msg!(
+            "Escrow initialized from template {}: {} lamports, timeout: {} seconds",
+            template.template_id,
+            amount,
+            escrow.timeout_period
+        );
+
+        emit!(EscrowInitialized {
+            escrow: escrow.key(),
+            buyer: escrow.buyer,
+            seller: escrow.seller,
+            amount,
+            category: escrow.category,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::Initialized, clock.unix_timestamp);
+
+        Ok(())
+    }
+
+    /// Buyer and seller both sign off on a hash of the deal terms.
+    /// Once bound, the terms can't be replaced with a different hash.
+    pub fn bind_terms(ctx: Context<BindTerms>, terms_hash: [u8; 32]) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        require!(
+            (escrow.state == EscrowState::Created || escrow.state == EscrowState::Funded)
+                && !escrow.decision_pending(),
+            EscrowError::InvalidState
+        );
+        require!(
+            !escrow.terms_bound || escrow.terms_hash == terms_hash,
+            EscrowError::TermsAlreadyBound
+        );
+
+        escrow.terms_hash = terms_hash;
+        escrow.terms_bound = true;
+
+        msg!("Terms bound by buyer and seller");
+
+        emit!(TermsBound {
+            escrow: escrow.key(),
+            buyer: escrow.buyer,
+            seller: escrow.seller,
+            terms_hash,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::TermsBound, event_time());
+
+        Ok(())
+    }
+
+    /// Renegotiate the arbiter fee before any money is committed. The arbiter
+    /// can do this alone, buyer and seller only together.
+    pub fn update_arbiter_fee(ctx: Context<UpdateArbiterFee>, new_fee_bps: u16) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        require!(
+            escrow.state == EscrowState::Created,
+            EscrowError::EscrowAlreadyFunded
+        );
+
+        let caller = ctx.accounts.caller.key();
+        let counterparty = ctx.accounts.counterparty.as_ref().map(|c| c.key());
+        let parties_agree = (caller == escrow.buyer && counterparty == Some(escrow.seller))
+            || (caller == escrow.seller && counterparty == Some(escrow.buyer));
+        let is_authorized = escrow.arbiter == Some(caller) || parties_agree;
+
+        require_authorized(is_authorized, "update_arbiter_fee", &caller, &escrow.key())?;
+
+        require!(
+            escrow.arbiter.is_some() || new_fee_bps == 0,
+            EscrowError::InvalidFee
+        );
+        require_arbiter_fee_allowed(escrow, &ctx.accounts.config, new_fee_bps)?;
+        // Contributors reclaim exactly what they put in, there's no room for a fee
+        require!(
+            !escrow.is_crowdfunded() || new_fee_bps == 0,
+            EscrowError::CrowdfundingUnsupported
+        );
+
+        let old_fee_bps = escrow.arbiter_fee_bps;
+        escrow.arbiter_fee_bps = new_fee_bps;
+
+        msg!("Arbiter fee updated: {} -> {} bps", old_fee_bps, new_fee_bps);
+
+        emit!(ArbiterFeeUpdated {
+            escrow: escrow.key(),
+            old_fee_bps,
+            new_fee_bps,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::ArbiterFeeUpdated, event_time());
+
+        Ok(())
+    }
+
+    /// Recovery for a compromised arbiter: buyer and seller together hand the
+    /// role to the config's trusted default arbiter. Unlike a free-form
+    /// replacement this can only ever point at that one key.
+    pub fn fallback_to_default_arbiter(ctx: Context<FallbackToDefaultArbiter>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        require!(
+            (escrow.state == EscrowState::Created || escrow.state == EscrowState::Funded)
+                && !escrow.decision_pending(),
+            EscrowError::InvalidState
+        );
+
+        let default_arbiter = ctx
+            .accounts
+            .config
+            .default_arbiter
+            .ok_or(EscrowError::NoDefaultArbiter)?;
+
+        validate_roles(&escrow.buyer, &escrow.seller, Some(default_arbiter))?;
+
+        let old_arbiter = escrow.arbiter;
+        escrow.arbiter = Some(default_arbiter);
+
+        msg!("Arbiter reassigned to config default: {}", default_arbiter);
+
+        emit!(ArbiterReassigned {
+            escrow: escrow.key(),
+            old_arbiter,
+            new_arbiter: default_arbiter,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::ArbiterReplaced, event_time());
+
+        Ok(())
+    }
+
+    /// Buyer or seller flags a funded escrow as disputed, starting the clock
+    /// on the arbiter's response time
+    pub fn open_dispute(ctx: Context<OpenDispute>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let caller = ctx.accounts.caller.key();
+
+        require_authorized(
+            caller == escrow.buyer || caller == escrow.seller,
+            "open_dispute",
+            &caller,
+            &escrow.key(),
+        )?;
+        require!(escrow.state == EscrowState::Funded, EscrowError::InvalidState);
+        require!(escrow.arbiter.is_some(), EscrowError::NoArbiter);
+
+        escrow.transition_dispute(DisputeState::Open)?;
+        let now = Clock::get()?.unix_timestamp;
+        escrow.dispute_opened_at = now;
+
+        // The opener stakes the dispute fee, whoever wins gets it back
+        let fee = ctx.accounts.config.dispute_fee;
+        if fee > 0 {
+            transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.caller.to_account_info(),
+                        to: escrow.to_account_info(),
+                    },
+                ),
+                fee,
+            )?;
+        }
+        escrow.dispute_fee_payer = Some(caller);
+        escrow.dispute_fee = fee;
+
+        msg!("Dispute opened by {}", caller);
+
+        emit!(DisputeOpened {
+            escrow: escrow.key(),
+            opened_by: caller,
+            fee,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::DisputeOpened, now);
+
+        Ok(())
+    }
+
+    /// The current arbiter steps down unilaterally. From then on only the
+    /// parties (or the timeout) can settle the escrow.
+    pub fn renounce_arbiter(ctx: Context<RenounceArbiter>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let caller = ctx.accounts.arbiter.key();
+
+        require_authorized(
+            escrow.arbiter == Some(caller),
+            "renounce_arbiter",
+            &caller,
+            &escrow.key(),
+        )?;
+
+        // A recorded decision still needs this arbiter to finalize or be appealed
+        require!(
+            (escrow.state == EscrowState::Created || escrow.state == EscrowState::Funded)
+                && !escrow.decision_pending(),
+            EscrowError::InvalidState
+        );
+
+        escrow.arbiter = None;
+        escrow.arbiter_fee_bps = 0;
+
+        msg!("Arbiter {} renounced the role", caller);
+
+        emit!(ArbiterRenounced {
+            escrow: escrow.key(),
+            arbiter: caller,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::ArbiterRenounced, event_time());
+
+        Ok(())
+    }
+
+    /// Buyer delegates funding and release to an operator key, e.g. a backend
+    /// wallet. Passing the buyer's own key removes the operator.
+    pub fn set_operator(ctx: Context<SetOperator>, operator: Pubkey) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        require!(
+            (escrow.state == EscrowState::Created || escrow.state == EscrowState::Funded)
+                && !escrow.decision_pending(),
+            EscrowError::InvalidState
+        );
+
+        escrow.buyer_operator = Some(operator).filter(|o| *o != escrow.buyer);
+
+        match escrow.buyer_operator {
+            Some(operator) => msg!("Buyer operator set: {}", operator),
+            None => msg!("Buyer operator removed"),
+        }
+
+        escrow.record_event(EscrowAction::OperatorSet, event_time());
+
+        Ok(())
+    }
+
+    /// Buyer or seller signals the escrow is still in use
+    pub fn ping_escrow(ctx: Context<PingEscrow>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let caller = ctx.accounts.caller.key();
+
+        require_authorized(
+            caller == escrow.buyer || caller == escrow.seller,
+            "ping",
+            &caller,
+            &escrow.key(),
+        )?;
+        require!(
+            (escrow.state == EscrowState::Created || escrow.state == EscrowState::Funded)
+                && !escrow.decision_pending(),
+            EscrowError::InvalidState
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        escrow.last_activity = now;
+
+        emit!(EscrowPinged {
+            escrow: escrow.key(),
+            caller,
+            last_activity: escrow.last_activity,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::Pinged, now);
+
         Ok(())
     }
 
     /// Fund the escrow by transferring SOL from buyer to escrow PDA
     pub fn fund_escrow(ctx: Context<FundEscrow>) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        // Retry-safe: a retry after a fund that already landed is a no-op
+        if escrow.state == EscrowState::Funded {
+            msg!("Escrow already funded, nothing to do");
+            return Ok(());
+        }
 
+        // Also completes an escrow that was partly paid through installments
         require!(
-            escrow.state == EscrowState::Created,
+            matches!(escrow.state, EscrowState::Created | EscrowState::PartiallyFunded),
             EscrowError::InvalidState
         );
+        require_fundable(escrow, clock.unix_timestamp)?;
+
+        // Program<System> already validates this, but re-check right before the CPI
+        require_keys_eq!(
+            ctx.accounts.system_program.key(),
+            anchor_lang::system_program::ID,
+            anchor_lang::error::ErrorCode::InvalidProgramId
+        );
 
         // Transfer SOL from buyer to escrow PDA
         let cpi_context = CpiContext::new(
             ctx.accounts.system_program.to_account_info(),
             Transfer {
                 from: ctx.accounts.buyer.to_account_info(),
-                to: ctx.accounts.escrow.to_account_info(),
+                to: escrow.to_account_info(),
             },
         );
 
-        transfer(cpi_context, escrow.amount)?;
+        // Recurring escrows are pre-funded for every period up front. Init
+        // already rejects a zero amount, but never mark an empty escrow Funded.
+        let total = escrow.locked_amount()?;
+        require!(total > 0, EscrowError::InvalidAmount);
 
-        escrow.state = EscrowState::Funded;
+        // Only top up what's missing, lamports may already sit above rent and
+        // tranches may already have been paid out. An unclaimed arbiter fee
+        // sitting in the escrow isn't the buyer's money, so it doesn't count.
+        let needed = total
+            .saturating_sub(escrow.vested_claimed)
+            .checked_add(escrow.accrued_arbiter_fee)
+            .and_then(|needed| needed.checked_add(escrow.dispute_fee))
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        let shortfall = needed.saturating_sub(payable_balance(&escrow.to_account_info())?);
+        if shortfall > 0 {
+            transfer(cpi_context, shortfall)?;
+        }
+
+        escrow.funded_amount = total;
+        escrow.funded_at = clock.unix_timestamp;
+        escrow.funded_slot = clock.slot;
+        escrow.transition_to(EscrowState::Funded)?;
+        ctx.accounts.global_stats.record_funded(total)?;
+
+        msg!("Escrow funded with {} lamports", total);
 
-        msg!("Escrow funded with {} lamports", escrow.amount);
+        escrow.record_event(EscrowAction::Funded, clock.unix_timestamp);
 
         Ok(())
     }
 
-    // Release funds to the seller
-    // Buyer can do this anytime, seller only after timeout, arbiter anytime
-    pub fn release_to_seller(ctx: Context<ReleaseToSeller>) -> Result<()> {
+    /// Mint the buyer the receipt token of a funded escrow created with
+    /// `mint_receipt`. The mint is a PDA of the escrow and its authority is
+    /// dropped after minting, fixing the supply at one.
+    pub fn mint_receipt(ctx: Context<MintReceipt>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        require!(escrow.mint_receipt, EscrowError::ReceiptNotEnabled);
+        require!(escrow.receipt_mint.is_none(), EscrowError::ReceiptAlreadyMinted);
+        escrow.require_funded()?;
+
+        let (buyer, seller) = (escrow.buyer, escrow.seller);
+        let seeds: &[&[u8]] = &[b"escrow", buyer.as_ref(), seller.as_ref(), &[escrow.bump]];
+        let signer_seeds = &[seeds];
+
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    to: ctx.accounts.buyer_receipt.to_account_info(),
+                    authority: escrow.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            1,
+        )?;
+        token::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::SetAuthority {
+                    current_authority: escrow.to_account_info(),
+                    account_or_mint: ctx.accounts.receipt_mint.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        let mint = ctx.accounts.receipt_mint.key();
+        escrow.receipt_mint = Some(mint);
+
+        msg!("Receipt {} minted to buyer", mint);
+
+        emit!(ReceiptMinted {
+            escrow: escrow.key(),
+            mint,
+            buyer,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::ReceiptMinted, event_time());
+
+        Ok(())
+    }
+
+    /// Buyer pays part of the escrow now and the rest later. The escrow stays
+    /// PartiallyFunded, and can't be released, until installments reach the
+    /// full amount.
+    pub fn fund_installment(ctx: Context<FundEscrow>, amount: u64) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow;
         let clock = Clock::get()?;
 
+        validate_amount(amount)?;
         require!(
-            escrow.state == EscrowState::Funded,
-            EscrowError::EscrowNotFunded
+            matches!(escrow.state, EscrowState::Created | EscrowState::PartiallyFunded),
+            EscrowError::InvalidState
         );
+        require_fundable(escrow, clock.unix_timestamp)?;
+
+        let target = escrow.locked_amount()?;
+        let funded_so_far = escrow
+            .funded_amount
+            .checked_add(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        require!(funded_so_far <= target, EscrowError::InstallmentExceedsTarget);
+
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: escrow.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        escrow.funded_amount = funded_so_far;
+
+        if funded_so_far == target {
+            escrow.funded_at = clock.unix_timestamp;
+            escrow.funded_slot = clock.slot;
+            escrow.transition_to(EscrowState::Funded)?;
+            ctx.accounts.global_stats.record_funded(target)?;
+        } else if escrow.state == EscrowState::Created {
+            escrow.transition_to(EscrowState::PartiallyFunded)?;
+        }
+
+        msg!("Installment of {} lamports, {} of {} funded", amount, funded_so_far, target);
 
+        escrow.record_event(EscrowAction::InstallmentPaid, clock.unix_timestamp);
+
+        Ok(())
+    }
+
+    /// Unwind an installment escrow the buyer stopped paying into: the
+    /// installments paid so far, less tranches the seller already claimed, go
+    /// back to the buyer and the escrow ends Refunded. The seller can give up
+    /// at any time, the buyer once the timeout has passed.
+    pub fn refund_installments(ctx: Context<RefundInstallments>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let now = Clock::get()?.unix_timestamp;
         let caller = ctx.accounts.caller.key();
-        let time_elapsed = clock.unix_timestamp - escrow.created_at;
 
-        // Who can release:
-        // - Buyer: always
-        // - Arbiter: always (if one exists)
-        // - Seller: only after the timeout period
-        let is_authorized = caller == escrow.buyer
-            || escrow.arbiter.map_or(false, |a| caller == a)
-            || (caller == escrow.seller && time_elapsed >= escrow.timeout_period);
+        require!(escrow.state == EscrowState::PartiallyFunded, EscrowError::InvalidState);
+
+        let timed_out = now - escrow.created_at >= escrow.timeout_period;
+        let is_authorized = caller == escrow.seller || (caller == escrow.buyer && timed_out);
+        require_authorized(is_authorized, "refund", &caller, &escrow.key())?;
 
-        require!(is_authorized, EscrowError::UnauthorizedOperation);
+        let transfer_amount = escrow
+            .funded_amount
+            .checked_sub(escrow.vested_claimed)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        move_lamports(&escrow.to_account_info(), &ctx.accounts.buyer, transfer_amount)?;
+        require_usable_recipient(&ctx.accounts.buyer)?;
 
-        // Figure out how much we can transfer (need to keep rent in the account)
-        let escrow_balance = ctx.accounts.escrow.to_account_info().lamports();
-        let rent = Rent::get()?.minimum_balance(ctx.accounts.escrow.to_account_info().data_len());
-        let transfer_amount = escrow_balance.saturating_sub(rent);
+        escrow.transition_to(EscrowState::Refunded)?;
 
-        // Send it to the seller
-        **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? -= transfer_amount;
-        **ctx.accounts.seller.to_account_info().try_borrow_mut_lamports()? += transfer_amount;
+        emit!(InstallmentsRefunded {
+            escrow: escrow.key(),
+            buyer: escrow.buyer,
+            amount: transfer_amount,
+            refunded_by: caller,
+            label: escrow.label,
+        });
 
-        escrow.state = EscrowState::Released;
+        msg!("Installments refunded: {} lamports to buyer", transfer_amount);
 
-        msg!("Escrow released: {} lamports to seller", transfer_amount);
+        escrow.record_event(EscrowAction::InstallmentsRefunded, now);
 
         Ok(())
     }
 
-    /// Refund funds to buyer (callable by seller, arbiter, or buyer for mutual agreement)
-    pub fn refund_to_buyer(ctx: Context<RefundToBuyer>) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
+    /// Fund several of the signer's escrows in one transaction.
+    /// `remaining_accounts` holds the (writable) escrows, and `amounts[i]`
+    /// must equal what escrow i locks, so the buyer never funds a surprise.
+    pub fn fund_escrows_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, FundEscrowsBatch<'info>>,
+        amounts: Vec<u64>,
+    ) -> Result<()> {
+        let accounts = ctx.remaining_accounts;
+        let buyer = &ctx.accounts.buyer;
+        let clock = Clock::get()?;
 
         require!(
-            escrow.state == EscrowState::Funded,
-            EscrowError::EscrowNotFunded
+            !accounts.is_empty()
+                && accounts.len() <= MAX_BATCH_FUND
+                && accounts.len() == amounts.len(),
+            EscrowError::InvalidBatch
         );
 
-        let caller = ctx.accounts.caller.key();
+        for (escrow_info, amount) in accounts.iter().zip(amounts) {
+            require_keys_eq!(*escrow_info.owner, crate::ID, EscrowError::InvalidAccountOwner);
+            let mut escrow = Escrow::try_deserialize(&mut &escrow_info.try_borrow_data()?[..])?;
 
-        // Authorization logic:
-        // 1. Seller agreeing to refund
-        // 2. Arbiter deciding to refund
-        // 3. Buyer (but requires seller's cooperation in practice)
-        let is_authorized = caller == escrow.seller
-            || escrow.arbiter.map_or(false, |a| caller == a)
-            || caller == escrow.buyer;
+            // Same gates as fund_escrow
+            require!(
+                escrow.is_buyer_or_operator(&buyer.key()),
+                EscrowError::NotEscrowBuyer
+            );
+            require!(
+                escrow.state == EscrowState::Created,
+                EscrowError::InvalidState
+            );
+            require_fundable(&escrow, clock.unix_timestamp)?;
 
-        require!(is_authorized, EscrowError::UnauthorizedOperation);
+            validate_amount(amount)?;
+            let total = escrow.locked_amount()?;
+            require!(amount == total, EscrowError::InvalidAmount);
 
-        // Calculate transfer amount (escrow balance minus rent)
-        let escrow_balance = ctx.accounts.escrow.to_account_info().lamports();
-        let rent = Rent::get()?.minimum_balance(ctx.accounts.escrow.to_account_info().data_len());
-        let transfer_amount = escrow_balance.saturating_sub(rent);
+            transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: buyer.to_account_info(),
+                        to: escrow_info.clone(),
+                    },
+                ),
+                total,
+            )?;
 
-        // Transfer funds from escrow PDA to buyer
-        **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? -= transfer_amount;
-        **ctx.accounts.buyer.to_account_info().try_borrow_mut_lamports()? += transfer_amount;
+            escrow.funded_amount = total;
+            escrow.funded_at = clock.unix_timestamp;
+            escrow.funded_slot = clock.slot;
+            escrow.transition_to(EscrowState::Funded)?;
+            ctx.accounts.global_stats.record_funded(total)?;
 
-        escrow.state = EscrowState::Refunded;
+            escrow.record_event(EscrowAction::Funded, clock.unix_timestamp);
 
-        msg!("Escrow refunded: {} lamports to buyer", transfer_amount);
+            escrow.try_serialize(&mut &mut escrow_info.try_borrow_mut_data()?[..])?;
+        }
+
+        msg!("Funded {} escrows", accounts.len());
 
         Ok(())
     }
 
-    /// Cancel an unfunded escrow
-    pub fn cancel_escrow(ctx: Context<CancelEscrow>) -> Result<()> {
-        let escrow = &ctx.accounts.escrow;
+    /// Add lamports to a crowdfunded escrow. It becomes Funded once the
+    /// contributions reach the escrow amount.
+    pub fn contribute(ctx: Context<Contribute>, amount: u64) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
 
         require!(
             escrow.state == EscrowState::Created,
-            EscrowError::EscrowAlreadyFunded
+            EscrowError::InvalidState
+        );
+        require!(escrow.is_crowdfunded(), EscrowError::NotCrowdfunded);
+        require!(
+            clock.unix_timestamp < escrow.funding_deadline,
+            EscrowError::FundingDeadlinePassed
         );
 
-        let caller = ctx.accounts.caller.key();
-        let is_authorized = caller == escrow.buyer || caller == escrow.seller;
+        // Same gates as fund_escrow
+        require!(
+            clock.unix_timestamp >= escrow.start_time,
+            EscrowError::NotYetActive
+        );
+        require!(
+            !escrow.requires_terms || escrow.terms_bound,
+            EscrowError::TermsNotBound
+        );
+        require!(
+            !escrow.requires_arbiter || escrow.effective_arbiter().is_some(),
+            EscrowError::ArbiterRequired
+        );
 
-        require!(is_authorized, EscrowError::UnauthorizedOperation);
+        // No overshooting the target, the last contributor tops it up exactly
+        let remaining = escrow
+            .amount
+            .checked_sub(escrow.funded_amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        validate_amount(amount)?;
+        require!(amount <= remaining, EscrowError::InvalidAmount);
 
-        // Escrow account will be closed, rent returned to buyer
-        msg!("Escrow cancelled");
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.contributor.to_account_info(),
+                to: escrow.to_account_info(),
+            },
+        );
+
+        transfer(cpi_context, amount)?;
+
+        let contribution = &mut ctx.accounts.contribution;
+        contribution.escrow = escrow.key();
+        contribution.contributor = ctx.accounts.contributor.key();
+        contribution.amount = contribution
+            .amount
+            .checked_add(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        contribution.bump = ctx.bumps.contribution;
+
+        escrow.funded_amount = escrow
+            .funded_amount
+            .checked_add(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        msg!(
+            "Contribution of {} lamports, {}/{} funded",
+            amount,
+            escrow.funded_amount,
+            escrow.amount
+        );
+
+        if escrow.funded_amount == escrow.amount {
+            escrow.funded_at = clock.unix_timestamp;
+            escrow.funded_slot = clock.slot;
+            escrow.transition_to(EscrowState::Funded)?;
+            ctx.accounts.global_stats.record_funded(escrow.amount)?;
+
+            msg!("Crowdfunding target reached, escrow funded");
+        }
+
+        escrow.record_event(EscrowAction::Contributed, clock.unix_timestamp);
 
         Ok(())
     }
-}
 
-// ========== Context Structs ==========
+    /// Return a contributor's share when the target wasn't met by the
+    /// deadline, or after a crowdfunded escrow was refunded
+    pub fn reclaim_contribution(ctx: Context<ReclaimContribution>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
 
-#[derive(Accounts)]
-#[instruction(amount: u64, timeout_period: i64)]
-pub struct InitializeEscrow<'info> {
-    #[account(
-        init,
-        payer = buyer,
-        space = 8 + Escrow::LEN,
-        seeds = [b"escrow", buyer.key().as_ref(), seller.key().as_ref()],
-        bump
-    )]
-    pub escrow: Account<'info, Escrow>,
-    
-    #[account(mut)]
-    pub buyer: Signer<'info>,
-    
-    /// CHECK: Seller doesn't need to sign for initialization
-    pub seller: AccountInfo<'info>,
-    
-    /// CHECK: Optional arbiter, can be buyer's key if not used
-    pub arbiter: AccountInfo<'info>,
-    
-    pub system_program: Program<'info, System>,
-}
+        require!(escrow.is_crowdfunded(), EscrowError::NotCrowdfunded);
 
-#[derive(Accounts)]
-pub struct FundEscrow<'info> {
+        match escrow.state {
+            EscrowState::Created => require!(
+                clock.unix_timestamp >= escrow.funding_deadline,
+                EscrowError::TimeoutNotReached
+            ),
+            EscrowState::Refunded => {}
+            _ => return err!(EscrowError::InvalidState),
+        }
+
+        let share = ctx.accounts.contribution.amount;
+
+        move_lamports(
+            &escrow.to_account_info(),
+            &ctx.accounts.contributor.to_account_info(),
+            share,
+        )?;
+
+        escrow.funded_amount = escrow
+            .funded_amount
+            .checked_sub(share)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        // Contribution account is closed, its rent goes back to the contributor
+        msg!("Contribution of {} lamports reclaimed", share);
+
+        escrow.record_event(EscrowAction::ContributionReclaimed, clock.unix_timestamp);
+
+        Ok(())
+    }
+
+    // Release funds to the seller
+    // Buyer can do this anytime, seller only after timeout, arbiter anytime
+    pub fn release_to_seller(ctx: Context<ReleaseToSeller>, memo: Option<String>) -> Result<()> {
+        let caller = ctx.accounts.caller.key();
+        release_escrow(ctx.accounts, &caller, memo)
+    }
+
+    /// Buyer confirms the goods arrived, releasing to the seller. Pays out
+    /// exactly like a buyer's release_to_seller, but is recorded as a
+    /// confirmation so analytics can tell it apart from other releases.
+    pub fn confirm_delivery(ctx: Context<ReleaseToSeller>) -> Result<()> {
+        let caller = ctx.accounts.caller.key();
+        let escrow_key = ctx.accounts.escrow.key();
+
+        require_authorized(
+            caller == ctx.accounts.escrow.buyer,
+            "confirm_delivery",
+            &caller,
+            &escrow_key,
+        )?;
+        require!(
+            ctx.accounts.escrow.state == EscrowState::Funded,
+            EscrowError::EscrowNotFunded
+        );
+
+        release_escrow(ctx.accounts, &caller, None)?;
+
+        msg!("Delivery confirmed by buyer");
+
+        emit!(DeliveryConfirmed {
+            escrow: escrow_key,
+            buyer: caller,
+            seller: ctx.accounts.escrow.seller,
+            label: ctx.accounts.escrow.label,
+        });
+
+        Ok(())
+    }
+
+    /// Gasless release: a relayer submits the buyer's off-chain signature over
+    /// `RELEASE_AUTH_PREFIX || escrow || nonce (u64 LE)`, verified by an
+    /// Ed25519 program instruction placed right before this one. The relayer
+    /// pays the fees; `nonce` must equal the escrow's next authorization nonce.
+    pub fn release_with_authorization(
+        ctx: Context<ReleaseWithAuthorization>,
+        nonce: u64,
+        memo: Option<String>,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.release.escrow;
+
+        require!(
+            nonce == escrow.authorization_nonce,
+            EscrowError::StaleAuthorizationNonce
+        );
+
+        let mut message = RELEASE_AUTH_PREFIX.to_vec();
+        message.extend_from_slice(escrow.key().as_ref());
+        message.extend_from_slice(&nonce.to_le_bytes());
+        require_ed25519_signature(&ctx.accounts.instructions, &escrow.buyer, &message)?;
+
+        // Burn the nonce before releasing so the signature can't be replayed
+        escrow.authorization_nonce = nonce
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        let buyer = escrow.buyer;
+        release_escrow(&mut ctx.accounts.release, &buyer, memo)
+    }
+
+    /// release_to_seller for a seller that is another program's account (e.g.
+    /// its PDA). Lamports can be credited to any account, this variant just
+    /// pins down that the payout lands in an account owned by `expected_owner`
+    /// so composing programs get a deterministic destination.
+    pub fn release_to_pda(
+        ctx: Context<ReleaseToSeller>,
+        expected_owner: Pubkey,
+        memo: Option<String>,
+    ) -> Result<()> {
+        let seller = &ctx.accounts.seller;
+
+        require!(
+            *seller.owner == expected_owner
+                && expected_owner != anchor_lang::system_program::ID
+                && !seller.executable,
+            EscrowError::DestinationNotProgramOwned
+        );
+
+        release_to_seller(ctx, memo)
+    }
+
+    /// Buyer or arbiter hash-locks a release: whoever later shows the seller
+    /// the preimage of `voucher_hash` off-chain lets it redeem_voucher.
+    pub fn issue_voucher(ctx: Context<IssueVoucher>, voucher_hash: [u8; 32]) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let issuer = ctx.accounts.issuer.key();
+
+        require_authorized(
+            issuer == escrow.buyer || escrow.arbiter == Some(issuer),
+            "issue_voucher",
+            &issuer,
+            &escrow.key(),
+        )?;
+        require!(
+            (escrow.state == EscrowState::Created || escrow.state == EscrowState::Funded)
+                && !escrow.decision_pending(),
+            EscrowError::InvalidState
+        );
+        require!(escrow.release_voucher.is_none(), EscrowError::VoucherAlreadyIssued);
+
+        escrow.release_voucher = Some(voucher_hash);
+
+        msg!("Release voucher issued by {}", issuer);
+
+        emit!(VoucherIssued {
+            escrow: escrow.key(),
+            issuer,
+            voucher_hash,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::VoucherIssued, event_time());
+
+        Ok(())
+    }
+
+    /// Seller redeems the release voucher with its preimage. The voucher is
+    /// the buyer's or arbiter's consent given in advance, so the release is
+    /// authorized as the buyer's; the seller calling earns no arbiter fee.
+    pub fn redeem_voucher(ctx: Context<RedeemVoucher>, preimage: Vec<u8>) -> Result<()> {
+        let release = &mut ctx.accounts.release;
+        let caller = release.caller.key();
+        let escrow = &mut release.escrow;
+
+        require_authorized(
+            caller == escrow.seller,
+            "redeem_voucher",
+            &caller,
+            &escrow.key(),
+        )?;
+        require!(
+            preimage.len() <= MAX_VOUCHER_PREIMAGE_LEN,
+            EscrowError::VoucherPreimageTooLong
+        );
+
+        let voucher_hash = escrow.release_voucher.ok_or(EscrowError::NoVoucherIssued)?;
+        require!(
+            hash(&preimage).to_bytes() == voucher_hash,
+            EscrowError::VoucherMismatch
+        );
+
+        // Single use: clear it before releasing so it can't pay out twice
+        escrow.release_voucher = None;
+
+        emit!(VoucherRedeemed {
+            escrow: escrow.key(),
+            seller: caller,
+            voucher_hash,
+            label: escrow.label,
+        });
+
+        let buyer = escrow.buyer;
+        release_escrow(release, &buyer, None)
+    }
+
+    /// Read-only dry run of release_to_seller for `caller`. Runs the same
+    /// checks but only reports the outcome as return data.
+    pub fn simulate_release(ctx: Context<SimulateRelease>) -> Result<ReleaseEligibility> {
+        let escrow = &ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        let check = check_release(
+            escrow,
+            &ctx.accounts.config,
+            &ctx.accounts.caller.key(),
+            ctx.accounts.cosigner.as_ref().map(|c| c.key()),
+            &clock,
+        );
+
+        let eligibility = match check {
+            Ok(fee_bps) => ReleaseEligibility {
+                allowed: true,
+                reason: 0,
+                releasable: release_split(escrow, &ctx.accounts.config, fee_bps, clock.unix_timestamp)?.seller,
+            },
+            Err(error) => ReleaseEligibility {
+                allowed: false,
+                reason: reason_code(&error),
+                releasable: 0,
+            },
+        };
+
+        Ok(eligibility)
+    }
+
+    /// Read-only: whether cancel_escrow would succeed for `caller` right now,
+    /// using the same checks
+    pub fn is_cancellable(ctx: Context<IsCancellable>) -> Result<bool> {
+        Ok(check_cancel(
+            &ctx.accounts.escrow,
+            &ctx.accounts.config,
+            &ctx.accounts.caller.key(),
+        )
+        .is_ok())
+    }
+
+    /// Resolve a joint purchase; anyone may crank it. If contributions met the
+    /// target the whole amount goes to the seller. If the deadline passed
+    /// short of it, the escrow becomes Refunded and every contributor
+    /// reclaims exactly their own share through reclaim_contribution.
+    pub fn settle_goal(ctx: Context<SettleGoal>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        require!(
+            escrow.is_crowdfunded() && escrow.release_on_goal,
+            EscrowError::NotCrowdfunded
+        );
+
+        // A dispute over a met goal, and its fee, are the arbiter's to settle
+        require!(escrow.dispute_state == DisputeState::None, EscrowError::InvalidState);
+
+        let succeeded = match escrow.state {
+            EscrowState::Funded => true,
+            EscrowState::Created => {
+                require!(
+                    clock.unix_timestamp >= escrow.funding_deadline,
+                    EscrowError::TimeoutNotReached
+                );
+                false
+            }
+            _ => return err!(EscrowError::InvalidState),
+        };
+
+        let amount = if succeeded {
+            escrow.require_funded()?;
+            let seller = &ctx.accounts.seller;
+            let (paid, _) = pay_out_balance(&escrow.to_account_info(), seller, seller, 0, &ctx.accounts.config)?;
+
+            let rent = Rent::get()?.minimum_balance(seller.data_len());
+            require!(seller.lamports() >= rent, EscrowError::SellerBelowRent);
+
+            ctx.accounts.global_stats.record_settled(escrow.locked_amount()?)?;
+            escrow.transition_to(EscrowState::Released)?;
+            paid
+        } else {
+            escrow.transition_to(EscrowState::Refunded)?;
+            0
+        };
+
+        msg!(
+            "Joint purchase {}: {} lamports to seller",
+            if succeeded { "succeeded" } else { "failed" },
+            amount
+        );
+
+        emit!(GoalSettled {
+            escrow: escrow.key(),
+            succeeded,
+            amount,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::GoalSettled, clock.unix_timestamp);
+
+        Ok(())
+    }
+
+    /// Permissionless settlement of an abandoned escrow: once its expiry has
+    /// passed with the escrow still funded and undisputed, everything it
+    /// holds goes to the party named by `expiry_default`. A ping postpones
+    /// the expiry, see Escrow::expires_at.
+    pub fn settle_expired(ctx: Context<SettleExpired>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        escrow.require_funded()?;
+        require!(
+            escrow.expiry != 0 && clock.unix_timestamp >= escrow.expires_at(),
+            EscrowError::NotExpired
+        );
+        // Someone acted: a dispute is the arbiter's to settle
+        require!(escrow.dispute_state == DisputeState::None, EscrowError::InvalidState);
+        require_balance_covers_held(escrow)?;
+
+        let (recipient, next_state) = match escrow.expiry_default {
+            ExpiryDefault::Buyer => {
+                // A receipt holder has the buyer's refund rights, and has to
+                // claim them through refund_to_buyer
+                require!(escrow.receipt_mint.is_none(), EscrowError::ReceiptRequired);
+                settle_vested_to_seller(escrow, &ctx.accounts.seller)?;
+                (&ctx.accounts.buyer, EscrowState::Refunded)
+            }
+            ExpiryDefault::Seller => (&ctx.accounts.seller, EscrowState::Released),
+        };
+
+        let (amount, _) = pay_out_balance(&escrow.to_account_info(), recipient, recipient, 0, &ctx.accounts.config)?;
+        require_usable_recipient(recipient)?;
+
+        ctx.accounts.global_stats.record_settled(escrow.locked_amount()?)?;
+        escrow.transition_to(next_state)?;
+
+        msg!("Expired escrow settled: {} lamports to {}", amount, recipient.key());
+
+        emit!(ExpirySettled {
+            escrow: escrow.key(),
+            settled_to: escrow.expiry_default,
+            recipient: recipient.key(),
+            amount,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::ExpirySettled, clock.unix_timestamp);
+
+        Ok(())
+    }
+
+    /// Refund funds to buyer (callable by seller, arbiter, or buyer for mutual agreement)
+    pub fn refund_to_buyer(ctx: Context<RefundToBuyer>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        escrow.require_funded()?;
+
+        let caller = ctx.accounts.caller.key();
+
+        // Once a receipt exists its holder stands in for the buyer, and the
+        // refund goes to the holder instead
+        let buyer_side = match escrow.receipt_mint {
+            Some(mint) => {
+                let receipt = ctx.accounts.receipt.as_ref().ok_or(EscrowError::ReceiptRequired)?;
+                require!(
+                    receipt.mint == mint
+                        && receipt.amount == 1
+                        && receipt.owner == ctx.accounts.buyer.key(),
+                    EscrowError::ReceiptRequired
+                );
+                receipt.owner
+            }
+            None => escrow.buyer,
+        };
+
+        // Who may refund is the escrow's refund policy; by default the
+        // seller, the arbiter or the buyer
+        let counterparty = ctx.accounts.counterparty.as_ref().map(|c| c.key());
+        let is_authorized = escrow.may_refund(&caller, counterparty, &buyer_side);
+
+        require_authorized(is_authorized, "refund", &caller, &escrow.key())?;
+
+        // High-value escrows additionally need the config cosigner
+        require_cosigner(
+            &ctx.accounts.config,
+            escrow.amount,
+            ctx.accounts.cosigner.as_ref().map(|c| c.key()),
+        )?;
+
+        // The arbiter earns its fee when it's the one resolving; the common
+        // escrow without an arbiter skips the check, there's no fee to earn
+        let fee_bps = match escrow.effective_arbiter() {
+            Some(arbiter) if arbiter == caller => escrow.arbiter_fee_bps,
+            _ => 0,
+        };
+
+        // A refund favours the buyer side, so a pending dispute fee goes to
+        // it before the balance is split and the arbiter fee never applies to it
+        settle_dispute_fee(escrow, &ctx.accounts.buyer)?;
+
+        let transfer_amount = if escrow.is_crowdfunded() {
+            // The funds stay put, each contributor reclaims their own share
+            0
+        } else {
+            // A vesting seller keeps what has vested, only the remainder is refunded
+            settle_vested_to_seller(escrow, &ctx.accounts.seller)?;
+
+            // Transfer everything above rent back to the buyer
+            let (transfer_amount, fee) = pay_out_balance(
+                &escrow.to_account_info(),
+                &ctx.accounts.buyer,
+                &ctx.accounts.caller,
+                fee_bps,
+                &ctx.accounts.config,
+            )?;
+            ctx.accounts.global_stats.record_fee(fee)?;
+
+            // A closed/drained buyer wallet may not be able to hold the refund
+            require_usable_recipient(&ctx.accounts.buyer)?;
+
+            transfer_amount
+        };
+
+        escrow.transition_to(EscrowState::Refunded)?;
+        ctx.accounts.global_stats.record_settled(escrow.locked_amount()?)?;
+
+        msg!("Escrow refunded: {} lamports to buyer", transfer_amount);
+
+        escrow.record_event(EscrowAction::Refunded, event_time());
+
+        Ok(())
+    }
+
+    /// Return any lamports above the agreed amount to the buyer, keeping the escrow funded
+    pub fn withdraw_excess(ctx: Context<WithdrawExcess>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        escrow.require_funded()?;
+
+        // Anything beyond rent + what the escrow holds for the parties, the
+        // arbiter and a pending dispute is surplus
+        let excess = payable_balance(&escrow.to_account_info())?.saturating_sub(escrow.held_amount()?);
+
+        require!(excess > 0, EscrowError::NoExcessFunds);
+
+        move_lamports(&escrow.to_account_info(), &ctx.accounts.buyer.to_account_info(), excess)?;
+
+        msg!("Excess withdrawn: {} lamports to buyer", excess);
+
+        escrow.record_event(EscrowAction::ExcessWithdrawn, event_time());
+
+        Ok(())
+    }
+
+    /// Anyone can cover the rent shortfall of an escrow whose data grew (e.g.
+    /// after a realloc), so it stays rent-exempt on top of the funds it holds.
+    /// `amount` must be exactly the shortfall.
+    pub fn top_up_rent(ctx: Context<TopUpRent>, amount: u64) -> Result<()> {
+        validate_amount(amount)?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        let info = escrow.to_account_info();
+
+        let required = Rent::get()?
+            .minimum_balance(info.data_len())
+            .checked_add(escrow.held_amount()?)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        let shortfall = required.saturating_sub(info.lamports());
+
+        require!(shortfall > 0, EscrowError::NoRentShortfall);
+        require!(amount == shortfall, EscrowError::InvalidAmount);
+
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: info,
+                },
+            ),
+            shortfall,
+        )?;
+
+        msg!("Rent topped up by {} lamports", shortfall);
+
+        escrow.record_event(EscrowAction::RentToppedUp, event_time());
+
+        Ok(())
+    }
+
+    /// Seller withdraws whatever has vested so far on a vesting escrow.
+    /// Funds vest linearly from creation over the timeout period.
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        escrow.require_funded()?;
+        require!(escrow.vesting, EscrowError::NotVesting);
+
+        let claimable = escrow.vested_claimable(clock.unix_timestamp)?;
+
+        require!(claimable > 0, EscrowError::NothingToClaim);
+
+        // Partial claims must clear the dust floor; the final one may not
+        let claimed_after = escrow
+            .vested_claimed
+            .checked_add(claimable)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        if claimed_after < escrow.amount {
+            require!(
+                claimable >= ctx.accounts.config.min_partial_release,
+                EscrowError::BelowMinimumRelease
+            );
+        }
+
+        move_lamports(&escrow.to_account_info(), &ctx.accounts.seller, claimable)?;
+        escrow.vested_claimed = claimed_after;
+
+        // Fully vested and claimed: the escrow is done
+        if escrow.vested_claimed == escrow.amount {
+            escrow.transition_to(EscrowState::Released)?;
+            ctx.accounts.global_stats.record_settled(escrow.locked_amount()?)?;
+        }
+
+        msg!(
+            "Vested claim: {} lamports to seller ({} of {} claimed)",
+            claimable,
+            escrow.vested_claimed,
+            escrow.amount
+        );
+
+        escrow.record_event(EscrowAction::VestedClaimed, clock.unix_timestamp);
+
+        Ok(())
+    }
+
+    /// Read-only: lamports claim_vested would pay the seller right now, 0
+    /// while the escrow isn't in a state claim_vested accepts
+    pub fn vested_claimable(ctx: Context<DescribeEscrow>) -> Result<u64> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.vesting, EscrowError::NotVesting);
+
+        if escrow.require_funded().is_err() {
+            return Ok(0);
+        }
+        escrow.vested_claimable(Clock::get()?.unix_timestamp)
+    }
+
+    /// Buyer and seller agree to pay the escrow out in tranches, before any
+    /// funding. The buyer then funds with fund_installment and the seller
+    /// claims each tranche with claim_tranche once it's funded and unlocked.
+    /// Setting a schedule again before funding replaces the old one.
+    pub fn set_tranche_schedule(
+        ctx: Context<SetTrancheSchedule>,
+        tranches: Vec<TrancheTerms>,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        require!(
+            escrow.state == EscrowState::Created && escrow.funded_amount == 0,
+            EscrowError::InvalidState
+        );
+        // Vesting and recurring escrows pay out on their own schedules
+        require!(
+            !escrow.vesting && !escrow.recurring,
+            EscrowError::ConflictingPayoutModes
+        );
+        require!(!escrow.is_crowdfunded(), EscrowError::InvalidState);
+        require!(
+            (1..=MAX_TRANCHES).contains(&tranches.len())
+                && tranches.iter().all(|tranche| tranche.amount > 0)
+                && tranches.windows(2).all(|pair| pair[0].unlock_time <= pair[1].unlock_time),
+            EscrowError::InvalidTrancheSchedule
+        );
+        let total = tranches
+            .iter()
+            .try_fold(0u64, |total, tranche| total.checked_add(tranche.amount))
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        require!(total == escrow.amount, EscrowError::TrancheSumMismatch);
+
+        let schedule = &mut ctx.accounts.tranche_schedule;
+        schedule.escrow = escrow.key();
+        schedule.escrow_created_at = escrow.created_at;
+        schedule.tranches = tranches
+            .iter()
+            .map(|terms| Tranche {
+                amount: terms.amount,
+                unlock_time: terms.unlock_time,
+                released: false,
+            })
+            .collect();
+        schedule.bump = ctx.bumps.tranche_schedule;
+
+        msg!("Tranche schedule set: {} tranches", schedule.tranches.len());
+
+        escrow.record_event(EscrowAction::TrancheScheduleSet, event_time());
+
+        Ok(())
+    }
+
+    /// Seller claims tranche `index` once the buyer's installments cover it
+    /// and its unlock time has passed. Claiming the last outstanding tranche
+    /// of a fully funded escrow settles it.
+    pub fn claim_tranche(ctx: Context<ClaimTranche>, index: u8) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let schedule = &mut ctx.accounts.tranche_schedule;
+        let now = Clock::get()?.unix_timestamp;
+
+        match escrow.state {
+            EscrowState::Funded => escrow.require_funded()?,
+            EscrowState::PartiallyFunded => {}
+            _ => return err!(EscrowError::EscrowNotFunded),
+        }
+        // A dispute freezes the schedule until the arbiter decides
+        require!(escrow.dispute_state == DisputeState::None, EscrowError::InvalidState);
+        require!(schedule.is_current(escrow), EscrowError::StaleTrancheSchedule);
+
+        let index_usize = index as usize;
+        require!(index_usize < schedule.tranches.len(), EscrowError::InvalidTrancheIndex);
+        let tranche = schedule.tranches[index_usize];
+        require!(!tranche.released, EscrowError::TrancheAlreadyClaimed);
+        require!(now >= tranche.unlock_time, EscrowError::TrancheLocked);
+        require!(
+            escrow.funded_amount >= schedule.funded_through(index_usize)?,
+            EscrowError::TrancheNotFunded
+        );
+
+        move_lamports(&escrow.to_account_info(), &ctx.accounts.seller, tranche.amount)?;
+        schedule.tranches[index_usize].released = true;
+        escrow.vested_claimed = escrow
+            .vested_claimed
+            .checked_add(tranche.amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        // Every tranche paid out: the escrow is done
+        if escrow.vested_claimed == escrow.amount {
+            escrow.transition_to(EscrowState::Released)?;
+            ctx.accounts.global_stats.record_settled(escrow.locked_amount()?)?;
+        }
+
+        emit!(TrancheClaimed {
+            escrow: escrow.key(),
+            seller: ctx.accounts.seller.key(),
+            index,
+            amount: tranche.amount,
+            total_claimed: escrow.vested_claimed,
+            label: escrow.label,
+        });
+
+        msg!(
+            "Tranche {} claimed: {} lamports to seller ({} of {} claimed)",
+            index,
+            tranche.amount,
+            escrow.vested_claimed,
+            escrow.amount
+        );
+
+        escrow.record_event(EscrowAction::TrancheClaimed, now);
+
+        Ok(())
+    }
+
+    /// Arbiter settles the escrow, recording its decision and a hash of the
+    /// off-chain reasoning on the account before moving the funds
+    pub fn arbiter_resolve(
+        ctx: Context<ArbiterResolve>,
+        decision: ArbiterDecision,
+        reason_hash: [u8; 32],
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        escrow.require_funded()?;
+
+        let caller = ctx.accounts.arbiter.key();
+        let is_authorized = escrow.arbiter == Some(caller);
+
+        require_authorized(is_authorized, "arbiter_resolve", &caller, &escrow.key())?;
+
+        // High-value escrows additionally need the config cosigner
+        require_cosigner(
+            &ctx.accounts.config,
+            escrow.amount,
+            ctx.accounts.cosigner.as_ref().map(|c| c.key()),
+        )?;
+
+        if let Some(stats) = ctx.accounts.arbiter_stats.as_mut() {
+            record_resolution_latency(stats, escrow)?;
+        }
+        // Decided, so it leaves the arbiter's caseload
+        if let Some(index) = ctx.accounts.arbiter_index.as_mut() {
+            index.untrack(&escrow.key());
+        }
+
+        resolve_escrow(
+            escrow,
+            &ctx.accounts.buyer,
+            &ctx.accounts.seller,
+            &mut ctx.accounts.global_stats,
+            &ctx.accounts.config,
+            decision,
+            reason_hash,
+        )
+    }
+
+    /// Gasless arbitration: anyone submits the arbiter's off-chain signature
+    /// over `DECISION_AUTH_PREFIX || escrow || decision (u8) || reason_hash ||
+    /// nonce (u64 LE)`, verified by an Ed25519 program instruction placed right
+    /// before this one. Settles exactly like arbiter_resolve; `nonce` must
+    /// equal the escrow's next authorization nonce.
+    pub fn settle_with_arbiter_sig(
+        ctx: Context<SettleWithArbiterSig>,
+        decision: ArbiterDecision,
+        reason_hash: [u8; 32],
+        nonce: u64,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        escrow.require_funded()?;
+
+        // The buyer sentinel is no arbiter, its signature can't decide anything
+        let arbiter = escrow.effective_arbiter().ok_or(EscrowError::NoArbiter)?;
+        require!(
+            nonce == escrow.authorization_nonce,
+            EscrowError::StaleAuthorizationNonce
+        );
+
+        let mut message = DECISION_AUTH_PREFIX.to_vec();
+        message.extend_from_slice(escrow.key().as_ref());
+        message.push(decision as u8);
+        message.extend_from_slice(&reason_hash);
+        message.extend_from_slice(&nonce.to_le_bytes());
+        require_ed25519_signature(&ctx.accounts.instructions, &arbiter, &message)?;
+
+        require_cosigner(
+            &ctx.accounts.config,
+            escrow.amount,
+            ctx.accounts.cosigner.as_ref().map(|c| c.key()),
+        )?;
+
+        // Burn the nonce before settling so the signature can't be replayed
+        escrow.authorization_nonce = nonce
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        if let Some(stats) = ctx.accounts.arbiter_stats.as_mut() {
+            record_resolution_latency(stats, escrow)?;
+        }
+        // Decided, so it leaves the arbiter's caseload
+        if let Some(index) = ctx.accounts.arbiter_index.as_mut() {
+            index.untrack(&escrow.key());
+        }
+
+        resolve_escrow(
+            escrow,
+            &ctx.accounts.buyer,
+            &ctx.accounts.seller,
+            &mut ctx.accounts.global_stats,
+            &ctx.accounts.config,
+            decision,
+            reason_hash,
+        )
+    }
+
+    /// The party a pending arbiter decision goes against escalates it to the
+    /// config's appeal arbiter, freezing the payout until that arbiter decides
+    pub fn appeal_decision(ctx: Context<AppealDecision>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        require!(
+            escrow.state == EscrowState::Funded && escrow.dispute_state == DisputeState::Resolved,
+            EscrowError::InvalidState
+        );
+
+        let decision = escrow.arbiter_decision.ok_or(EscrowError::InvalidState)?;
+        let losing_party = match decision {
+            ArbiterDecision::ReleaseToSeller => escrow.buyer,
+            ArbiterDecision::RefundToBuyer => escrow.seller,
+        };
+
+        let caller = ctx.accounts.appellant.key();
+        require_authorized(caller == losing_party, "appeal", &caller, &escrow.key())?;
+
+        require!(
+            clock.unix_timestamp < escrow.appeal_deadline,
+            EscrowError::AppealWindowClosed
+        );
+
+        escrow.transition_dispute(DisputeState::UnderAppeal)?;
+
+        msg!("Arbiter decision ({:?}) appealed", decision);
+
+        emit!(AppealOpened {
+            escrow: escrow.key(),
+            appellant: caller,
+            decision,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::Appealed, clock.unix_timestamp);
+
+        Ok(())
+    }
+
+    /// Execute an unappealed arbiter decision once the appeal window has
+    /// closed. The outcome is fixed, so anyone can crank it.
+    pub fn finalize_resolution(ctx: Context<FinalizeResolution>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        require!(
+            escrow.state == EscrowState::Funded && escrow.dispute_state == DisputeState::Resolved,
+            EscrowError::InvalidState
+        );
+        require!(
+            clock.unix_timestamp >= escrow.appeal_deadline,
+            EscrowError::AppealWindowOpen
+        );
+
+        let decision = escrow.arbiter_decision.ok_or(EscrowError::InvalidState)?;
+
+        let (transfer_amount, arbiter_fee) = execute_decision(
+            escrow,
+            decision,
+            &ctx.accounts.buyer,
+            &ctx.accounts.seller,
+            escrow.arbiter_fee_bps,
+            &ctx.accounts.config,
+        )?;
+        ctx.accounts.global_stats.record_settled(escrow.locked_amount()?)?;
+        ctx.accounts.global_stats.record_fee(arbiter_fee)?;
+
+        msg!(
+            "Arbiter decision ({:?}) finalized: {} lamports paid, {} lamports fee accrued",
+            decision,
+            transfer_amount,
+            arbiter_fee
+        );
+
+        escrow.record_event(EscrowAction::ResolutionFinalized, clock.unix_timestamp);
+
+        Ok(())
+    }
+
+    /// The config's appeal arbiter settles an appealed escrow. Its decision is
+    /// final and executes immediately; no arbiter fee is paid on appeal.
+    pub fn resolve_appeal(
+        ctx: Context<ResolveAppeal>,
+        decision: ArbiterDecision,
+        reason_hash: [u8; 32],
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        require!(
+            escrow.state == EscrowState::Funded && escrow.dispute_state == DisputeState::UnderAppeal,
+            EscrowError::InvalidState
+        );
+
+        let caller = ctx.accounts.appeal_arbiter.key();
+        let is_authorized = ctx.accounts.config.appeal_arbiter == Some(caller);
+
+        require_authorized(is_authorized, "resolve_appeal", &caller, &escrow.key())?;
+
+        require_cosigner(
+            &ctx.accounts.config,
+            escrow.amount,
+            ctx.accounts.cosigner.as_ref().map(|c| c.key()),
+        )?;
+
+        let original_decision = escrow.arbiter_decision.ok_or(EscrowError::InvalidState)?;
+        escrow.arbiter_decision = Some(decision);
+        escrow.reason_hash = reason_hash;
+        escrow.transition_dispute(DisputeState::Resolved)?;
+
+        let (transfer_amount, _) = execute_decision(
+            escrow,
+            decision,
+            &ctx.accounts.buyer,
+            &ctx.accounts.seller,
+            0,
+            &ctx.accounts.config,
+        )?;
+        ctx.accounts.global_stats.record_settled(escrow.locked_amount()?)?;
+
+        msg!(
+            "Appeal resolved ({:?} -> {:?}): {} lamports paid",
+            original_decision,
+            decision,
+            transfer_amount
+        );
+
+        emit!(AppealResolved {
+            escrow: escrow.key(),
+            appeal_arbiter: caller,
+            original_decision,
+            decision,
+            reason_hash,
+            amount: transfer_amount,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::AppealResolved, event_time());
+
+        Ok(())
+    }
+
+    /// Arbiter withdraws the fee accrued when it resolved the escrow, so fee
+    /// transfers can be batched apart from the resolution itself
+    pub fn claim_arbiter_fee(ctx: Context<ClaimArbiterFee>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        let caller = ctx.accounts.arbiter.key();
+        let is_authorized = escrow.arbiter == Some(caller);
+
+        require_authorized(is_authorized, "claim_arbiter_fee", &caller, &escrow.key())?;
+
+        let amount = escrow.accrued_arbiter_fee;
+        require!(amount > 0, EscrowError::NoFeeAccrued);
+
+        escrow.accrued_arbiter_fee = 0;
+        move_lamports(&escrow.to_account_info(), &ctx.accounts.arbiter, amount)?;
+
+        msg!("Arbiter claimed {} lamports in fees", amount);
+
+        emit!(ArbiterFeeClaimed {
+            escrow: escrow.key(),
+            arbiter: caller,
+            amount,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::ArbiterFeeClaimed, event_time());
+
+        Ok(())
+    }
+
+    /// Return the escrowed funds to the buyer but keep the agreement alive in
+    /// Created so it can be funded again. Needs both buyer and seller to sign.
+    pub fn soft_refund(ctx: Context<SoftRefund>) -> Result<()> {
+        let transfer_amount = refund_to_created(ctx.accounts)?;
+        let escrow = &mut ctx.accounts.escrow;
+
+        msg!("Escrow soft-refunded: {} lamports to buyer, awaiting re-funding", transfer_amount);
+
+        emit!(EscrowSoftRefunded {
+            escrow: escrow.key(),
+            buyer: escrow.buyer,
+            amount: transfer_amount,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::SoftRefunded, event_time());
+
+        Ok(())
+    }
+
+    /// soft_refund for changing the deal: the buyer gets the funds back and
+    /// the escrow starts over in Created as if it had never been funded, with
+    /// its terms unbound so amend_terms, update_arbiter_fee and bind_terms can
+    /// set new ones. Needs both buyer and seller, and no dispute.
+    pub fn reopen_for_renegotiation(ctx: Context<SoftRefund>) -> Result<()> {
+        let transfer_amount = refund_to_created(ctx.accounts)?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        let now = Clock::get()?.unix_timestamp;
+
+        escrow.created_at = now;
+        escrow.funded_at = 0;
+        escrow.funded_slot = 0;
+        escrow.last_activity = now;
+        escrow.terms_bound = false;
+        escrow.terms_hash = [0; 32];
+        // A voucher was consent to release under the old terms
+        escrow.release_voucher = None;
+
+        msg!("Escrow reopened: {} lamports to buyer, terms open for renegotiation", transfer_amount);
+
+        emit!(EscrowReopened {
+            escrow: escrow.key(),
+            buyer: escrow.buyer,
+            amount: transfer_amount,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::Reopened, now);
+
+        Ok(())
+    }
+
+    /// Buyer and seller change the amount and timeout of an escrow that holds
+    /// no funds yet, e.g. after reopen_for_renegotiation. Bound terms have to
+    /// be reopened first, they were agreed for the old amount.
+    pub fn amend_terms(ctx: Context<BindTerms>, amount: u64, timeout_period: i64) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        require!(
+            escrow.state == EscrowState::Created,
+            EscrowError::EscrowAlreadyFunded
+        );
+        // Contributions were made against the old target
+        require!(!escrow.is_crowdfunded(), EscrowError::CrowdfundingUnsupported);
+        require!(!escrow.terms_bound, EscrowError::TermsAlreadyBound);
+
+        validate_amount(amount)?;
+        require!(timeout_period > 0, EscrowError::InvalidTimeout);
+        require!(timeout_period >= MIN_TIMEOUT_PERIOD, EscrowError::TimeoutTooShort);
+
+        // The schedule was checked against the clock at creation, which may
+        // be long past by the time the parties renegotiate
+        let now = Clock::get()?.unix_timestamp;
+        let expires_at = escrow.expires_at();
+        validate_schedule(escrow.start_time, escrow.funding_deadline, expires_at, now)?;
+        if expires_at != 0 {
+            // The new timeout has to run out before the expiry settles the escrow
+            require!(
+                now.max(escrow.start_time).saturating_add(timeout_period) <= expires_at,
+                EscrowError::InvalidTimeout
+            );
+        }
+        if escrow.penalty_rate_bps_per_day != 0 {
+            // A lapsed soft deadline would penalise the new terms from the start
+            require!(escrow.soft_deadline > now, EscrowError::InvalidPenalty);
+        }
+
+        let (old_amount, old_timeout_period) = (escrow.amount, escrow.timeout_period);
+        escrow.amount = amount;
+        escrow.timeout_period = timeout_period;
+        // Every remaining period of a recurring escrow is locked at this amount
+        escrow.locked_amount()?;
+
+        msg!(
+            "Terms amended: {} -> {} lamports, {} -> {} seconds timeout",
+            old_amount,
+            amount,
+            old_timeout_period,
+            timeout_period
+        );
+
+        emit!(TermsAmended {
+            escrow: escrow.key(),
+            old_amount,
+            new_amount: amount,
+            old_timeout_period,
+            new_timeout_period: timeout_period,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::TermsAmended, now);
+
+        Ok(())
+    }
+
+    /// Cancel an unfunded escrow
+    pub fn cancel_escrow(ctx: Context<CancelEscrow>, reason_code: u8) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        let clock = Clock::get()?;
+        let reason = CancelReason::try_from(reason_code)?;
+
+        let caller = ctx.accounts.caller.key();
+        check_cancel(escrow, &ctx.accounts.config, &caller)?;
+
+        // Remember when this pair cancelled so re-init can honour the cooldown
+        let cooldown = &mut ctx.accounts.cooldown;
+        cooldown.cancelled_at = clock.unix_timestamp;
+        cooldown.bump = ctx.bumps.cooldown;
+
+        // Escrow account will be closed, rent returned to buyer
+        msg!("Escrow cancelled ({:?})", reason);
+
+        // The account is gone after this, so the reason only lives in the event
+        emit!(EscrowCancelled {
+            escrow: escrow.key(),
+            cancelled_by: caller,
+            reason,
+            label: escrow.label,
+        });
+
+        Ok(())
+    }
+
+    /// Close several settled escrows at once. `remaining_accounts` holds
+    /// (escrow, buyer) pairs; each escrow's rent goes back to its buyer.
+    pub fn close_escrows_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseEscrowsBatch<'info>>,
+    ) -> Result<()> {
+        let accounts = ctx.remaining_accounts;
+
+        // is_multiple_of is newer than the toolchains this still builds on
+        #[allow(clippy::manual_is_multiple_of)]
+        let paired = accounts.len() % 2 == 0;
+        require!(
+            !accounts.is_empty()
+                && paired
+                && accounts.len() / 2 <= MAX_BATCH_CLOSE,
+            EscrowError::InvalidBatch
+        );
+
+        for pair in accounts.chunks(2) {
+            let (escrow_info, buyer_info) = (&pair[0], &pair[1]);
+
+            require_keys_eq!(*escrow_info.owner, crate::ID, EscrowError::InvalidAccountOwner);
+            let escrow = Escrow::try_deserialize(&mut &escrow_info.try_borrow_data()?[..])?;
+
+            require_closable(&escrow)?;
+            require_keys_eq!(buyer_info.key(), escrow.buyer, EscrowError::InvalidRecipient);
+
+            close_program_account(escrow_info, buyer_info)?;
+        }
+
+        msg!("Closed {} settled escrows", accounts.len() / 2);
+
+        Ok(())
+    }
+
+    /// Write a compact summary of a settled escrow into its archive PDA, then
+    /// close the escrow. The buyer pays the archive's rent out of the escrow
+    /// rent it gets back.
+    pub fn archive_escrow(ctx: Context<ArchiveEscrow>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require_closable(escrow)?;
+
+        let archive = &mut ctx.accounts.archive;
+        archive.escrow = escrow.key();
+        archive.buyer = escrow.buyer;
+        archive.seller = escrow.seller;
+        archive.arbiter = escrow.effective_arbiter();
+        archive.amount = escrow.amount;
+        archive.funded_amount = escrow.funded_amount;
+        archive.final_state = escrow.state;
+        archive.arbiter_decision = escrow.arbiter_decision;
+        archive.created_at = escrow.created_at;
+        archive.funded_at = escrow.funded_at;
+        archive.archived_at = Clock::get()?.unix_timestamp;
+        archive.label = escrow.label;
+        archive.last_event_hash = escrow.last_event_hash;
+        archive.bump = ctx.bumps.archive;
+
+        emit!(EscrowArchived {
+            escrow: escrow.key(),
+            archive: archive.key(),
+            final_state: escrow.state,
+            label: escrow.label,
+        });
+
+        Ok(())
+    }
+
+    /// Seller lists an NFT for `price` lamports. The NFT moves into a vault
+    /// owned by the NFT escrow PDA until the swap settles or is called off.
+    pub fn initialize_nft_escrow(ctx: Context<InitializeNftEscrow>, price: u64) -> Result<()> {
+        validate_amount(price)?;
+
+        // Only a single, indivisible token can stand in for an NFT
+        let mint = &ctx.accounts.mint;
+        require!(mint.decimals == 0 && mint.supply == 1, EscrowError::InvalidNft);
+
+        let nft_escrow = &mut ctx.accounts.nft_escrow;
+        nft_escrow.buyer = ctx.accounts.buyer.key();
+        nft_escrow.seller = ctx.accounts.seller.key();
+        validate_roles(&nft_escrow.buyer, &nft_escrow.seller, None)?;
+        nft_escrow.mint = mint.key();
+        nft_escrow.price = price;
+        nft_escrow.state = EscrowState::Created;
+        nft_escrow.bump = ctx.bumps.nft_escrow;
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.seller_token.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.seller.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        msg!("NFT escrow initialized: {} for {} lamports", nft_escrow.mint, price);
+
+        Ok(())
+    }
+
+    /// Buyer deposits the price, after which either party can settle the swap
+    pub fn fund_nft_escrow(ctx: Context<FundNftEscrow>) -> Result<()> {
+        let nft_escrow = &mut ctx.accounts.nft_escrow;
+
+        require!(
+            nft_escrow.state == EscrowState::Created,
+            EscrowError::InvalidState
+        );
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: nft_escrow.to_account_info(),
+            },
+        );
+
+        transfer(cpi_context, nft_escrow.price)?;
+
+        nft_escrow.state = EscrowState::Funded;
+
+        msg!("NFT escrow funded with {} lamports", nft_escrow.price);
+
+        Ok(())
+    }
+
+    /// Atomic swap: NFT to the buyer, price to the seller. The vault and the
+    /// NFT escrow account are closed, their rent goes back to the seller.
+    pub fn settle_nft_escrow(ctx: Context<SettleNftEscrow>) -> Result<()> {
+        let nft_escrow = &ctx.accounts.nft_escrow;
+
+        require!(
+            nft_escrow.state == EscrowState::Funded,
+            EscrowError::EscrowNotFunded
+        );
+
+        let caller = ctx.accounts.caller.key();
+        let is_authorized = caller == nft_escrow.buyer || caller == nft_escrow.seller;
+
+        require_authorized(is_authorized, "settle_nft", &caller, &nft_escrow.key())?;
+
+        release_nft_vault(
+            nft_escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.buyer_token,
+            &ctx.accounts.seller,
+            &ctx.accounts.token_program,
+        )?;
+
+        move_lamports(
+            &nft_escrow.to_account_info(),
+            &ctx.accounts.seller,
+            nft_escrow.price,
+        )?;
+
+        msg!("NFT escrow settled: NFT to buyer, {} lamports to seller", nft_escrow.price);
+
+        Ok(())
+    }
+
+    /// Either party backs out before settlement: the NFT goes back to the
+    /// seller and, if the escrow was funded, the price back to the buyer
+    pub fn cancel_nft_escrow(ctx: Context<CancelNftEscrow>) -> Result<()> {
+        let nft_escrow = &ctx.accounts.nft_escrow;
+
+        let caller = ctx.accounts.caller.key();
+        let is_authorized = caller == nft_escrow.buyer || caller == nft_escrow.seller;
+
+        require_authorized(is_authorized, "cancel_nft", &caller, &nft_escrow.key())?;
+
+        release_nft_vault(
+            nft_escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.seller_token,
+            &ctx.accounts.seller,
+            &ctx.accounts.token_program,
+        )?;
+
+        if nft_escrow.state == EscrowState::Funded {
+            move_lamports(
+                &nft_escrow.to_account_info(),
+                &ctx.accounts.buyer,
+                nft_escrow.price,
+            )?;
+        }
+
+        msg!("NFT escrow cancelled, assets returned");
+
+        Ok(())
+    }
+
+    /// Create the program-wide stats account (one-time setup)
+    pub fn initialize_global_stats(ctx: Context<InitializeGlobalStats>) -> Result<()> {
+        let stats = &mut ctx.accounts.global_stats;
+
+        stats.total_escrowed = 0;
+        stats.active_escrows = 0;
+        stats.total_fees_collected = 0;
+        stats.bump = ctx.bumps.global_stats;
+
+        msg!("Global stats initialized");
+
+        Ok(())
+    }
+
+    /// Create the reputation record for a seller; anyone may pay for it
+    pub fn initialize_seller_reputation(ctx: Context<InitializeSellerReputation>) -> Result<()> {
+        let reputation = &mut ctx.accounts.seller_reputation;
+
+        reputation.seller = ctx.accounts.seller.key();
+        reputation.completed_deals = 0;
+        reputation.total_volume = 0;
+        reputation.bump = ctx.bumps.seller_reputation;
+
+        msg!("Seller reputation initialized for {}", reputation.seller);
+
+        Ok(())
+    }
+
+    /// Create the program config, the signer becomes its authority
+    pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        config.authority = ctx.accounts.authority.key();
+        config.high_value_threshold = 0;
+        config.cosigner = None;
+        config.reinit_cooldown = 0;
+        config.default_arbiter = None;
+        config.max_arbiter_fee_bps = MAX_BPS;
+        config.appeal_arbiter = None;
+        config.appeal_window = 0;
+        config.seller_can_cancel_unfunded = true;
+        config.timeout_from_funding = false;
+        config.winddown = false;
+        config.fee_rounding = FeeRounding::Down;
+        config.max_transfer_per_instruction = u64::MAX;
+        config.min_arbiter_stake = 0;
+        config.protocol_fee_bps = 0;
+        config.dispute_fee = 0;
+        config.fee_discount_tiers = [FeeDiscountTier::default(); MAX_FEE_DISCOUNT_TIERS];
+        config.min_slots_before_release = 0;
+        config.min_partial_release = 0;
+        config.bump = ctx.bumps.config;
+
+        msg!("Config initialized, authority: {}", config.authority);
+
+        Ok(())
+    }
+
+    /// Require `cosigner` on release/refund of escrows above `high_value_threshold`.
+    /// Passing no cosigner turns the policy off.
+    pub fn set_high_value_policy(
+        ctx: Context<UpdateConfig>,
+        high_value_threshold: u64,
+        cosigner: Option<Pubkey>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        config.high_value_threshold = high_value_threshold;
+        config.cosigner = cosigner;
+
+        msg!("High-value policy: threshold {} lamports, cosigner {:?}", high_value_threshold, cosigner);
+
+        Ok(())
+    }
+
+    /// Arbiter assigned to escrows initialized without one (None disables it)
+    pub fn set_default_arbiter(
+        ctx: Context<UpdateConfig>,
+        default_arbiter: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.config.default_arbiter = default_arbiter;
+
+        msg!("Default arbiter set to {:?}", default_arbiter);
+
+        Ok(())
+    }
+
+    /// Highest arbiter fee update_arbiter_fee accepts
+    pub fn set_max_arbiter_fee(ctx: Context<UpdateConfig>, max_arbiter_fee_bps: u16) -> Result<()> {
+        require!(max_arbiter_fee_bps <= MAX_BPS, EscrowError::InvalidFee);
+
+        ctx.accounts.config.max_arbiter_fee_bps = max_arbiter_fee_bps;
+
+        msg!("Arbiter fee cap set to {} bps", max_arbiter_fee_bps);
+
+        Ok(())
+    }
+
+    /// Route arbiter decisions through an appeal window of `appeal_window`
+    /// seconds, escalating to `appeal_arbiter`. No arbiter or a zero window
+    /// turns appeals off.
+    pub fn set_appeal_policy(
+        ctx: Context<UpdateConfig>,
+        appeal_arbiter: Option<Pubkey>,
+        appeal_window: i64,
+    ) -> Result<()> {
+        require!(appeal_window >= 0, EscrowError::InvalidAppealWindow);
+
+        let config = &mut ctx.accounts.config;
+        config.appeal_arbiter = appeal_arbiter;
+        config.appeal_window = appeal_window;
+
+        msg!("Appeal policy: arbiter {:?}, window {} seconds", appeal_arbiter, appeal_window);
+
+        Ok(())
+    }
+
+    /// Whether the seller may cancel an unfunded escrow (the buyer always can)
+    pub fn set_cancel_policy(
+        ctx: Context<UpdateConfig>,
+        seller_can_cancel_unfunded: bool,
+    ) -> Result<()> {
+        ctx.accounts.config.seller_can_cancel_unfunded = seller_can_cancel_unfunded;
+
+        msg!("Seller can cancel unfunded escrows: {}", seller_can_cancel_unfunded);
+
+        Ok(())
+    }
+
+    /// Whether the seller's self-claim timeout counts from funding rather than creation
+    pub fn set_timeout_policy(ctx: Context<UpdateConfig>, timeout_from_funding: bool) -> Result<()> {
+        ctx.accounts.config.timeout_from_funding = timeout_from_funding;
+
+        msg!("Timeout counts from funding: {}", timeout_from_funding);
+
+        Ok(())
+    }
+
+    /// Wind-down stops new escrows while in-flight ones fund and settle as usual
+    pub fn set_winddown(ctx: Context<UpdateConfig>, winddown: bool) -> Result<()> {
+        ctx.accounts.config.winddown = winddown;
+
+        msg!("Wind-down: {}", winddown);
+
+        Ok(())
+    }
+
+    /// Rounding direction for all basis-point fees
+    pub fn set_fee_rounding(ctx: Context<UpdateConfig>, fee_rounding: FeeRounding) -> Result<()> {
+        ctx.accounts.config.fee_rounding = fee_rounding;
+
+        msg!("Fee rounding: {:?}", fee_rounding);
+
+        Ok(())
+    }
+
+    /// Most lamports a single release or refund may move; raise it for large escrows
+    pub fn set_max_transfer(ctx: Context<UpdateConfig>, max_transfer_per_instruction: u64) -> Result<()> {
+        validate_amount(max_transfer_per_instruction)?;
+
+        ctx.accounts.config.max_transfer_per_instruction = max_transfer_per_instruction;
+
+        msg!("Max transfer per instruction: {} lamports", max_transfer_per_instruction);
+
+        Ok(())
+    }
+
+    /// Protocol's cut of each release in basis points, collected in the fee vault
+    pub fn set_protocol_fee(ctx: Context<UpdateConfig>, protocol_fee_bps: u16) -> Result<()> {
+        require!(protocol_fee_bps <= MAX_BPS, EscrowError::InvalidFee);
+
+        ctx.accounts.config.protocol_fee_bps = protocol_fee_bps;
+
+        msg!("Protocol fee: {} bps", protocol_fee_bps);
+
+        Ok(())
+    }
+
+    /// Replace the fast-release discount tiers; an empty list turns
+    /// discounts off
+    pub fn set_fee_discount_tiers(
+        ctx: Context<UpdateConfig>,
+        tiers: Vec<FeeDiscountTier>,
+    ) -> Result<()> {
+        require!(tiers.len() <= MAX_FEE_DISCOUNT_TIERS, EscrowError::InvalidFeeTiers);
+        require!(
+            tiers.iter().all(|tier| tier.max_elapsed > 0),
+            EscrowError::InvalidFeeTiers
+        );
+        require!(
+            tiers.windows(2).all(|pair| pair[0].max_elapsed < pair[1].max_elapsed),
+            EscrowError::InvalidFeeTiers
+        );
+        require!(
+            tiers.iter().all(|tier| tier.discount_bps <= MAX_BPS),
+            EscrowError::InvalidFee
+        );
+
+        let mut stored = [FeeDiscountTier::default(); MAX_FEE_DISCOUNT_TIERS];
+        stored[..tiers.len()].copy_from_slice(&tiers);
+        ctx.accounts.config.fee_discount_tiers = stored;
+
+        msg!("Fee discount tiers: {}", tiers.len());
+
+        Ok(())
+    }
+
+    /// Create the fee vault PDA that releases credit protocol fees to
+    pub fn initialize_fee_vault(ctx: Context<InitializeFeeVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.fee_vault;
+        vault.balance = 0;
+        vault.total_collected = 0;
+        vault.bump = ctx.bumps.fee_vault;
+
+        msg!("Fee vault initialized");
+
+        Ok(())
+    }
+
+    /// Config authority moves `amount` of the collected fees to `treasury`
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+        validate_amount(amount)?;
+
+        let vault = &mut ctx.accounts.fee_vault;
+        require!(amount <= vault.balance, EscrowError::InsufficientFeeBalance);
+
+        vault.balance = vault
+            .balance
+            .checked_sub(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        move_lamports(&vault.to_account_info(), &ctx.accounts.treasury, amount)?;
+
+        msg!("Withdrew {} lamports in fees ({} left)", amount, vault.balance);
+
+        emit!(FeesWithdrawn {
+            treasury: ctx.accounts.treasury.key(),
+            amount,
+            remaining: vault.balance,
+        });
+
+        Ok(())
+    }
+
+    /// Fee a party pays to open a dispute; 0 makes disputes free
+    pub fn set_dispute_fee(ctx: Context<UpdateConfig>, dispute_fee: u64) -> Result<()> {
+        ctx.accounts.config.dispute_fee = dispute_fee;
+
+        msg!("Dispute fee: {} lamports", dispute_fee);
+
+        Ok(())
+    }
+
+    /// Slots the parties must wait after funding before releasing, so funding
+    /// and releasing can't happen atomically in one slot. The arbiter is exempt.
+    pub fn set_min_slots_before_release(
+        ctx: Context<UpdateConfig>,
+        min_slots_before_release: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.min_slots_before_release = min_slots_before_release;
+
+        msg!("Minimum slots before release: {}", min_slots_before_release);
+
+        Ok(())
+    }
+
+    /// Smallest vesting claim that doesn't empty the escrow, to keep dust
+    /// claims out; 0 allows any amount
+    pub fn set_min_partial_release(ctx: Context<UpdateConfig>, min_partial_release: u64) -> Result<()> {
+        ctx.accounts.config.min_partial_release = min_partial_release;
+
+        msg!("Minimum partial release: {} lamports", min_partial_release);
+
+        Ok(())
+    }
+
+    /// Stake an arbiter must hold to be assigned to new escrows; 0 disables it
+    pub fn set_min_arbiter_stake(ctx: Context<UpdateConfig>, min_arbiter_stake: u64) -> Result<()> {
+        ctx.accounts.config.min_arbiter_stake = min_arbiter_stake;
+
+        msg!("Min arbiter stake: {} lamports", min_arbiter_stake);
+
+        Ok(())
+    }
+
+    /// Arbiter locks `amount` lamports in its stake PDA, creating it on the
+    /// first deposit. Stakes add up across calls.
+    pub fn stake_as_arbiter(ctx: Context<StakeAsArbiter>, amount: u64) -> Result<()> {
+        validate_amount(amount)?;
+
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.arbiter.to_account_info(),
+                    to: ctx.accounts.arbiter_stake.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let stake = &mut ctx.accounts.arbiter_stake;
+        stake.arbiter = ctx.accounts.arbiter.key();
+        stake.amount = stake
+            .amount
+            .checked_add(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        stake.bump = ctx.bumps.arbiter_stake;
+
+        msg!("Arbiter staked {} lamports ({} total)", amount, stake.amount);
+
+        emit!(ArbiterStaked {
+            arbiter: stake.arbiter,
+            amount,
+            total: stake.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Config authority takes up to `amount` of a misbehaving arbiter's stake
+    pub fn slash_arbiter(ctx: Context<SlashArbiter>, amount: u64) -> Result<()> {
+        validate_amount(amount)?;
+
+        let stake = &mut ctx.accounts.arbiter_stake;
+        let slashed = amount.min(stake.amount);
+        require!(slashed > 0, EscrowError::InsufficientArbiterStake);
+
+        stake.amount = stake
+            .amount
+            .checked_sub(slashed)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        move_lamports(&stake.to_account_info(), &ctx.accounts.authority, slashed)?;
+
+        msg!("Arbiter slashed {} lamports ({} left)", slashed, stake.amount);
+
+        emit!(ArbiterSlashed {
+            arbiter: stake.arbiter,
+            amount: slashed,
+            remaining: stake.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Seconds a buyer/seller pair must wait after a cancel before re-initializing
+    pub fn set_reinit_cooldown(ctx: Context<UpdateConfig>, reinit_cooldown: i64) -> Result<()> {
+        require!(reinit_cooldown >= 0, EscrowError::InvalidCooldown);
+
+        ctx.accounts.config.reinit_cooldown = reinit_cooldown;
+
+        msg!("Re-init cooldown set to {} seconds", reinit_cooldown);
+
+        Ok(())
+    }
+
+    /// Migration tool: store the canonical bump on an escrow whose saved bump
+    /// is wrong, which locks it out of every seeds-checked instruction. Only
+    /// escrows living at the canonical address are touched. An account at any
+    /// other address keeps its bump, since the canonical one wouldn't derive it.
+    pub fn repair_bump(ctx: Context<RepairBump>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+
+        let (canonical, bump) = Pubkey::find_program_address(
+            &[b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+            ctx.program_id,
+        );
+        require_keys_eq!(escrow.key(), canonical, EscrowError::BumpRepairMismatch);
+
+        if escrow.bump == bump {
+            msg!("Escrow bump {} is already canonical", bump);
+            return Ok(());
+        }
+
+        let old_bump = escrow.bump;
+        escrow.bump = bump;
+
+        emit!(BumpRepaired {
+            escrow: escrow.key(),
+            old_bump,
+            new_bump: bump,
+            label: escrow.label,
+        });
+
+        msg!("Escrow bump repaired: {} -> {}", old_bump, bump);
+
+        escrow.record_event(EscrowAction::BumpRepaired, event_time());
+
+        Ok(())
+    }
+
+    /// Read-only: returns the program-wide totals as return data
+    pub fn get_global_stats(ctx: Context<GetGlobalStats>) -> Result<GlobalStats> {
+        let stats = &ctx.accounts.global_stats;
+
+        msg!(
+            "Global stats: {} lamports escrowed across {} active escrows",
+            stats.total_escrowed,
+            stats.active_escrows
+        );
+
+        Ok((**stats).clone())
+    }
+
+    /// Read-only: returns the whole operator config as return data. The
+    /// account is returned as is, so new config fields show up here too.
+    pub fn get_config(ctx: Context<GetConfig>) -> Result<Config> {
+        let config = &ctx.accounts.config;
+
+        msg!(
+            "Config: authority {}, winddown {}, protocol fee {} bps",
+            config.authority,
+            config.winddown,
+            config.protocol_fee_bps
+        );
+
+        Ok((**config).clone())
+    }
+
+    /// Read-only: returns the total of all fees paid out (arbiter, referral,
+    /// protocol) across all escrows
+    pub fn get_fees_collected(ctx: Context<GetGlobalStats>) -> Result<u64> {
+        let stats = &ctx.accounts.global_stats;
+
+        msg!("Fees collected: {} lamports", stats.total_fees_collected);
+
+        Ok(stats.total_fees_collected)
+    }
+
+    /// Create the resolution record for an arbiter; anyone may pay for it
+    pub fn initialize_arbiter_stats(ctx: Context<InitializeArbiterStats>) -> Result<()> {
+        let stats = &mut ctx.accounts.arbiter_stats;
+
+        stats.arbiter = ctx.accounts.arbiter.key();
+        stats.total_resolved = 0;
+        stats.total_latency = 0;
+        stats.bump = ctx.bumps.arbiter_stats;
+
+        msg!("Arbiter stats initialized for {}", stats.arbiter);
+
+        Ok(())
+    }
+
+    /// Create the caseload index for `arbiter`. Anyone can pay for it.
+    pub fn initialize_arbiter_index(ctx: Context<InitializeArbiterIndex>) -> Result<()> {
+        let index = &mut ctx.accounts.arbiter_index;
+
+        index.arbiter = ctx.accounts.arbiter.key();
+        index.escrows = Vec::new();
+        index.untracked = 0;
+        index.bump = ctx.bumps.arbiter_index;
+
+        msg!("Arbiter index initialized for {}", index.arbiter);
+
+        Ok(())
+    }
+
+    /// Read-only: returns the escrows listed in an arbiter's caseload
+    pub fn get_arbiter_index(ctx: Context<GetArbiterIndex>) -> Result<Vec<Pubkey>> {
+        let index = &ctx.accounts.arbiter_index;
+
+        msg!(
+            "Arbiter index: {} listed, {} untracked",
+            index.escrows.len(),
+            index.untracked
+        );
+
+        Ok(index.escrows.clone())
+    }
+
+    /// Drop listed escrows the arbiter no longer has to decide, passed as
+    /// `remaining_accounts`: closed or settled ones, ones already decided, and
+    /// ones reassigned to another arbiter. Anyone can prune, live cases stay.
+    pub fn prune_arbiter_index<'info>(
+        ctx: Context<'_, '_, '_, 'info, PruneArbiterIndex<'info>>,
+    ) -> Result<()> {
+        let index = &mut ctx.accounts.arbiter_index;
+        let before = index.escrows.len();
+
+        for escrow_info in ctx.remaining_accounts {
+            if !index.escrows.contains(escrow_info.key) {
+                continue;
+            }
+
+            let open_case = *escrow_info.owner == crate::ID
+                && Escrow::try_deserialize(&mut &escrow_info.try_borrow_data()?[..]).is_ok_and(
+                    |escrow| {
+                        escrow.arbiter == Some(index.arbiter)
+                            && escrow.arbiter_decision.is_none()
+                            && matches!(
+                                escrow.state,
+                                EscrowState::Created
+                                    | EscrowState::PartiallyFunded
+                                    | EscrowState::Funded
+                            )
+                    },
+                );
+            if !open_case {
+                index.untrack(escrow_info.key);
+            }
+        }
+
+        msg!("Pruned {} escrows from the arbiter index", before - index.escrows.len());
+
+        Ok(())
+    }
+
+    /// Read-only: returns an arbiter's resolution record
+    pub fn get_arbiter_stats(ctx: Context<GetArbiterStats>) -> Result<ArbiterStats> {
+        let stats = &ctx.accounts.arbiter_stats;
+
+        msg!(
+            "Arbiter stats: {} resolved, {} seconds total latency",
+            stats.total_resolved,
+            stats.total_latency
+        );
+
+        Ok((**stats).clone())
+    }
+
+    /// Read-only: returns a seller's reputation record
+    pub fn get_seller_reputation(ctx: Context<GetSellerReputation>) -> Result<SellerReputation> {
+        let reputation = &ctx.accounts.seller_reputation;
+
+        msg!(
+            "Seller reputation: {} completed deals, {} lamports volume",
+            reputation.completed_deals,
+            reputation.total_volume
+        );
+
+        Ok((**reputation).clone())
+    }
+
+    /// Read-only: returns the escrow PDA and bump for a buyer/seller pair,
+    /// using the same seeds as the account constraints
+    pub fn derive_escrow_address(
+        _ctx: Context<DeriveEscrowAddress>,
+        buyer: Pubkey,
+        seller: Pubkey,
+    ) -> Result<EscrowAddress> {
+        let (address, bump) = Pubkey::find_program_address(
+            &[b"escrow", buyer.as_ref(), seller.as_ref()],
+            &crate::ID,
+        );
+
+        Ok(EscrowAddress { address, bump })
+    }
+
+    /// Read-only: the escrow's condition packed into FLAG_* bits, for cheap
+    /// status checks that don't need the full describe_escrow
+    pub fn escrow_flags(ctx: Context<EscrowFlags>) -> Result<u32> {
+        let escrow = &ctx.accounts.escrow;
+        let now = Clock::get()?.unix_timestamp;
+        let timeout_start = escrow.timeout_start(ctx.accounts.config.timeout_from_funding);
+
+        let flags = [
+            (escrow.state == EscrowState::Funded, FLAG_FUNDED),
+            (
+                escrow.state == EscrowState::Funded && escrow.dispute_state != DisputeState::None,
+                FLAG_DISPUTED,
+            ),
+            (now - timeout_start >= escrow.timeout_period, FLAG_TIMEOUT_ELAPSED),
+            (escrow.effective_arbiter().is_some(), FLAG_HAS_ARBITER),
+            (escrow.vesting, FLAG_VESTING),
+            (escrow.is_crowdfunded(), FLAG_CROWDFUNDED),
+            (escrow.recurring, FLAG_RECURRING),
+            (
+                matches!(
+                    escrow.state,
+                    EscrowState::Released | EscrowState::Refunded | EscrowState::Cancelled
+                ),
+                FLAG_SETTLED,
+            ),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |acc, (_, bit)| acc | bit);
+
+        Ok(flags)
+    }
+
+    /// Read-only: the escrow's arbiter with the buyer sentinel resolved,
+    /// None when nobody but the parties can resolve it
+    pub fn effective_arbiter(ctx: Context<DescribeEscrow>) -> Result<Option<Pubkey>> {
+        Ok(ctx.accounts.escrow.effective_arbiter())
+    }
+
+    /// Read-only: returns a summary of one escrow, including its label
+    pub fn describe_escrow(ctx: Context<DescribeEscrow>) -> Result<EscrowDescription> {
+        let escrow = &ctx.accounts.escrow;
+
+        Ok(EscrowDescription {
+            buyer: escrow.buyer,
+            seller: escrow.seller,
+            arbiter: escrow.effective_arbiter(),
+            amount: escrow.amount,
+            created_at: escrow.created_at,
+            timeout_period: escrow.timeout_period,
+            state: escrow.state,
+            category: escrow.category,
+            label: escrow.label,
+            last_event_hash: escrow.last_event_hash,
+        })
+    }
+}
+
+// ========== Helpers ==========
+
+/// Fails with UnauthorizedOperation, first logging a structured line so
+/// monitoring can flag repeated denied attempts from the RPC logs
+fn require_authorized(
+    is_authorized: bool,
+    action: &str,
+    caller: &Pubkey,
+    escrow: &Pubkey,
+) -> Result<()> {
+    if !is_authorized {
+        msg!("auth denied: action={} caller={} escrow={}", action, caller, escrow);
+        return err!(EscrowError::UnauthorizedOperation);
+    }
+    Ok(())
+}
+
+/// Enforces the config's high-value cosigner policy for an escrow of `amount`
+fn require_cosigner(config: &Config, amount: u64, cosigner: Option<Pubkey>) -> Result<()> {
+    if config.requires_cosigner(amount) {
+        let cosigned = cosigner.is_some() && cosigner == config.cosigner;
+        require!(cosigned, EscrowError::CosignerRequired);
+    }
+    Ok(())
+}
+
+/// Every rule release_to_seller enforces before moving funds, shared with
+/// simulate_release so the dry run can't drift from the real handler.
+/// Returns the arbiter fee (bps) that applies when `caller` releases.
+fn check_release(
+    escrow: &Account<Escrow>,
+    config: &Config,
+    caller: &Pubkey,
+    cosigner: Option<Pubkey>,
+    clock: &Clock,
+) -> Result<u16> {
+    escrow.require_funded()?;
+
+    // Belt and braces: refuse rather than "release" an empty account
+    let available = require_balance_covers_held(escrow)?;
+    require!(available > 0, EscrowError::NothingToTransfer);
+
+    let time_elapsed = clock.unix_timestamp - escrow.timeout_start(config.timeout_from_funding);
+
+    // Who can release:
+    // - Buyer (or its operator): always
+    // - Arbiter: always (if one exists), alone once the timeout has passed
+    // - Seller: only after the timeout period and the arbiter's window, and
+    //   never on escrows that disabled self-claim
+    let seller_may_claim = !escrow.seller_autoclaim_disabled
+        && time_elapsed >= escrow.seller_claim_delay();
+    let is_party = escrow.is_buyer_or_operator(caller)
+        || (*caller == escrow.seller && seller_may_claim);
+
+    // Fast path for the common escrow without an arbiter (or with the buyer
+    // sentinel): only the parties can release and no arbiter fee applies
+    let (is_arbiter, fee_bps) = match escrow.effective_arbiter() {
+        None => {
+            require_authorized(is_party, "release", caller, &escrow.key())?;
+            (false, 0)
+        }
+        Some(arbiter) => {
+            let is_arbiter = arbiter == *caller;
+            require_authorized(is_party || is_arbiter, "release", caller, &escrow.key())?;
+            // The arbiter earns its fee when it's the one resolving
+            (is_arbiter, if is_arbiter { escrow.arbiter_fee_bps } else { 0 })
+        }
+    };
+
+    // No funding and releasing in one atomic slot, except by the arbiter
+    if !is_arbiter {
+        let earliest = escrow
+            .funded_slot
+            .checked_add(config.min_slots_before_release)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        require!(clock.slot >= earliest, EscrowError::TooSoonAfterFunding);
+    }
+
+    // High-value escrows additionally need the config cosigner
+    require_cosigner(config, escrow.amount, cosigner)?;
+
+    Ok(fee_bps)
+}
+
+/// Shared body of arbiter_resolve and settle_with_arbiter_sig, after the
+/// caller has checked the arbiter and the cosigner: records the decision and
+/// pays it out, or holds it for the appeal window when appeals are enabled.
+fn resolve_escrow<'info>(
+    escrow: &mut Account<'info, Escrow>,
+    buyer: &AccountInfo<'info>,
+    seller: &AccountInfo<'info>,
+    global_stats: &mut Account<'info, GlobalStats>,
+    config: &Config,
+    decision: ArbiterDecision,
+    reason_hash: [u8; 32],
+) -> Result<()> {
+    let arbiter = escrow.arbiter.ok_or(EscrowError::NoArbiter)?;
+
+    // Record the decision before any funds move
+    escrow.arbiter_decision = Some(decision);
+    escrow.reason_hash = reason_hash;
+    escrow.transition_dispute(DisputeState::Resolved)?;
+
+    // With appeals enabled the payout waits until the window closes
+    if config.appeals_enabled() {
+        let clock = Clock::get()?;
+        escrow.appeal_deadline = clock
+            .unix_timestamp
+            .checked_add(config.appeal_window)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        msg!(
+            "Arbiter decision recorded ({:?}), appealable until {}",
+            decision,
+            escrow.appeal_deadline
+        );
+
+        // Nothing is paid yet, amount and fee follow once the decision executes
+        emit!(ArbiterResolved {
+            escrow: escrow.key(),
+            arbiter,
+            decision,
+            reason_hash,
+            amount: 0,
+            arbiter_fee: 0,
+            label: escrow.label,
+        });
+
+        escrow.record_event(EscrowAction::ArbiterResolved, clock.unix_timestamp);
+
+        return Ok(());
+    }
+
+    let (transfer_amount, arbiter_fee) = execute_decision(
+        escrow,
+        decision,
+        buyer,
+        seller,
+        escrow.arbiter_fee_bps,
+        config,
+    )?;
+    global_stats.record_settled(escrow.locked_amount()?)?;
+    global_stats.record_fee(arbiter_fee)?;
+
+    msg!(
+        "Arbiter resolved escrow ({:?}): {} lamports paid, {} lamports fee accrued",
+        decision,
+        transfer_amount,
+        arbiter_fee
+    );
+
+    emit!(ArbiterResolved {
+        escrow: escrow.key(),
+        arbiter,
+        decision,
+        reason_hash,
+        amount: transfer_amount,
+        arbiter_fee,
+        label: escrow.label,
+    });
+
+    escrow.record_event(EscrowAction::ArbiterResolved, event_time());
+
+    Ok(())
+}
+
+/// Response time runs from the dispute, or from funding if no party opened one
+fn record_resolution_latency(stats: &mut ArbiterStats, escrow: &Escrow) -> Result<()> {
+    let started = if escrow.dispute_opened_at != 0 {
+        escrow.dispute_opened_at
+    } else {
+        escrow.funded_at
+    };
+    let latency = Clock::get()?.unix_timestamp.saturating_sub(started).max(0);
+    stats.record_resolution(latency as u64)
+}
+
+/// Shared body of soft_refund and reopen_for_renegotiation: everything above
+/// rent goes back to the buyer and the escrow returns to Created
+fn refund_to_created(accounts: &mut SoftRefund) -> Result<u64> {
+    let escrow = &mut accounts.escrow;
+
+    escrow.require_funded()?;
+    // A dispute (and any fee paid to open it) is the arbiter's to settle
+    require!(escrow.dispute_state == DisputeState::None, EscrowError::InvalidState);
+    // The buyer didn't put in the money, so it can't all go back to them
+    require!(!escrow.is_crowdfunded(), EscrowError::CrowdfundingUnsupported);
+
+    // What the escrow holds for the parties; an unclaimed arbiter fee stays
+    // behind for claim_arbiter_fee
+    let transfer_amount = escrow
+        .held_amount()?
+        .checked_sub(escrow.accrued_arbiter_fee)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    move_lamports(&escrow.to_account_info(), &accounts.buyer.to_account_info(), transfer_amount)?;
+
+    // The next funding round starts from nothing paid out
+    escrow.funded_amount = 0;
+    escrow.vested_claimed = 0;
+    escrow.transition_to(EscrowState::Created)?;
+    accounts.global_stats.record_settled(escrow.locked_amount()?)?;
+
+    Ok(transfer_amount)
+}
+
+/// Shared body of the release instructions. `authority` is who the release is
+/// authorized as (the caller, or the buyer for a signed authorization); fees
+/// go to the transaction's caller.
+fn release_escrow(
+    accounts: &mut ReleaseToSeller,
+    authority: &Pubkey,
+    memo: Option<String>,
+) -> Result<()> {
+    let escrow = &mut accounts.escrow;
+    let clock = Clock::get()?;
+
+    // Memo is only surfaced in logs/events, but keep it bounded
+    if let Some(memo) = &memo {
+        require!(memo.len() <= MAX_MEMO_LEN, EscrowError::MemoTooLong);
+    }
+
+    let fee_bps = check_release(
+        escrow,
+        &accounts.config,
+        authority,
+        accounts.cosigner.as_ref().map(|c| c.key()),
+        &clock,
+    )?;
+
+    // Chained deals only release behind their dependency
+    if let Some(dependency) = escrow.depends_on {
+        let satisfied = accounts.dependency.as_ref().is_some_and(|dep| {
+            dep.key() == dependency && dep.state == EscrowState::Released
+        });
+        require!(satisfied, EscrowError::DependencyNotSatisfied);
+    }
+
+    let split = release_split(escrow, &accounts.config, fee_bps, clock.unix_timestamp)?;
+    accounts.config.require_within_transfer_cap(split.gross)?;
+
+    // Releasing during a dispute ends it in the seller's favour: the dispute
+    // fee goes to the seller first, outside the base of every fee below
+    settle_dispute_fee(escrow, &accounts.seller)?;
+
+    // Releasing past the soft deadline hands the accrued penalty back to the buyer
+    let penalty = split.penalty;
+    if penalty > 0 {
+        let buyer = accounts.buyer.as_ref().ok_or(EscrowError::InvalidRecipient)?;
+        move_lamports(&escrow.to_account_info(), buyer, penalty)?;
+        require_usable_recipient(buyer)?;
+
+        emit!(LatePenaltyRefunded {
+            escrow: escrow.key(),
+            buyer: escrow.buyer,
+            amount: penalty,
+            label: escrow.label,
+        });
+    }
+
+    let referral = split.referral;
+    if referral > 0 {
+        let referrer = accounts.referrer.as_ref().ok_or(EscrowError::InvalidRecipient)?;
+        move_lamports(&escrow.to_account_info(), referrer, referral)?;
+        require_usable_recipient(referrer)?;
+        accounts.global_stats.record_fee(referral)?;
+
+        emit!(ReferralPaid {
+            escrow: escrow.key(),
+            referrer: referrer.key(),
+            amount: referral,
+            label: escrow.label,
+        });
+    }
+
+    // The protocol's cut only gets credited to the vault, the authority
+    // withdraws it in bulk later
+    let protocol_fee = split.protocol_fee;
+    if protocol_fee > 0 {
+        let vault = accounts.fee_vault.as_mut().ok_or(EscrowError::FeeVaultRequired)?;
+        move_lamports(&escrow.to_account_info(), &vault.to_account_info(), protocol_fee)?;
+        vault.credit(protocol_fee)?;
+        accounts.global_stats.record_fee(protocol_fee)?;
+    }
+
+    // The rest goes to the seller, less the caller's fee
+    let transfer_amount = split.seller;
+    move_lamports(&escrow.to_account_info(), &accounts.seller, transfer_amount)?;
+    move_lamports(&escrow.to_account_info(), &accounts.caller, split.caller_fee)?;
+    accounts.global_stats.record_fee(split.caller_fee)?;
+
+    // A never-seen seller is created by the credit; make sure it ends up
+    // rent-exempt rather than stranding the payout on a sub-rent account
+    if transfer_amount > 0 {
+        let rent = Rent::get()?.minimum_balance(accounts.seller.data_len());
+        require!(accounts.seller.lamports() >= rent, EscrowError::SellerBelowRent);
+    }
+
+    if let Some(reputation) = accounts.seller_reputation.as_mut() {
+        reputation.record_release(transfer_amount, !escrow.has_next_period())?;
+    }
+
+    if escrow.has_next_period() {
+        // Stay Funded and restart the timeout for the next period
+        escrow.periods_remaining = escrow
+            .periods_remaining
+            .checked_sub(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        escrow.created_at = clock.unix_timestamp;
+        accounts.global_stats.record_period_released(escrow.amount)?;
+    } else {
+        accounts.global_stats.record_settled(escrow.locked_amount()?)?;
+        if escrow.recurring {
+            escrow.periods_remaining = 0;
+        }
+        escrow.transition_to(EscrowState::Released)?;
+    }
+
+    msg!("Escrow released: {} lamports to seller", transfer_amount);
+    if let Some(memo) = &memo {
+        msg!("Release memo: {}", memo);
+    }
+
+    if escrow.recurring {
+        emit!(RecurringPeriodReleased {
+            escrow: escrow.key(),
+            seller: escrow.seller,
+            amount: transfer_amount,
+            periods_remaining: escrow.periods_remaining,
+            label: escrow.label,
+        });
+    }
+
+    emit!(EscrowReleased {
+        escrow: escrow.key(),
+        seller: escrow.seller,
+        amount: transfer_amount,
+        memo,
+        label: escrow.label,
+    });
+
+    escrow.record_event(EscrowAction::Released, clock.unix_timestamp);
+
+    Ok(())
+}
+
+/// Requires the instruction right before this one to be an Ed25519 program
+/// check of exactly one `signer` signature over `message`, with all data
+/// inline. The Ed25519 program already failed the transaction if the
+/// signature itself was bad; this pins down whose signature and over what.
+fn require_ed25519_signature(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    // Header (2) + one set of offsets (14)
+    const DATA_START: usize = 2 + 14;
+
+    let current = load_current_index_checked(instructions)? as usize;
+    require!(current > 0, EscrowError::InvalidAuthorization);
+    let ix = load_instruction_at_checked(current - 1, instructions)?;
+
+    require_keys_eq!(ix.program_id, ed25519_program::ID, EscrowError::InvalidAuthorization);
+    require!(
+        ix.accounts.is_empty() && ix.data.len() >= DATA_START && ix.data[0] == 1,
+        EscrowError::InvalidAuthorization
+    );
+
+    let read_u16 = |at: usize| u16::from_le_bytes([ix.data[at], ix.data[at + 1]]);
+    let (signature_ix, pubkey_offset, pubkey_ix) = (read_u16(4), read_u16(6) as usize, read_u16(8));
+    let (message_offset, message_size, message_ix) =
+        (read_u16(10) as usize, read_u16(12) as usize, read_u16(14));
+
+    // u16::MAX means "this instruction's own data"
+    require!(
+        signature_ix == u16::MAX && pubkey_ix == u16::MAX && message_ix == u16::MAX,
+        EscrowError::InvalidAuthorization
+    );
+
+    let signed_pubkey = ix.data.get(pubkey_offset..pubkey_offset + 32);
+    let signed_message = ix.data.get(message_offset..message_offset + message_size);
+    require!(
+        signed_pubkey == Some(signer.as_ref()) && signed_message == Some(message),
+        EscrowError::InvalidAuthorization
+    );
+
+    Ok(())
+}
+
+/// Rejects re-initialization while the pair's cancel cooldown is running.
+/// The marker only exists once the pair has cancelled at least once.
+fn require_cooldown_elapsed(marker: &AccountInfo, cooldown: i64, now: i64) -> Result<()> {
+    if cooldown == 0 || marker.data_is_empty() {
+        return Ok(());
+    }
+
+    require_keys_eq!(*marker.owner, crate::ID, EscrowError::InvalidAccountOwner);
+    let marker = CooldownMarker::try_deserialize(&mut &marker.try_borrow_data()?[..])?;
+    require!(
+        now >= marker.cancelled_at.saturating_add(cooldown),
+        EscrowError::CooldownActive
+    );
+    Ok(())
+}
+
+/// Shared rule for every amount an instruction takes: zero is never valid.
+/// Overflow is handled where amounts are combined, with checked arithmetic.
+fn validate_amount(amount: u64) -> Result<()> {
+    require!(amount > 0, EscrowError::InvalidAmount);
+    Ok(())
+}
+
+/// Time-ordering rules for an escrow's schedule, zero disabling a deadline:
+/// the funding deadline and expiry are still ahead, and funding opens at the
+/// start time before either of them
+fn validate_schedule(start_time: i64, funding_deadline: i64, expiry: i64, now: i64) -> Result<()> {
+    if funding_deadline != 0 {
+        require!(funding_deadline > now, EscrowError::InvalidFundingDeadline);
+        // Contributions are only taken between the start and the deadline
+        require!(start_time < funding_deadline, EscrowError::InvalidTimeout);
+    }
+    if expiry != 0 {
+        require!(expiry > now, EscrowError::InvalidTimeout);
+        // An escrow that expires before it can be funded never holds anything
+        require!(start_time < expiry, EscrowError::InvalidTimeout);
+    }
+    Ok(())
+}
+
+/// Checks shared by the buyer's funding paths, on top of the state check
+fn require_fundable(escrow: &Escrow, now: i64) -> Result<()> {
+    require!(!escrow.is_crowdfunded(), EscrowError::ContributionsOnly);
+
+    // Scheduled escrows can't be funded before their start time
+    require!(now >= escrow.start_time, EscrowError::NotYetActive);
+
+    // Escrows that opted in can't take money until both parties bound terms
+    require!(
+        !escrow.requires_terms || escrow.terms_bound,
+        EscrowError::TermsNotBound
+    );
+
+    // No funding into an escrow that lost (or never had) its arbiter
+    require!(
+        !escrow.requires_arbiter || escrow.effective_arbiter().is_some(),
+        EscrowError::ArbiterRequired
+    );
+    Ok(())
+}
+
+/// The one place the role invariant lives: buyer, seller and (when present)
+/// arbiter are pairwise distinct. Called wherever one of them gets assigned.
+fn validate_roles(buyer: &Pubkey, seller: &Pubkey, arbiter: Option<Pubkey>) -> Result<()> {
+    require_keys_neq!(*buyer, *seller, EscrowError::DuplicateRole);
+    if let Some(arbiter) = arbiter {
+        require_keys_neq!(arbiter, *buyer, EscrowError::DuplicateRole);
+        require_keys_neq!(arbiter, *seller, EscrowError::DuplicateRole);
+    }
+    Ok(())
+}
+
+/// Checks for cancel_escrow, shared with the is_cancellable query
+fn check_cancel(escrow: &Account<Escrow>, config: &Config, caller: &Pubkey) -> Result<()> {
+    // Partially crowdfunded escrows hold contributors' lamports, which
+    // closing would hand to the buyer
+    require!(
+        escrow.state == EscrowState::Created && escrow.funded_amount == 0,
+        EscrowError::EscrowAlreadyFunded
+    );
+
+    // Operators can restrict cancelling an unfunded escrow to its buyer
+    let is_authorized = *caller == escrow.buyer
+        || (config.seller_can_cancel_unfunded && *caller == escrow.seller);
+
+    require_authorized(is_authorized, "cancel", caller, &escrow.key())
+}
+
+/// Checks shared by the instructions that close a settled escrow
+fn require_closable(escrow: &Escrow) -> Result<()> {
+    require!(
+        matches!(escrow.state, EscrowState::Released | EscrowState::Refunded),
+        EscrowError::InvalidState
+    );
+    // A refunded crowdfunded escrow stays open until contributors reclaim
+    require!(
+        !(escrow.is_crowdfunded()
+            && escrow.state == EscrowState::Refunded
+            && escrow.funded_amount > 0),
+        EscrowError::InvalidState
+    );
+    // Closing would hand the arbiter's unclaimed fee to the buyer
+    require!(escrow.accrued_arbiter_fee == 0, EscrowError::ArbiterFeeUnclaimed);
+    Ok(())
+}
+
+/// A Funded state without the lamports it records means state and balance
+/// have diverged, so refuse rather than pull from rent. Returns the payable
+/// balance.
+fn require_balance_covers_held(escrow: &Account<Escrow>) -> Result<u64> {
+    let available = payable_balance(&escrow.to_account_info())?;
+    require!(
+        available >= escrow.held_amount()?,
+        EscrowError::InsufficientEscrowBalance
+    );
+    Ok(available)
+}
+
+/// An arbiter fee of `fee_bps` must respect the config cap and, together with
+/// the referral and protocol fees, stay within 100% of a payout
+fn require_arbiter_fee_allowed(escrow: &Escrow, config: &Config, fee_bps: u16) -> Result<()> {
+    require!(fee_bps <= config.max_arbiter_fee_bps, EscrowError::FeeAboveCap);
+    require!(
+        escrow.fees_within_total(fee_bps, config.protocol_fee_bps),
+        EscrowError::FeesExceedTotal
+    );
+    Ok(())
+}
+
+/// With a configured minimum, `arbiter` must have that much in its stake PDA
+fn require_arbiter_stake(
+    config: &Config,
+    stake: Option<&Account<ArbiterStake>>,
+    arbiter: &Pubkey,
+) -> Result<()> {
+    if config.min_arbiter_stake == 0 {
+        return Ok(());
+    }
+    let staked = stake
+        .filter(|s| s.arbiter == *arbiter)
+        .map_or(0, |s| s.amount);
+    require!(
+        staked >= config.min_arbiter_stake,
+        EscrowError::InsufficientArbiterStake
+    );
+    Ok(())
+}
+
+/// Timestamp for the event hash chain. It's bookkeeping only, so an
+/// unavailable Clock sysvar falls back to 0 instead of failing the handler.
+fn event_time() -> i64 {
+    Clock::get().map(|clock| clock.unix_timestamp).unwrap_or(0)
+}
+
+/// Basis-point share of `amount`, rounded as the config asks
+fn bps_of(amount: u64, bps: u16, rounding: FeeRounding) -> Result<u64> {
+    let scaled = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+    let bias = match rounding {
+        FeeRounding::Down => 0,
+        FeeRounding::Up => MAX_BPS as u128 - 1,
+        FeeRounding::Nearest => MAX_BPS as u128 / 2,
+    };
+    // bps <= MAX_BPS, so the share never exceeds `amount` and fits in a u64
+    Ok(((scaled + bias) / MAX_BPS as u128) as u64)
+}
+
+/// On vesting escrows, pays the seller anything vested but not yet claimed so
+/// a refund only returns the unvested remainder. No-op for other escrows.
+///
+/// The clock decides how much has vested, so it's only fetched (and required)
+/// for vesting escrows; plain refunds don't need the Clock sysvar, their event
+/// timestamp comes from `event_time`.
+fn settle_vested_to_seller(escrow: &mut Account<Escrow>, seller: &AccountInfo) -> Result<()> {
+    if !escrow.vesting {
+        return Ok(());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let owed = escrow.vested_amount(now)?.saturating_sub(escrow.vested_claimed);
+    move_lamports(&escrow.to_account_info(), seller, owed)?;
+    escrow.vested_claimed = escrow
+        .vested_claimed
+        .checked_add(owed)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+
+    Ok(())
+}
+
+/// Carries out an arbiter decision and moves the escrow to its final state.
+/// Refunds settle vested funds to the seller first, and refunded crowdfunded
+/// escrows keep the funds for reclaim_contribution. Returns (paid, fee).
+fn execute_decision<'info>(
+    escrow: &mut Account<'info, Escrow>,
+    decision: ArbiterDecision,
+    buyer: &AccountInfo<'info>,
+    seller: &AccountInfo<'info>,
+    fee_bps: u16,
+    config: &Config,
+) -> Result<(u64, u64)> {
+    let refund = decision == ArbiterDecision::RefundToBuyer;
+
+    // Only what is still held is decided on: anything the seller already
+    // claimed through vesting has left the escrow
+    require_balance_covers_held(escrow)?;
+
+    let (recipient, next_state) = if refund {
+        settle_vested_to_seller(escrow, seller)?;
+        (buyer, EscrowState::Refunded)
+    } else {
+        (seller, EscrowState::Released)
+    };
+
+    // The dispute fee goes to whoever the decision favours, before the
+    // balance is split so the arbiter fee never applies to it
+    settle_dispute_fee(escrow, recipient)?;
+
+    // The fee stays behind in the escrow until the arbiter claims it
+    let (paid, fee) = if refund && escrow.is_crowdfunded() {
+        (0, 0)
+    } else {
+        let escrow_info = escrow.to_account_info();
+        let gross = payable_balance(&escrow_info)?;
+        config.require_within_transfer_cap(gross)?;
+        let fee = bps_of(gross, fee_bps, config.fee_rounding)?;
+
+        move_lamports(&escrow_info, recipient, gross - fee)?;
+        escrow.accrued_arbiter_fee = escrow
+            .accrued_arbiter_fee
+            .checked_add(fee)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+
+        (gross - fee, fee)
+    };
+
+    if refund && !escrow.is_crowdfunded() {
+        require_usable_recipient(buyer)?;
+    }
+
+    escrow.transition_to(next_state)?;
+
+    Ok((paid, fee))
+}
+
+/// Pays a pending dispute fee to `recipient`, the party the outcome favours,
+/// and clears it. No-op when no fee was paid.
+fn settle_dispute_fee(escrow: &mut Account<Escrow>, recipient: &AccountInfo) -> Result<()> {
+    let dispute_fee = escrow.dispute_fee;
+    if dispute_fee == 0 {
+        return Ok(());
+    }
+
+    move_lamports(&escrow.to_account_info(), recipient, dispute_fee)?;
+    escrow.dispute_fee = 0;
+
+    emit!(DisputeFeeSettled {
+        escrow: escrow.key(),
+        payer: escrow.dispute_fee_payer.unwrap_or_default(),
+        recipient: recipient.key(),
+        amount: dispute_fee,
+        label: escrow.label,
+    });
+
+    Ok(())
+}
+
+/// Pays everything above rent out of the escrow: `fee_bps` of it to `fee_to`,
+/// the rest to `to`. Returns (amount paid to `to`, fee)
+fn pay_out_balance(
+    escrow: &AccountInfo,
+    to: &AccountInfo,
+    fee_to: &AccountInfo,
+    fee_bps: u16,
+    config: &Config,
+) -> Result<(u64, u64)> {
+    let gross = payable_balance(escrow)?;
+    pay_out(escrow, to, fee_to, gross, fee_bps, config)
+}
+
+/// Pays `gross` out of the escrow: `fee_bps` of it to `fee_to`, the rest to
+/// `to`, within the config's per-instruction transfer cap. Returns (amount
+/// paid to `to`, fee)
+fn pay_out(
+    escrow: &AccountInfo,
+    to: &AccountInfo,
+    fee_to: &AccountInfo,
+    gross: u64,
+    fee_bps: u16,
+    config: &Config,
+) -> Result<(u64, u64)> {
+    config.require_within_transfer_cap(gross)?;
+    let fee = bps_of(gross, fee_bps, config.fee_rounding)?;
+
+    move_lamports(escrow, to, gross - fee)?;
+    move_lamports(escrow, fee_to, fee)?;
+
+    Ok((gross - fee, fee))
+}
+
+/// Everything in the escrow above its rent-exempt minimum
+fn payable_balance(escrow: &AccountInfo) -> Result<u64> {
+    Ok(escrow.lamports().saturating_sub(escrow_rent(escrow)?))
+}
+
+/// Where a release's lamports go, worked out before any of them move
+struct ReleaseSplit {
+    /// One period's amount while a recurring escrow has periods left,
+    /// otherwise everything above rent and the pending dispute fee
+    gross: u64,
+    /// Late penalty handed back to the buyer
+    penalty: u64,
+    referral: u64,
+    /// Protocol fee after the fast-release discount
+    protocol_fee: u64,
+    /// `fee_bps` of what's left, paid to the caller
+    caller_fee: u64,
+    /// What the seller receives
+    seller: u64,
+}
+
+/// The one place a release's payout is split, shared by release_escrow and
+/// simulate_release so the dry run reports exactly what a release pays. The
+/// dispute fee is settled on its own and stays outside every fee base.
+fn release_split(escrow: &Account<Escrow>, config: &Config, fee_bps: u16, now: i64) -> Result<ReleaseSplit> {
+    let available = payable_balance(&escrow.to_account_info())?.saturating_sub(escrow.dispute_fee);
+    let gross = if escrow.has_next_period() {
+        escrow.amount.min(available)
+    } else {
+        available
+    };
+    let penalty = escrow.penalty_at(now, gross)?;
+
+    // The referrer's cut comes off the top, the caller's fee applies to the rest
+    let net = gross - penalty;
+    let referral = bps_of(net, escrow.referral_fee_bps, config.fee_rounding)?;
+
+    // Fast releases get part of the protocol fee waived
+    let full_protocol_fee = bps_of(net, config.protocol_fee_bps, config.fee_rounding)?;
+    let discount = bps_of(
+        full_protocol_fee,
+        config.fee_discount_bps_at(now - escrow.funded_at),
+        config.fee_rounding,
+    )?;
+    let protocol_fee = full_protocol_fee - discount;
+
+    let payable = net
+        .checked_sub(referral)
+        .and_then(|rest| rest.checked_sub(protocol_fee))
+        .ok_or(EscrowError::FeesExceedTotal)?;
+    let caller_fee = bps_of(payable, fee_bps, config.fee_rounding)?;
+
+    Ok(ReleaseSplit {
+        gross,
+        penalty,
+        referral,
+        protocol_fee,
+        caller_fee,
+        seller: payable - caller_fee,
+    })
+}
+
+/// Rent-exempt minimum of an escrow account. Refuses to compute it for an
+/// account that isn't exactly `8 + Escrow::LEN` bytes, since a resized account
+/// would silently shift how much of the balance counts as payable.
+fn escrow_rent(escrow: &AccountInfo) -> Result<u64> {
+    require_eq!(
+        escrow.data_len(),
+        8 + Escrow::LEN,
+        EscrowError::UnexpectedAccountSize
+    );
+    Ok(Rent::get()?.minimum_balance(escrow.data_len()))
+}
+
+/// Checks a payout recipient after it was credited: it must not be executable
+/// and must be rent-exempt, otherwise the runtime would reject the transaction
+/// with a much less obvious error
+fn require_usable_recipient(recipient: &AccountInfo) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(recipient.data_len());
+    require!(
+        !recipient.executable && recipient.lamports() >= rent,
+        EscrowError::RefundRecipientUnusable
+    );
+    Ok(())
+}
+
+/// Same steps as Anchor's `close` constraint, for accounts that arrive
+/// through `remaining_accounts`
+fn close_program_account<'info>(info: &AccountInfo<'info>, dest: &AccountInfo<'info>) -> Result<()> {
+    move_lamports(info, dest, info.lamports())?;
+    info.assign(&anchor_lang::system_program::ID);
+    info.realloc(0, false)?;
+    Ok(())
+}
+
+/// Error code number for a failed check, as reported by simulate_release
+fn reason_code(error: &Error) -> u16 {
+    match error {
+        Error::AnchorError(e) => e.error_code_number as u16,
+        Error::ProgramError(_) => u16::MAX,
+    }
+}
+
+/// Sends the vaulted NFT to `to` and closes the vault, rent going to `rent_to`
+fn release_nft_vault<'info>(
+    nft_escrow: &Account<'info, NftEscrow>,
+    vault: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    rent_to: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let mint = nft_escrow.mint;
+    let seeds: &[&[u8]] = &[b"nft_escrow", mint.as_ref(), &[nft_escrow.bump]];
+    let signer_seeds = &[seeds];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            token::Transfer {
+                from: vault.to_account_info(),
+                to: to.to_account_info(),
+                authority: nft_escrow.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        1,
+    )?;
+
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: vault.to_account_info(),
+            destination: rent_to.clone(),
+            authority: nft_escrow.to_account_info(),
+        },
+        signer_seeds,
+    ))
+}
+
+/// Moves lamports out of a program-owned account by direct mutation.
+/// Borrows are taken one at a time, so a recipient passed twice (e.g. the
+/// seller as caller) is fine, but the debited account is never a recipient.
+/// Both sides are checked, so a bad amount fails with ArithmeticOverflow
+/// instead of panicking.
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    require_keys_neq!(from.key(), to.key(), EscrowError::InvalidRecipient);
+    if amount == 0 {
+        return Ok(());
+    }
+    let debited = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+    let credited = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(EscrowError::ArithmeticOverflow)?;
+    **from.try_borrow_mut_lamports()? = debited;
+    **to.try_borrow_mut_lamports()? = credited;
+    Ok(())
+}
+
+// ========== Context Structs ==========
+
+#[derive(Accounts)]
+#[instruction(amount: u64, timeout_period: i64)]
+pub struct InitializeEscrow<'info> {
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Escrow::LEN,
+        seeds = [b"escrow", buyer.key().as_ref(), seller.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    /// CHECK: Seller doesn't need to sign for initialization
+    pub seller: AccountInfo<'info>,
+    
+    /// CHECK: Optional arbiter, can be buyer's key if not used. Only its key is
+    /// read, so it is neither mutable nor required to sign, and it may alias an
+    /// account that signs elsewhere in the transaction.
+    pub arbiter: AccountInfo<'info>,
+    
+    /// CHECK: Cooldown marker for this pair, may not exist yet (validated in handler)
+    #[account(
+        seeds = [b"cooldown", buyer.key().as_ref(), seller.key().as_ref()],
+        bump
+    )]
+    pub cooldown: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Stake of the assigned arbiter; needed when the config sets a minimum.
+    /// Checked against the resolved arbiter (explicit or default) in the handler.
+    pub arbiter_stake: Option<Account<'info, ArbiterStake>>,
+    
+    /// Caseload of the assigned arbiter, updated when passed in. Checked
+    /// against the resolved arbiter in the handler, which must then sign.
+    #[account(mut)]
+    pub arbiter_index: Option<Account<'info, ArbiterIndex>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(template_id: u64)]
+pub struct CreateTemplate<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Template::LEN,
+        seeds = [b"template", creator.key().as_ref(), &template_id.to_le_bytes()],
+        bump
+    )]
+    pub template: Account<'info, Template>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFromTemplate<'info> {
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Escrow::LEN,
+        seeds = [b"escrow", buyer.key().as_ref(), seller.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        seeds = [b"template", template.creator.as_ref(), &template.template_id.to_le_bytes()],
+        bump = template.bump,
+    )]
+    pub template: Account<'info, Template>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    /// CHECK: Seller doesn't need to sign for initialization
+    pub seller: AccountInfo<'info>,
+    
+    /// CHECK: Cooldown marker for this pair, may not exist yet (validated in handler)
+    #[account(
+        seeds = [b"cooldown", buyer.key().as_ref(), seller.key().as_ref()],
+        bump
+    )]
+    pub cooldown: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Stake of the template's arbiter; needed when the config sets a minimum
+    pub arbiter_stake: Option<Account<'info, ArbiterStake>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BindTerms<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+        has_one = buyer,
+        has_one = seller
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    pub buyer: Signer<'info>,
+    
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateArbiterFee<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// The arbiter, or the buyer/seller proposing the change
+    pub caller: Signer<'info>,
+    
+    /// The other party when buyer and seller update the fee together
+    pub counterparty: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct FallbackToDefaultArbiter<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+        has_one = buyer,
+        has_one = seller
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub buyer: Signer<'info>,
+    
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Mutable so it can pay the dispute fee
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RenounceArbiter<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    pub arbiter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.is_buyer_or_operator(&buyer.key()) @ EscrowError::NotEscrowBuyer
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// The buyer or its operator; whoever signs pays the escrowed amount
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MintReceipt<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+        has_one = buyer
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        init,
+        payer = buyer,
+        seeds = [b"receipt", escrow.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = escrow,
+    )]
+    pub receipt_mint: Account<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_receipt: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundEscrowsBatch<'info> {
+    /// Buyer (or operator) of every escrow in the batch, pays all of them
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PingEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetOperator<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+        has_one = buyer
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Contribute<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        init_if_needed,
+        payer = contributor,
+        space = 8 + Contribution::LEN,
+        seeds = [b"contribution", escrow.key().as_ref(), contributor.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+    
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimContribution<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        mut,
+        seeds = [b"contribution", escrow.key().as_ref(), contributor.key().as_ref()],
+        bump = contribution.bump,
+        has_one = escrow,
+        has_one = contributor,
+        close = contributor
+    )]
+    pub contribution: Account<'info, Contribution>,
+    
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimArbiterFee<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(mut)]
+    pub arbiter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleGoal<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// CHECK: Receives the pooled funds on success, must be the escrow's seller
+    #[account(
+        mut,
+        constraint = seller.key() == escrow.seller @ EscrowError::InvalidRecipient
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+    
+    /// Only read for the transfer cap
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleExpired<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// CHECK: Refunded when the escrow defaults to the buyer
+    #[account(
+        mut,
+        constraint = buyer.key() == escrow.buyer @ EscrowError::InvalidRecipient
+    )]
+    pub buyer: AccountInfo<'info>,
+    
+    /// CHECK: Paid when the escrow defaults to the seller, and for vested funds
+    #[account(
+        mut,
+        constraint = seller.key() == escrow.seller @ EscrowError::InvalidRecipient
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+    
+    /// Only read for the transfer cap
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Anyone can crank an expired escrow
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseToSeller<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// CHECK: Seller will receive funds, must be the escrow's seller
+    #[account(
+        mut,
+        constraint = seller.key() == escrow.seller @ EscrowError::InvalidRecipient
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Required only for escrows above the high-value threshold
+    pub cosigner: Option<Signer<'info>>,
+    
+    /// Updated when passed in; omit it for sellers without a record
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.seller.as_ref()],
+        bump = seller_reputation.bump,
+    )]
+    pub seller_reputation: Option<Account<'info, SellerReputation>>,
+    
+    /// CHECK: Receives the late penalty, required only past the soft deadline
+    #[account(
+        mut,
+        constraint = buyer.key() == escrow.buyer @ EscrowError::InvalidRecipient
+    )]
+    pub buyer: Option<AccountInfo<'info>>,
+    
+    /// CHECK: Receives the referral fee, required only for escrows with one
+    #[account(
+        mut,
+        constraint = Some(referrer.key()) == escrow.referrer @ EscrowError::InvalidRecipient
+    )]
+    pub referrer: Option<AccountInfo<'info>>,
+    
+    /// Credited with the protocol fee, required only while one is configured
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump = fee_vault.bump,
+    )]
+    pub fee_vault: Option<Account<'info, FeeVault>>,
+    
+    /// The escrow named in `depends_on`, read-only; required only when set
+    pub dependency: Option<Account<'info, Escrow>>,
+    
+    /// Mutable so it can receive the arbiter fee when the arbiter resolves
+    #[account(mut)]
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseWithAuthorization<'info> {
+    /// Same accounts as release_to_seller; `caller` is the relayer
+    pub release: ReleaseToSeller<'info>,
+    
+    /// CHECK: The instructions sysvar, checked by address
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct IssueVoucher<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// The buyer or the escrow's arbiter
+    pub issuer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemVoucher<'info> {
+    /// Same accounts as release_to_seller; `caller` is the seller
+    pub release: ReleaseToSeller<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SimulateRelease<'info> {
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// CHECK: Key the release would be checked for, the real call needs its signature
+    pub cosigner: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Would-be caller of release_to_seller, doesn't have to sign a dry run
+    pub caller: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct IsCancellable<'info> {
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// CHECK: Would-be caller of cancel_escrow, doesn't have to sign a query
+    pub caller: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefundToBuyer<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// CHECK: Receives the refund. The escrow's buyer, or on escrows with a
+    /// minted receipt the receipt holder (checked against `receipt`)
+    #[account(
+        mut,
+        constraint = buyer.key() == escrow.buyer || escrow.receipt_mint.is_some()
+            @ EscrowError::InvalidRecipient
+    )]
+    pub buyer: AccountInfo<'info>,
+    
+    /// CHECK: Receives any vested-but-unclaimed funds, must be the escrow's seller
+    #[account(
+        mut,
+        constraint = seller.key() == escrow.seller @ EscrowError::InvalidRecipient
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Required only for escrows above the high-value threshold
+    pub cosigner: Option<Signer<'info>>,
+    
+    /// The other party, for escrows whose refund policy is MutualOnly
+    pub counterparty: Option<Signer<'info>>,
+    
+    /// Token account holding the receipt; required once one was minted
+    pub receipt: Option<Account<'info, TokenAccount>>,
+    
+    /// Mutable so it can receive the arbiter fee when the arbiter resolves
+    #[account(mut)]
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TopUpRent<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+        has_one = seller
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct SetTrancheSchedule<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+        has_one = buyer,
+        has_one = seller
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + TrancheSchedule::LEN,
+        seeds = [b"tranches", escrow.key().as_ref()],
+        bump
+    )]
+    pub tranche_schedule: Account<'info, TrancheSchedule>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    pub seller: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTranche<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+        has_one = seller
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        mut,
+        seeds = [b"tranches", escrow.key().as_ref()],
+        bump = tranche_schedule.bump,
+    )]
+    pub tranche_schedule: Account<'info, TrancheSchedule>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[derive(Accounts)]
+pub struct ArbiterResolve<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// CHECK: Receives funds on a release decision, must be the escrow's seller
+    #[account(
+        mut,
+        constraint = seller.key() == escrow.seller @ EscrowError::InvalidRecipient
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    /// CHECK: Receives funds on a refund decision, must be the escrow's buyer
+    #[account(
+        mut,
+        constraint = buyer.key() == escrow.buyer @ EscrowError::InvalidRecipient
+    )]
+    pub buyer: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Required only for escrows above the high-value threshold
+    pub cosigner: Option<Signer<'info>>,
+    
+    pub arbiter: Signer<'info>,
+    
+    /// Updated when passed in; omit it for arbiters without a record
+    #[account(
+        mut,
+        seeds = [b"arbiter_stats", arbiter.key().as_ref()],
+        bump = arbiter_stats.bump,
+    )]
+    pub arbiter_stats: Option<Account<'info, ArbiterStats>>,
+    
+    /// Updated when passed in; omit it for arbiters without an index
+    #[account(
+        mut,
+        seeds = [b"arbiter_index", arbiter.key().as_ref()],
+        bump = arbiter_index.bump,
+    )]
+    pub arbiter_index: Option<Account<'info, ArbiterIndex>>,
+}
+
+#[derive(Accounts)]
+pub struct SettleWithArbiterSig<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// CHECK: Receives funds on a release decision, must be the escrow's seller
+    #[account(
+        mut,
+        constraint = seller.key() == escrow.seller @ EscrowError::InvalidRecipient
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    /// CHECK: Receives funds on a refund decision, must be the escrow's buyer
+    #[account(
+        mut,
+        constraint = buyer.key() == escrow.buyer @ EscrowError::InvalidRecipient
+    )]
+    pub buyer: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Required only for escrows above the high-value threshold
+    pub cosigner: Option<Signer<'info>>,
+    
+    /// Updated when passed in; omit it for arbiters without a record
+    #[account(
+        mut,
+        seeds = [b"arbiter_stats", escrow.arbiter.unwrap_or_default().as_ref()],
+        bump = arbiter_stats.bump,
+    )]
+    pub arbiter_stats: Option<Account<'info, ArbiterStats>>,
+    
+    /// Updated when passed in; omit it for arbiters without an index
+    #[account(
+        mut,
+        seeds = [b"arbiter_index", escrow.arbiter.unwrap_or_default().as_ref()],
+        bump = arbiter_index.bump,
+    )]
+    pub arbiter_index: Option<Account<'info, ArbiterIndex>>,
+    
+    /// CHECK: The instructions sysvar, checked by address
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AppealDecision<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// The party the pending decision goes against
+    pub appellant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeResolution<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// CHECK: Receives funds on a release decision, must be the escrow's seller
+    #[account(
+        mut,
+        constraint = seller.key() == escrow.seller @ EscrowError::InvalidRecipient
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    /// CHECK: Receives funds on a refund decision, must be the escrow's buyer
+    #[account(
+        mut,
+        constraint = buyer.key() == escrow.buyer @ EscrowError::InvalidRecipient
+    )]
+    pub buyer: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+    
+    /// Only read for the fee rounding and transfer cap
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveAppeal<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// CHECK: Receives funds on a release decision, must be the escrow's seller
+    #[account(
+        mut,
+        constraint = seller.key() == escrow.seller @ EscrowError::InvalidRecipient
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    /// CHECK: Receives funds on a refund decision, must be the escrow's buyer
+    #[account(
+        mut,
+        constraint = buyer.key() == escrow.buyer @ EscrowError::InvalidRecipient
+    )]
+    pub buyer: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Required only for escrows above the high-value threshold
+    pub cosigner: Option<Signer<'info>>,
+    
+    pub appeal_arbiter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SoftRefund<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+        has_one = buyer,
+        has_one = seller
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    pub seller: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[derive(Accounts)]
+pub struct RefundInstallments<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+        has_one = buyer
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// CHECK: Receives the refund; verified against escrow.buyer
+    #[account(mut)]
+    pub buyer: UncheckedAccount<'info>,
+    
+    /// Seller, or the buyer once the timeout has passed
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawExcess<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+        has_one = buyer
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+        close = buyer
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// CHECK: Receives rent refund, must be the escrow's buyer
+    #[account(
+        mut,
+        constraint = buyer.key() == escrow.buyer @ EscrowError::InvalidRecipient
+    )]
+    pub buyer: AccountInfo<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = 8 + CooldownMarker::LEN,
+        seeds = [b"cooldown", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump
+    )]
+    pub cooldown: Account<'info, CooldownMarker>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Pays for the cooldown marker the first time a pair cancels
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEscrowsBatch<'info> {
+    /// Anyone can sweep, rent only ever goes back to each escrow's buyer
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ArchiveEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
+        bump = escrow.bump,
+        has_one = buyer,
+        close = buyer
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + EscrowArchive::LEN,
+        seeds = [b"archive", escrow.key().as_ref(), &escrow.created_at.to_le_bytes()],
+        bump
+    )]
+    pub archive: Account<'info, EscrowArchive>,
+    
+    /// Pays for the archive and receives the escrow's rent
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeNftEscrow<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + NftEscrow::LEN,
+        seeds = [b"nft_escrow", mint.key().as_ref()],
+        bump
+    )]
+    pub nft_escrow: Account<'info, NftEscrow>,
+    
+    /// Holds the NFT until settlement, owned by the NFT escrow PDA
+    #[account(
+        init,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = nft_escrow
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = seller
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    /// CHECK: Only recorded, the buyer signs when funding
+    pub buyer: AccountInfo<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundNftEscrow<'info> {
     #[account(
         mut,
-        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
-        bump = escrow.bump,
+        seeds = [b"nft_escrow", nft_escrow.mint.as_ref()],
+        bump = nft_escrow.bump,
         has_one = buyer
     )]
-    pub escrow: Account<'info, Escrow>,
+    pub nft_escrow: Account<'info, NftEscrow>,
     
     #[account(mut)]
     pub buyer: Signer<'info>,
@@ -215,50 +4798,570 @@ pub struct FundEscrow<'info> {
 }
 
 #[derive(Accounts)]
-pub struct ReleaseToSeller<'info> {
+pub struct SettleNftEscrow<'info> {
     #[account(
         mut,
-        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
-        bump = escrow.bump,
+        seeds = [b"nft_escrow", nft_escrow.mint.as_ref()],
+        bump = nft_escrow.bump,
+        has_one = buyer,
+        has_one = seller,
+        has_one = mint,
+        close = seller
     )]
-    pub escrow: Account<'info, Escrow>,
+    pub nft_escrow: Account<'info, NftEscrow>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = nft_escrow
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    /// Created for the buyer if they don't have one for this mint yet
+    #[account(
+        init_if_needed,
+        payer = caller,
+        associated_token::mint = mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+    
+    /// CHECK: Validated by has_one, owns the receiving token account
+    pub buyer: AccountInfo<'info>,
     
-    /// CHECK: Seller will receive funds
+    /// CHECK: Validated by has_one, receives the price and the rent
     #[account(mut)]
     pub seller: AccountInfo<'info>,
     
+    #[account(mut)]
     pub caller: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct RefundToBuyer<'info> {
+pub struct CancelNftEscrow<'info> {
     #[account(
         mut,
-        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
-        bump = escrow.bump,
+        seeds = [b"nft_escrow", nft_escrow.mint.as_ref()],
+        bump = nft_escrow.bump,
+        has_one = buyer,
+        has_one = seller,
+        has_one = mint,
+        close = seller
     )]
-    pub escrow: Account<'info, Escrow>,
+    pub nft_escrow: Account<'info, NftEscrow>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = nft_escrow
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = seller
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
     
-    /// CHECK: Buyer will receive refund
+    /// CHECK: Validated by has_one, gets the price back if it was deposited
     #[account(mut)]
     pub buyer: AccountInfo<'info>,
     
+    /// CHECK: Validated by has_one, receives the rent
+    #[account(mut)]
+    pub seller: AccountInfo<'info>,
+    
     pub caller: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct CancelEscrow<'info> {
+pub struct InitializeGlobalStats<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + GlobalStats::LEN,
+        seeds = [b"global_stats"],
+        bump
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSellerReputation<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + SellerReputation::LEN,
+        seeds = [b"reputation", seller.key().as_ref()],
+        bump
+    )]
+    pub seller_reputation: Account<'info, SellerReputation>,
+    
+    /// CHECK: Only used as a PDA seed
+    pub seller: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeArbiterStats<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ArbiterStats::LEN,
+        seeds = [b"arbiter_stats", arbiter.key().as_ref()],
+        bump
+    )]
+    pub arbiter_stats: Account<'info, ArbiterStats>,
+    
+    /// CHECK: Only used as a PDA seed
+    pub arbiter: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeArbiterIndex<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ArbiterIndex::LEN,
+        seeds = [b"arbiter_index", arbiter.key().as_ref()],
+        bump
+    )]
+    pub arbiter_index: Account<'info, ArbiterIndex>,
+    
+    /// CHECK: Only used as a PDA seed
+    pub arbiter: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetArbiterIndex<'info> {
+    #[account(
+        seeds = [b"arbiter_index", arbiter_index.arbiter.as_ref()],
+        bump = arbiter_index.bump,
+    )]
+    pub arbiter_index: Account<'info, ArbiterIndex>,
+}
+
+#[derive(Accounts)]
+pub struct PruneArbiterIndex<'info> {
     #[account(
         mut,
-        seeds = [b"escrow", escrow.buyer.as_ref(), escrow.seller.as_ref()],
-        bump = escrow.bump,
-        close = buyer
+        seeds = [b"arbiter_index", arbiter_index.arbiter.as_ref()],
+        bump = arbiter_index.bump,
+    )]
+    pub arbiter_index: Account<'info, ArbiterIndex>,
+}
+
+#[derive(Accounts)]
+pub struct GetArbiterStats<'info> {
+    #[account(
+        seeds = [b"arbiter_stats", arbiter_stats.arbiter.as_ref()],
+        bump = arbiter_stats.bump,
+    )]
+    pub arbiter_stats: Account<'info, ArbiterStats>,
+}
+
+#[derive(Accounts)]
+pub struct GetSellerReputation<'info> {
+    #[account(
+        seeds = [b"reputation", seller_reputation.seller.as_ref()],
+        bump = seller_reputation.bump,
+    )]
+    pub seller_reputation: Account<'info, SellerReputation>,
+}
+
+#[derive(Accounts)]
+pub struct GetGlobalStats<'info> {
+    #[account(
+        seeds = [b"global_stats"],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[derive(Accounts)]
+pub struct GetConfig<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct DeriveEscrowAddress {}
+
+#[derive(Accounts)]
+pub struct EscrowFlags<'info> {
+    pub escrow: Account<'info, Escrow>,
+    
+    /// Read for the timeout policy
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
     )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct DescribeEscrow<'info> {
     pub escrow: Account<'info, Escrow>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Config::LEN,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
     
-    /// CHECK: Receives rent refund
     #[account(mut)]
-    pub buyer: AccountInfo<'info>,
+    pub authority: Signer<'info>,
     
-    pub caller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + FeeVault::LEN,
+        seeds = [b"fee_vault"],
+        bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump = fee_vault.bump,
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+    
+    /// CHECK: Any destination the authority picks for the fees
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StakeAsArbiter<'info> {
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        space = 8 + ArbiterStake::LEN,
+        seeds = [b"arbiter_stake", arbiter.key().as_ref()],
+        bump
+    )]
+    pub arbiter_stake: Account<'info, ArbiterStake>,
+    
+    #[account(mut)]
+    pub arbiter: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SlashArbiter<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"arbiter_stake", arbiter_stake.arbiter.as_ref()],
+        bump = arbiter_stake.bump,
+    )]
+    pub arbiter_stake: Account<'info, ArbiterStake>,
+    
+    /// Receives the slashed lamports
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RepairBump<'info> {
+    /// No seeds constraint: the stored bump is what's being repaired, and the
+    /// handler checks the address against the canonical derivation instead
+    #[account(mut)]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::entrypoint::SUCCESS;
+    use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+    use crate::state::tests::zeroed_escrow;
+
+    /// Answers the Rent sysvar off-chain with the default rent
+    struct RentStub;
+
+    impl SyscallStubs for RentStub {
+        fn sol_get_rent_sysvar(&self, _var_addr: *mut u8) -> u64 {
+            // Rent::get starts from Rent::default(), which is what we want
+            SUCCESS
+        }
+    }
+
+    /// Backing storage for an escrow AccountInfo owned by this program
+    struct TestAccount {
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+    }
+
+    impl TestAccount {
+        /// `escrow` serialized into an account of `data_len` bytes, holding
+        /// `above_rent` lamports on top of the rent for that size
+        fn new(escrow: &Escrow, data_len: usize, above_rent: u64) -> Self {
+            set_syscall_stubs(Box::new(RentStub));
+
+            let mut data = Vec::with_capacity(data_len);
+            escrow.try_serialize(&mut data).unwrap();
+            data.resize(data_len, 0);
+
+            Self {
+                key: Pubkey::new_unique(),
+                owner: crate::ID,
+                lamports: Rent::default().minimum_balance(data_len) + above_rent,
+                data,
+            }
+        }
+
+        fn escrow(escrow: &Escrow, above_rent: u64) -> Self {
+            Self::new(escrow, 8 + Escrow::LEN, above_rent)
+        }
+
+        fn info(&mut self) -> AccountInfo<'_> {
+            AccountInfo::new(
+                &self.key,
+                false,
+                true,
+                &mut self.lamports,
+                &mut self.data,
+                &self.owner,
+                false,
+                0,
+            )
+        }
+    }
+
+    fn funded_escrow(amount: u64) -> Escrow {
+        let mut escrow = zeroed_escrow();
+        escrow.amount = amount;
+        escrow.funded_amount = amount;
+        escrow.periods_remaining = 1;
+        escrow.state = EscrowState::Funded;
+        escrow
+    }
+
+    fn zeroed_config() -> Config {
+        Config::deserialize(&mut &[0u8; Config::LEN][..]).unwrap()
+    }
+
+    #[test]
+    fn balance_covering_held_amount_is_payable() {
+        let mut escrow = funded_escrow(1_000);
+        escrow.vesting = true;
+        escrow.vested_claimed = 400;
+        let mut account = TestAccount::escrow(&escrow, 600);
+        let info = account.info();
+        let escrow = Account::<Escrow>::try_from(&info).unwrap();
+
+        assert_eq!(require_balance_covers_held(&escrow).unwrap(), 600);
+    }
+
+    #[test]
+    fn underfunded_escrow_is_rejected() {
+        let mut account = TestAccount::escrow(&funded_escrow(1_000), 999);
+        let info = account.info();
+        let escrow = Account::<Escrow>::try_from(&info).unwrap();
+
+        assert_eq!(
+            require_balance_covers_held(&escrow).unwrap_err(),
+            EscrowError::InsufficientEscrowBalance.into()
+        );
+    }
+
+    #[test]
+    fn release_refuses_an_escrow_with_nothing_above_rent() {
+        let mut account = TestAccount::escrow(&funded_escrow(0), 0);
+        let info = account.info();
+        let escrow = Account::<Escrow>::try_from(&info).unwrap();
+        let buyer = escrow.buyer;
+
+        assert_eq!(
+            check_release(&escrow, &zeroed_config(), &buyer, None, &Clock::default()).unwrap_err(),
+            EscrowError::NothingToTransfer.into()
+        );
+    }
+
+    #[test]
+    fn rent_is_charged_for_the_escrow_size() {
+        let mut account = TestAccount::escrow(&zeroed_escrow(), 0);
+
+        assert_eq!(
+            escrow_rent(&account.info()).unwrap(),
+            Rent::default().minimum_balance(8 + Escrow::LEN)
+        );
+    }
+
+    #[test]
+    fn rent_rejects_a_wrongly_sized_account() {
+        let mut account = TestAccount::new(&zeroed_escrow(), 8 + Escrow::LEN + 1, 0);
+
+        assert_eq!(
+            escrow_rent(&account.info()).unwrap_err(),
+            EscrowError::UnexpectedAccountSize.into()
+        );
+    }
+
+    #[test]
+    fn schedule_needs_future_deadlines_after_the_start() {
+        assert!(validate_schedule(0, 0, 0, 100).is_ok());
+        assert!(validate_schedule(150, 200, 300, 100).is_ok());
+
+        for (start_time, funding_deadline, expiry, error) in [
+            (0, 100, 0, EscrowError::InvalidFundingDeadline),
+            (200, 200, 0, EscrowError::InvalidTimeout),
+            (0, 0, 100, EscrowError::InvalidTimeout),
+            (300, 0, 300, EscrowError::InvalidTimeout),
+        ] {
+            assert_eq!(
+                validate_schedule(start_time, funding_deadline, expiry, 100).unwrap_err(),
+                error.into()
+            );
+        }
+    }
+
+    /// `escrow` as an account created by an older layout, without the
+    /// trailing funded_slot, state and bump
+    fn old_size_account(escrow: &Escrow) -> TestAccount {
+        let mut account = TestAccount::escrow(escrow, 1_000);
+        let mut serialized = Vec::new();
+        escrow.try_serialize(&mut serialized).unwrap();
+        account.data.truncate(serialized.len() - 10);
+        account
+    }
+
+    #[test]
+    fn rent_rejects_an_old_size_account() {
+        let mut escrow = funded_escrow(1_000);
+        escrow.bump = 255;
+        let mut account = old_size_account(&escrow);
+
+        assert_eq!(
+            escrow_rent(&account.info()).unwrap_err(),
+            EscrowError::UnexpectedAccountSize.into()
+        );
+    }
+
+    #[test]
+    fn migrated_account_zero_fills_the_new_fields() {
+        let mut escrow = funded_escrow(1_000);
+        escrow.funded_slot = 42;
+        escrow.bump = 255;
+        let mut account = old_size_account(&escrow);
+
+        // Grow to the current layout the way a zero-initialising realloc would
+        account.data.resize(8 + Escrow::LEN, 0);
+        let info = account.info();
+        assert_eq!(
+            escrow_rent(&info).unwrap(),
+            Rent::default().minimum_balance(8 + Escrow::LEN)
+        );
+
+        let migrated = Account::<Escrow>::try_from(&info).unwrap();
+        assert_eq!(migrated.amount, 1_000);
+        assert_eq!(migrated.funded_amount, 1_000);
+        assert_eq!(migrated.funded_slot, 0);
+        assert_eq!(migrated.state, EscrowState::Created);
+        assert_eq!(migrated.bump, 0);
+    }
 }
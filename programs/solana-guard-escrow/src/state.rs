@@ -1,4 +1,60 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+use crate::errors::EscrowError;
+
+/// Denominator for basis-point fees (100%)
+pub const MAX_BPS: u16 = 10_000;
+
+/// Most escrows close_escrows_batch accepts, keeps it within compute and account limits
+pub const MAX_BATCH_CLOSE: usize = 10;
+
+// Bits returned by escrow_flags, see the FLAG_* docs
+/// State is Funded (including a pending or appealed decision)
+pub const FLAG_FUNDED: u32 = 1 << 0;
+/// A dispute is open, under appeal, or decided and awaiting finalization
+pub const FLAG_DISPUTED: u32 = 1 << 1;
+/// The seller's self-claim timeout has elapsed
+pub const FLAG_TIMEOUT_ELAPSED: u32 = 1 << 2;
+/// An arbiter is assigned
+pub const FLAG_HAS_ARBITER: u32 = 1 << 3;
+/// Funds vest linearly to the seller
+pub const FLAG_VESTING: u32 = 1 << 4;
+/// Funded through contributions
+pub const FLAG_CROWDFUNDED: u32 = 1 << 5;
+/// Pays one period per release
+pub const FLAG_RECURRING: u32 = 1 << 6;
+/// Released, refunded or cancelled
+pub const FLAG_SETTLED: u32 = 1 << 7;
+
+/// Domain tag that starts every message signed for release_with_authorization
+pub const RELEASE_AUTH_PREFIX: &[u8] = b"solana-guard-escrow:release";
+
+/// Domain tag that starts every message signed for settle_with_arbiter_sig
+pub const DECISION_AUTH_PREFIX: &[u8] = b"solana-guard-escrow:decision";
+
+/// Shortest timeout initialize_escrow accepts, in seconds. Anything shorter
+/// would let the seller self-claim almost as soon as the escrow is funded.
+pub const MIN_TIMEOUT_PERIOD: i64 = 60;
+
+/// Lamports a buyer must keep on top of the amount for `check_buyer_balance`:
+/// the signature fee of the funding transaction
+pub const FUNDING_FEE_ALLOWANCE: u64 = 5_000;
+
+/// Most escrows fund_escrows_batch accepts, each one costs a System transfer CPI
+pub const MAX_BATCH_FUND: usize = 8;
+
+/// Most protocol fee discount tiers the config holds
+pub const MAX_FEE_DISCOUNT_TIERS: usize = 3;
+
+/// Longest preimage redeem_voucher hashes
+pub const MAX_VOUCHER_PREIMAGE_LEN: usize = 64;
+
+/// Most escrows an ArbiterIndex lists at once
+pub const MAX_ARBITER_INDEX_ENTRIES: usize = 16;
+
+/// Most tranches a TrancheSchedule holds
+pub const MAX_TRANCHES: usize = 8;
 
 /// Escrow account structure storing all escrow state
 #[account]
@@ -21,6 +77,136 @@ pub struct Escrow {
     /// Timeout period in seconds after which seller can claim
     pub timeout_period: i64,      // 8 bytes
     
+    /// Unix timestamp before which the escrow can't be funded (0 = immediately)
+    pub start_time: i64,          // 8 bytes
+    
+    /// Arbiter's cut in basis points, paid when the arbiter resolves
+    pub arbiter_fee_bps: u16,     // 2 bytes
+    
+    /// Decision recorded by arbiter_resolve, if the arbiter settled the escrow
+    pub arbiter_decision: Option<ArbiterDecision>, // 1 + 1 = 2 bytes
+    
+    /// Hash of the arbiter's off-chain reasoning document
+    pub reason_hash: [u8; 32],    // 32 bytes
+    
+    /// Funds vest linearly to the seller over the timeout period
+    pub vesting: bool,            // 1 byte
+    
+    /// Lamports the seller has already claimed ahead of settlement, through
+    /// claim_vested or claim_tranche
+    pub vested_claimed: u64,      // 8 bytes
+    
+    /// Funding is blocked until both parties have bound the terms
+    pub requires_terms: bool,     // 1 byte
+    
+    /// Whether buyer and seller have signed the terms hash
+    pub terms_bound: bool,        // 1 byte
+    
+    /// Hash of the off-chain terms both parties agreed to
+    pub terms_hash: [u8; 32],     // 32 bytes
+    
+    /// Short support reference like "order-4821", null-padded, set once at init
+    pub label: [u8; 16],          // 16 bytes
+    
+    /// Crowdfunded escrows: contributions close at this timestamp (0 = buyer funds alone)
+    pub funding_deadline: i64,    // 8 bytes
+    
+    /// Lamports currently committed by funders
+    pub funded_amount: u64,       // 8 bytes
+    
+    /// While a decision is pending: the arbiter decision can be appealed until this timestamp
+    pub appeal_deadline: i64,     // 8 bytes
+    
+    /// Subscription escrow: each release pays one period and restarts the timeout
+    pub recurring: bool,          // 1 byte
+    
+    /// Periods still funded, including the current one (1 for one-off escrows)
+    pub periods_remaining: u32,   // 4 bytes
+    
+    /// Key allowed to fund and release on the buyer's behalf
+    pub buyer_operator: Option<Pubkey>, // 33 bytes
+    
+    /// Unix timestamp the escrow became Funded (0 until then)
+    pub funded_at: i64,           // 8 bytes
+    
+    /// Unix timestamp of the last ping_escrow or reopen (created_at until
+    /// then); the expiry counts from it
+    pub last_activity: i64,       // 8 bytes
+    
+    /// Who may call refund_to_buyer, fixed at init
+    pub refund_policy: RefundPolicy, // 1 byte
+    
+    /// Nonce the next signed authorization must carry, shared by buyer
+    /// releases and arbiter decisions since either one settles the escrow
+    pub authorization_nonce: u64, // 8 bytes
+    
+    /// Unix timestamp a party opened a dispute (0 = none)
+    pub dispute_opened_at: i64,   // 8 bytes
+    
+    /// Unix timestamp after which late releases are penalized (0 = never)
+    pub soft_deadline: i64,       // 8 bytes
+    
+    /// Share of `amount` returned to the buyer per day past the soft deadline
+    pub penalty_rate_bps_per_day: u16, // 2 bytes
+    
+    /// Dispute progress, tracked alongside the funding state
+    pub dispute_state: DisputeState, // 1 byte
+    
+    /// Head of the hash chain over every mutation, see `record_event`
+    pub last_event_hash: [u8; 32], // 32 bytes
+    
+    /// Affiliate paid a cut of each release (None = no referral)
+    pub referrer: Option<Pubkey>, // 1 + 32 = 33 bytes
+    
+    /// Referrer's cut of each release, in basis points
+    pub referral_fee_bps: u16,    // 2 bytes
+    
+    /// Joint purchase: settle_goal releases once contributions hit the target
+    pub release_on_goal: bool,    // 1 byte
+    
+    /// Arbiter fee earned on resolution, held here until claim_arbiter_fee
+    pub accrued_arbiter_fee: u64, // 8 bytes
+    
+    /// Extra time after the timeout reserved for the arbiter before the
+    /// seller can self-claim
+    pub arbiter_window: i64,      // 8 bytes
+    
+    /// Buyer gets a transferable receipt token after funding
+    pub mint_receipt: bool,       // 1 byte
+    
+    /// Receipt mint once minted; its holder has the buyer's refund rights
+    pub receipt_mint: Option<Pubkey>, // 33 bytes
+    
+    /// Seller can never self-claim after the timeout, only buyer and arbiter release
+    pub seller_autoclaim_disabled: bool, // 1 byte
+    
+    /// Another escrow that has to be Released before this one can release
+    pub depends_on: Option<Pubkey>, // 33 bytes
+    
+    /// Funding is blocked while the escrow has no effective arbiter
+    pub requires_arbiter: bool,   // 1 byte
+    
+    /// Party that paid the dispute fee when opening a dispute
+    pub dispute_fee_payer: Option<Pubkey>, // 33 bytes
+    
+    /// Dispute fee still held, paid to the winning party on resolution
+    pub dispute_fee: u64,         // 8 bytes
+    
+    /// Final deadline after which anyone can settle to `expiry_default` (0 = none)
+    pub expiry: i64,              // 8 bytes
+    
+    /// Party an abandoned escrow settles to at the expiry
+    pub expiry_default: ExpiryDefault, // 1 byte
+    
+    /// sha256 of the secret that lets the seller redeem a release (None = no voucher)
+    pub release_voucher: Option<[u8; 32]>, // 1 + 32 = 33 bytes
+    
+    /// Deal type, metadata for indexers to segment escrows by
+    pub category: EscrowCategory, // 1 byte
+    
+    /// Slot the escrow became Funded in, for `min_slots_before_release`
+    pub funded_slot: u64,         // 8 bytes
+    
     /// Current state of the escrow
     pub state: EscrowState,       // 1 byte
     
@@ -31,8 +217,753 @@ pub struct Escrow {
 impl Escrow {
     /// Calculate space needed for Escrow account
     /// Discriminator (8) + buyer (32) + seller (32) + arbiter (33) 
-    /// + amount (8) + created_at (8) + timeout_period (8) + state (1) + bump (1)
-    pub const LEN: usize = 32 + 32 + 33 + 8 + 8 + 8 + 1 + 1;
+    /// + amount (8) + created_at (8) + timeout_period (8) + start_time (8)
+    /// + arbiter_fee_bps (2) + arbiter_decision (2) + reason_hash (32)
+    /// + vesting (1) + vested_claimed (8) + requires_terms (1) + terms_bound (1)
+    /// + terms_hash (32) + label (16) + funding_deadline (8) + funded_amount (8)
+    /// + appeal_deadline (8) + recurring (1) + periods_remaining (4)
+    /// + buyer_operator (33) + funded_at (8) + last_activity (8) + refund_policy (1)
+    /// + authorization_nonce (8) + dispute_opened_at (8) + soft_deadline (8)
+    /// + penalty_rate_bps_per_day (2) + dispute_state (1) + last_event_hash (32)
+    /// + referrer (33) + referral_fee_bps (2) + release_on_goal (1)
+    /// + accrued_arbiter_fee (8) + arbiter_window (8) + mint_receipt (1)
+    /// + receipt_mint (33) + seller_autoclaim_disabled (1) + depends_on (33)
+    /// + requires_arbiter (1) + dispute_fee_payer (33) + dispute_fee (8)
+    /// + expiry (8) + expiry_default (1) + release_voucher (33) + category (1)
+    /// + funded_slot (8) + state (1) + bump (1)
+    pub const LEN: usize = 32 + 32 + 33 + 8 + 8 + 8 + 8 + 2 + 2 + 32 + 1 + 8 + 1 + 1 + 32
+        + 16 + 8 + 8 + 8 + 1 + 4 + 33 + 8 + 8 + 1 + 8 + 8 + 8 + 2 + 1 + 32 + 33 + 2 + 1 + 8 + 8
+        + 1 + 33 + 1 + 33 + 1 + 33 + 8 + 8 + 1 + 33 + 1 + 8 + 1 + 1;
+
+    /// Whether `caller` (with an optional co-signing `counterparty`) may
+    /// trigger refund_to_buyer under this escrow's refund policy
+    ///
+    /// `buyer_side` is whoever holds the buyer's refund rights: the buyer, or
+    /// the current receipt holder on escrows that minted one.
+    pub fn may_refund(&self, caller: &Pubkey, counterparty: Option<Pubkey>, buyer_side: &Pubkey) -> bool {
+        let is_arbiter = self.arbiter == Some(*caller);
+        match self.refund_policy {
+            RefundPolicy::Any => *caller == self.seller || is_arbiter || caller == buyer_side,
+            RefundPolicy::SellerOrArbiter => *caller == self.seller || is_arbiter,
+            RefundPolicy::ArbiterOnly => is_arbiter,
+            RefundPolicy::MutualOnly => {
+                (caller == buyer_side && counterparty == Some(self.seller))
+                    || (*caller == self.seller && counterparty == Some(*buyer_side))
+            }
+        }
+    }
+
+
+    /// When the seller's self-claim timeout starts counting. Recurring escrows
+    /// restart `created_at` each period, hence the later of the two.
+    pub fn timeout_start(&self, from_funding: bool) -> i64 {
+        if from_funding {
+            self.created_at.max(self.funded_at)
+        } else {
+            self.created_at
+        }
+    }
+
+    /// When settle_expired may run (0 = never): the expiry window set at
+    /// creation, counted from the last activity instead, so a ping from
+    /// either party keeps the escrow alive
+    pub fn expires_at(&self) -> i64 {
+        if self.expiry == 0 {
+            return 0;
+        }
+        let window = self.expiry.saturating_sub(self.created_at);
+        self.last_activity.saturating_add(window)
+    }
+
+    /// Seconds after the timeout start before the seller can self-claim: the
+    /// timeout plus the arbiter's intervention window
+    pub fn seller_claim_delay(&self) -> i64 {
+        self.timeout_period.saturating_add(self.arbiter_window)
+    }
+
+    /// The arbiter that can actually act on this escrow. Passing the buyer
+    /// as arbiter means "no arbiter", so a buyer key collapses to None here.
+    pub fn effective_arbiter(&self) -> Option<Pubkey> {
+        self.arbiter.filter(|arbiter| *arbiter != self.buyer)
+    }
+
+    /// The buyer, or the operator it delegated funding and release to
+    pub fn is_buyer_or_operator(&self, key: &Pubkey) -> bool {
+        *key == self.buyer || self.buyer_operator == Some(*key)
+    }
+
+    /// Late-delivery penalty owed to the buyer at `now`, accruing per second
+    /// past the soft deadline and capped at `available`
+    pub fn penalty_at(&self, now: i64, available: u64) -> Result<u64> {
+        if self.soft_deadline == 0 || self.penalty_rate_bps_per_day == 0 || now <= self.soft_deadline {
+            return Ok(0);
+        }
+
+        let late = (now - self.soft_deadline) as u128;
+        let penalty = (self.amount as u128)
+            .checked_mul(self.penalty_rate_bps_per_day as u128)
+            .and_then(|v| v.checked_mul(late))
+            .ok_or(EscrowError::ArithmeticOverflow)?
+            / (MAX_BPS as u128 * 86_400);
+
+        Ok(penalty.min(available as u128) as u64)
+    }
+
+    /// Lamports the escrow is holding for the parties and the arbiter, on top
+    /// of its rent
+    pub fn held_amount(&self) -> Result<u64> {
+        let for_parties = match self.state {
+            EscrowState::Funded => self.locked_amount()?.saturating_sub(self.vested_claimed),
+            EscrowState::PartiallyFunded => self.funded_amount.saturating_sub(self.vested_claimed),
+            // Contributions sit in a crowdfunded escrow until funded or reclaimed
+            EscrowState::Created | EscrowState::Refunded if self.is_crowdfunded() => {
+                self.funded_amount
+            }
+            _ => 0,
+        };
+        for_parties
+            .checked_add(self.accrued_arbiter_fee)
+            .and_then(|held| held.checked_add(self.dispute_fee))
+            .ok_or(error!(EscrowError::ArithmeticOverflow))
+    }
+
+    /// Lamports locked for the periods still ahead, `amount` for one-off escrows
+    pub fn locked_amount(&self) -> Result<u64> {
+        self.amount
+            .checked_mul(self.periods_remaining as u64)
+            .ok_or(error!(EscrowError::ArithmeticOverflow))
+    }
+
+    /// Whether a release leaves the escrow Funded for another period
+    pub fn has_next_period(&self) -> bool {
+        self.recurring && self.periods_remaining > 1
+    }
+
+    /// Funded by several contributors through `contribute` rather than by the buyer
+    pub fn is_crowdfunded(&self) -> bool {
+        self.funding_deadline != 0
+    }
+
+    /// Total vested at `now`: amount * min(elapsed, period) / period
+    pub fn vested_amount(&self, now: i64) -> Result<u64> {
+        let elapsed = now
+            .saturating_sub(self.created_at)
+            .clamp(0, self.timeout_period);
+        let vested = (self.amount as u128)
+            .checked_mul(elapsed as u128)
+            .ok_or(EscrowError::ArithmeticOverflow)?
+            / self.timeout_period as u128;
+        Ok(vested as u64)
+    }
+
+    /// What claim_vested would pay the seller at `now`: vested so far minus
+    /// what was already claimed
+    pub fn vested_claimable(&self, now: i64) -> Result<u64> {
+        Ok(self.vested_amount(now)?.saturating_sub(self.vested_claimed))
+    }
+
+    /// Guard for everything that pays out of a funded escrow. Only `Funded`
+    /// passes, and not while an arbiter decision waits to execute; those get
+    /// their own error so the direct instructions can't be used to skip them.
+    pub fn require_funded(&self) -> Result<()> {
+        match (self.state, self.dispute_state) {
+            (EscrowState::Funded, DisputeState::Resolved) => err!(EscrowError::DecisionPending),
+            (EscrowState::Funded, DisputeState::UnderAppeal) => err!(EscrowError::AppealPending),
+            (EscrowState::Funded, _) => Ok(()),
+            _ => err!(EscrowError::EscrowNotFunded),
+        }
+    }
+
+    /// Whether an arbiter fee of `arbiter_fee_bps` plus the referral and
+    /// protocol fees stays within 100% of a payout
+    pub fn fees_within_total(&self, arbiter_fee_bps: u16, protocol_fee_bps: u16) -> bool {
+        arbiter_fee_bps as u32 + self.referral_fee_bps as u32 + protocol_fee_bps as u32
+            <= MAX_BPS as u32
+    }
+
+    /// Funded with an arbiter decision recorded but not yet executed
+    pub fn decision_pending(&self) -> bool {
+        self.state == EscrowState::Funded
+            && matches!(self.dispute_state, DisputeState::Resolved | DisputeState::UnderAppeal)
+    }
+
+    /// Advance the event hash chain: sha256(prev_hash || action || timestamp),
+    /// with the action as one byte and the timestamp as i64 little-endian.
+    /// Clients replaying the event stream can recompute and compare it.
+    /// Handlers that don't otherwise need the clock pass 0 when it's unavailable.
+    pub fn record_event(&mut self, action: EscrowAction, now: i64) {
+        self.last_event_hash = hashv(&[
+            &self.last_event_hash,
+            &[action as u8],
+            &now.to_le_bytes(),
+        ])
+        .to_bytes();
+    }
+
+    /// Move the dispute to `next`, rejecting anything its table doesn't allow
+    pub fn transition_dispute(&mut self, next: DisputeState) -> Result<()> {
+        require!(
+            self.dispute_state.can_transition_to(next),
+            EscrowError::InvalidState
+        );
+        self.dispute_state = next;
+        Ok(())
+    }
+
+    /// Move to `next`, rejecting anything the transition table doesn't allow
+    pub fn transition_to(&mut self, next: EscrowState) -> Result<()> {
+        require!(
+            self.state.can_transition_to(next),
+            EscrowError::InvalidState
+        );
+        self.state = next;
+        Ok(())
+    }
+}
+
+/// Preset escrow parameters, seeded by creator and template id
+#[account]
+pub struct Template {
+    /// Who created (and owns) the template
+    pub creator: Pubkey,              // 32 bytes
+    
+    /// Creator-chosen id, part of the PDA seeds
+    pub template_id: u64,             // 8 bytes
+    
+    /// Timeout copied into escrows created from this template
+    pub timeout_period: i64,          // 8 bytes
+    
+    /// Arbiter copied into escrows created from this template
+    pub arbiter: Option<Pubkey>,      // 1 + 32 = 33 bytes
+    
+    /// Arbiter fee copied into escrows created from this template
+    pub arbiter_fee_bps: u16,         // 2 bytes
+    
+    /// Bump seed for PDA derivation
+    pub bump: u8,                     // 1 byte
+}
+
+impl Template {
+    /// creator (32) + template_id (8) + timeout_period (8) + arbiter (33)
+    /// + arbiter_fee_bps (2) + bump (1)
+    pub const LEN: usize = 32 + 8 + 8 + 33 + 2 + 1;
+}
+
+/// Program-owned PDA collecting protocol fees until the config authority
+/// withdraws them to a treasury
+#[account]
+pub struct FeeVault {
+    /// Fees credited and not yet withdrawn, on top of the vault's rent
+    pub balance: u64,             // 8 bytes
+    
+    /// Every fee ever credited
+    pub total_collected: u64,     // 8 bytes
+    
+    /// Bump seed for PDA derivation
+    pub bump: u8,                 // 1 byte
+}
+
+impl FeeVault {
+    /// balance (8) + total_collected (8) + bump (1)
+    pub const LEN: usize = 8 + 8 + 1;
+
+    /// Track a fee that was just moved into the vault
+    pub fn credit(&mut self, fee: u64) -> Result<()> {
+        self.balance = self
+            .balance
+            .checked_add(fee)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        self.total_collected = self
+            .total_collected
+            .checked_add(fee)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+/// Compact, write-once summary of a settled escrow, kept after the escrow
+/// itself is closed. Keyed by the escrow address and its creation time, since
+/// the same buyer/seller pair can reuse the escrow address later.
+#[account]
+pub struct EscrowArchive {
+    /// Address the archived escrow lived at
+    pub escrow: Pubkey,           // 32 bytes
+    pub buyer: Pubkey,            // 32 bytes
+    pub seller: Pubkey,           // 32 bytes
+    pub arbiter: Option<Pubkey>,  // 1 + 32 = 33 bytes
+    
+    /// Agreed amount and what was actually funded
+    pub amount: u64,              // 8 bytes
+    pub funded_amount: u64,       // 8 bytes
+    
+    /// Released or Refunded
+    pub final_state: EscrowState, // 1 byte
+    
+    /// The arbiter's ruling, if the escrow was resolved by one
+    pub arbiter_decision: Option<ArbiterDecision>, // 1 + 1 = 2 bytes
+    
+    pub created_at: i64,          // 8 bytes
+    pub funded_at: i64,           // 8 bytes
+    pub archived_at: i64,         // 8 bytes
+    
+    pub label: [u8; 16],          // 16 bytes
+    
+    /// Final link of the escrow's event hash chain
+    pub last_event_hash: [u8; 32], // 32 bytes
+    
+    /// Bump seed for PDA derivation
+    pub bump: u8,                 // 1 byte
+}
+
+impl EscrowArchive {
+    /// escrow (32) + buyer (32) + seller (32) + arbiter (33) + amount (8)
+    /// + funded_amount (8) + final_state (1) + arbiter_decision (2)
+    /// + created_at (8) + funded_at (8) + archived_at (8) + label (16)
+    /// + last_event_hash (32) + bump (1)
+    pub const LEN: usize = 32 + 32 + 32 + 33 + 8 + 8 + 1 + 2 + 8 + 8 + 8 + 16 + 32 + 1;
+}
+
+/// Program-wide totals, a single PDA shared by every escrow
+#[account]
+pub struct GlobalStats {
+    /// Lamports currently locked in funded escrows
+    pub total_escrowed: u64,      // 8 bytes
+    
+    /// Number of escrows currently in the Funded state
+    pub active_escrows: u64,      // 8 bytes
+    
+    /// Lamports paid out as fees (arbiter, referral, protocol) since the stats
+    /// were created
+    pub total_fees_collected: u64, // 8 bytes
+    
+    /// Bump seed for PDA derivation
+    pub bump: u8,                 // 1 byte
+}
+
+impl GlobalStats {
+    /// total_escrowed (8) + active_escrows (8) + total_fees_collected (8) + bump (1)
+    pub const LEN: usize = 8 + 8 + 8 + 1;
+
+    /// Track a fee deducted from a payout
+    pub fn record_fee(&mut self, fee: u64) -> Result<()> {
+        self.total_fees_collected = self
+            .total_fees_collected
+            .checked_add(fee)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Track an escrow entering the Funded state
+    pub fn record_funded(&mut self, amount: u64) -> Result<()> {
+        self.total_escrowed = self
+            .total_escrowed
+            .checked_add(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        self.active_escrows = self
+            .active_escrows
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Track one period of a recurring escrow being paid out; it stays active
+    pub fn record_period_released(&mut self, amount: u64) -> Result<()> {
+        self.total_escrowed = self
+            .total_escrowed
+            .checked_sub(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Track a funded escrow being released or refunded
+    pub fn record_settled(&mut self, amount: u64) -> Result<()> {
+        self.total_escrowed = self
+            .total_escrowed
+            .checked_sub(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        self.active_escrows = self
+            .active_escrows
+            .checked_sub(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+/// Program-wide operator settings, a single PDA controlled by `authority`
+#[account]
+pub struct Config {
+    /// Key allowed to change these settings
+    pub authority: Pubkey,              // 32 bytes
+    
+    /// Escrows with an amount above this need the cosigner to release/refund
+    pub high_value_threshold: u64,      // 8 bytes
+    
+    /// Extra signer for high-value escrows (None disables the policy)
+    pub cosigner: Option<Pubkey>,       // 1 + 32 = 33 bytes
+    
+    /// Seconds a pair must wait after cancelling before re-initializing
+    pub reinit_cooldown: i64,           // 8 bytes
+    
+    /// Arbiter for escrows initialized without one (explicit arbiters take precedence)
+    pub default_arbiter: Option<Pubkey>, // 1 + 32 = 33 bytes
+    
+    /// Upper bound for fees set through update_arbiter_fee
+    pub max_arbiter_fee_bps: u16,       // 2 bytes
+    
+    /// Second-level arbiter that hears appeals of arbiter decisions
+    pub appeal_arbiter: Option<Pubkey>, // 1 + 32 = 33 bytes
+    
+    /// Seconds after an arbiter decision during which it can be appealed
+    pub appeal_window: i64,             // 8 bytes
+    
+    /// Whether the seller may cancel an unfunded escrow (the buyer always can)
+    pub seller_can_cancel_unfunded: bool, // 1 byte
+    
+    /// Start the seller's self-claim timeout at funding instead of creation
+    pub timeout_from_funding: bool,     // 1 byte
+    
+    /// Blocks new escrows; existing ones can still be funded and settled
+    pub winddown: bool,                 // 1 byte
+    
+    /// Rounding applied to every basis-point fee
+    pub fee_rounding: FeeRounding,      // 1 byte
+    
+    /// Most lamports a single release or refund may move out of an escrow
+    pub max_transfer_per_instruction: u64, // 8 bytes
+    
+    /// Stake an arbiter needs before it can be assigned to an escrow (0 = none)
+    pub min_arbiter_stake: u64,         // 8 bytes
+    
+    /// Protocol's cut of every release, credited to the fee vault
+    pub protocol_fee_bps: u16,          // 2 bytes
+    
+    /// Lamports a party pays into the escrow to open a dispute (0 = free)
+    pub dispute_fee: u64,               // 8 bytes
+    
+    /// Protocol fee discounts for fast releases, shortest window first;
+    /// unused slots have a zero window
+    pub fee_discount_tiers: [FeeDiscountTier; 3], // 3 * 10 = 30 bytes
+    
+    /// Slots after funding before the parties can release (0 = same slot is fine)
+    pub min_slots_before_release: u64, // 8 bytes
+    
+    /// Smallest partial payout claim_vested makes, unless it empties the
+    /// escrow (0 = any amount)
+    pub min_partial_release: u64,      // 8 bytes
+    
+    /// Bump seed for PDA derivation
+    pub bump: u8,                       // 1 byte
+}
+
+impl Config {
+    /// authority (32) + high_value_threshold (8) + cosigner (33)
+    /// + reinit_cooldown (8) + default_arbiter (33) + max_arbiter_fee_bps (2)
+    /// + appeal_arbiter (33) + appeal_window (8) + seller_can_cancel_unfunded (1)
+    /// + timeout_from_funding (1) + winddown (1) + fee_rounding (1)
+    /// + max_transfer_per_instruction (8) + min_arbiter_stake (8)
+    /// + protocol_fee_bps (2) + dispute_fee (8) + fee_discount_tiers (30)
+    /// + min_slots_before_release (8) + min_partial_release (8) + bump (1)
+    pub const LEN: usize = 32 + 8 + 33 + 8 + 33 + 2 + 33 + 8 + 1 + 1 + 1 + 1 + 8 + 8 + 2 + 8 + 30 + 8 + 8 + 1;
+
+    /// Share of the protocol fee waived for a release `elapsed` seconds after
+    /// funding: the first tier whose window still covers it, edge inclusive
+    pub fn fee_discount_bps_at(&self, elapsed: i64) -> u16 {
+        self.fee_discount_tiers
+            .iter()
+            .find(|tier| tier.max_elapsed > 0 && elapsed <= tier.max_elapsed)
+            .map_or(0, |tier| tier.discount_bps)
+    }
+
+    /// Whether resolving an escrow of `amount` needs the cosigner
+    pub fn requires_cosigner(&self, amount: u64) -> bool {
+        self.cosigner.is_some() && amount > self.high_value_threshold
+    }
+
+    /// Whether arbiter decisions wait out an appeal window before paying out
+    pub fn appeals_enabled(&self) -> bool {
+        self.appeal_arbiter.is_some() && self.appeal_window > 0
+    }
+
+    /// Safety valve against over-transfers: rejects moving more than the cap
+    pub fn require_within_transfer_cap(&self, amount: u64) -> Result<()> {
+        require!(
+            amount <= self.max_transfer_per_instruction,
+            EscrowError::TransferCapExceeded
+        );
+        Ok(())
+    }
+}
+
+/// NFT-for-SOL swap: the seller's NFT sits in a vault ATA owned by this PDA
+/// (seeded by the mint) until the buyer's lamports are in and the swap settles
+#[account]
+pub struct NftEscrow {
+    /// Pays `price` and receives the NFT
+    pub buyer: Pubkey,            // 32 bytes
+    
+    /// Deposits the NFT and receives `price`
+    pub seller: Pubkey,           // 32 bytes
+    
+    /// Mint of the escrowed NFT
+    pub mint: Pubkey,             // 32 bytes
+    
+    /// Lamports the buyer pays for the NFT
+    pub price: u64,               // 8 bytes
+    
+    /// Created until the buyer funds, then Funded
+    pub state: EscrowState,       // 1 byte
+    
+    /// Bump seed for PDA derivation
+    pub bump: u8,                 // 1 byte
+}
+
+impl NftEscrow {
+    /// buyer (32) + seller (32) + mint (32) + price (8) + state (1) + bump (1)
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 1 + 1;
+}
+
+/// One contributor's share of a crowdfunded escrow, seeded by escrow and contributor
+#[account]
+pub struct Contribution {
+    /// Escrow the lamports were contributed to
+    pub escrow: Pubkey,           // 32 bytes
+    
+    /// Who contributed and can reclaim the share
+    pub contributor: Pubkey,      // 32 bytes
+    
+    /// Lamports contributed so far
+    pub amount: u64,              // 8 bytes
+    
+    /// Bump seed for PDA derivation
+    pub bump: u8,                 // 1 byte
+}
+
+impl Contribution {
+    /// escrow (32) + contributor (32) + amount (8) + bump (1)
+    pub const LEN: usize = 32 + 32 + 8 + 1;
+}
+
+/// Per-seller track record, updated by release_to_seller when it is passed in
+#[account]
+pub struct SellerReputation {
+    /// Seller this record belongs to
+    pub seller: Pubkey,           // 32 bytes
+    
+    /// Escrows released to this seller in full
+    pub completed_deals: u64,     // 8 bytes
+    
+    /// Lamports the seller has received through releases
+    pub total_volume: u64,        // 8 bytes
+    
+    /// Bump seed for PDA derivation
+    pub bump: u8,                 // 1 byte
+}
+
+impl SellerReputation {
+    /// seller (32) + completed_deals (8) + total_volume (8) + bump (1)
+    pub const LEN: usize = 32 + 8 + 8 + 1;
+
+    /// Track a release; `completed` is false for a recurring period that
+    /// leaves the escrow Funded
+    pub fn record_release(&mut self, amount: u64, completed: bool) -> Result<()> {
+        self.total_volume = self
+            .total_volume
+            .checked_add(amount)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        if completed {
+            self.completed_deals = self
+                .completed_deals
+                .checked_add(1)
+                .ok_or(EscrowError::ArithmeticOverflow)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lamports an arbiter has locked up to be assignable, held by this PDA and
+/// slashable by the config authority
+#[account]
+pub struct ArbiterStake {
+    /// Arbiter this stake belongs to
+    pub arbiter: Pubkey,          // 32 bytes
+    
+    /// Lamports staked, on top of the account's rent
+    pub amount: u64,              // 8 bytes
+    
+    /// Bump seed for PDA derivation
+    pub bump: u8,                 // 1 byte
+}
+
+impl ArbiterStake {
+    /// arbiter (32) + amount (8) + bump (1)
+    pub const LEN: usize = 32 + 8 + 1;
+}
+
+/// Release schedule of an escrow funded in installments: each tranche goes to
+/// the seller once the buyer's installments cover it and its unlock time has
+/// passed. Tranches are funded in order, so tranche i counts as funded once
+/// `funded_amount` reaches the sum of tranches 0..=i.
+#[account]
+pub struct TrancheSchedule {
+    /// Escrow this schedule belongs to
+    pub escrow: Pubkey,           // 32 bytes
+    
+    /// `created_at` of the escrow when the schedule was set, so a schedule
+    /// from a closed or renegotiated escrow at the same address is stale
+    pub escrow_created_at: i64,   // 8 bytes
+    
+    /// At most MAX_TRANCHES, unlock times non-decreasing
+    pub tranches: Vec<Tranche>,   // 4 + 17 * 8 bytes
+    
+    /// Bump seed for PDA derivation
+    pub bump: u8,                 // 1 byte
+}
+
+impl TrancheSchedule {
+    /// escrow (32) + escrow_created_at (8) + tranches (4 + 17 * MAX_TRANCHES) + bump (1)
+    pub const LEN: usize = 32 + 8 + 4 + Tranche::LEN * MAX_TRANCHES + 1;
+
+    /// Whether the schedule still matches the escrow's current terms: set for
+    /// this incarnation of the escrow, adding up to its amount, and with its
+    /// claims matching what the escrow has paid out (a soft refund resets that)
+    pub fn is_current(&self, escrow: &Escrow) -> bool {
+        let sum = |released_only: bool| {
+            self.tranches
+                .iter()
+                .filter(|tranche| !released_only || tranche.released)
+                .try_fold(0u64, |total, tranche| total.checked_add(tranche.amount))
+        };
+        self.escrow_created_at == escrow.created_at
+            && sum(false) == Some(escrow.amount)
+            && sum(true) == Some(escrow.vested_claimed)
+    }
+
+    /// Installments needed before tranche `index` counts as funded
+    pub fn funded_through(&self, index: usize) -> Result<u64> {
+        self.tranches[..=index]
+            .iter()
+            .try_fold(0u64, |total, tranche| total.checked_add(tranche.amount))
+            .ok_or(error!(EscrowError::ArithmeticOverflow))
+    }
+}
+
+/// One scheduled payout of a TrancheSchedule
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Tranche {
+    /// Lamports paid to the seller
+    pub amount: u64,
+    
+    /// Earliest time the seller can claim it
+    pub unlock_time: i64,
+    
+    /// Already paid out by claim_tranche
+    pub released: bool,
+}
+
+impl Tranche {
+    /// amount (8) + unlock_time (8) + released (1)
+    pub const LEN: usize = 8 + 8 + 1;
+}
+
+/// Tranche as the parties propose it in set_tranche_schedule
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct TrancheTerms {
+    pub amount: u64,
+    pub unlock_time: i64,
+}
+
+/// An arbiter's open caseload: escrows assigned to it that it hasn't decided
+/// yet. initialize_escrow adds to it and the arbiter's decision removes from
+/// it, whenever the index is passed in.
+#[account]
+pub struct ArbiterIndex {
+    /// Arbiter this index belongs to
+    pub arbiter: Pubkey,          // 32 bytes
+    
+    /// Assigned escrows, at most MAX_ARBITER_INDEX_ENTRIES
+    pub escrows: Vec<Pubkey>,     // 4 + 32 * 16 bytes
+    
+    /// Assignments that arrived while the list was full and aren't listed
+    pub untracked: u32,           // 4 bytes
+    
+    /// Bump seed for PDA derivation
+    pub bump: u8,                 // 1 byte
+}
+
+impl ArbiterIndex {
+    /// arbiter (32) + escrows (4 + 32 * MAX_ARBITER_INDEX_ENTRIES) + untracked (4) + bump (1)
+    pub const LEN: usize = 32 + 4 + 32 * MAX_ARBITER_INDEX_ENTRIES + 4 + 1;
+
+    /// List `escrow`, or count it as untracked when the list is full rather
+    /// than fail the escrow's initialization. Returns whether it was listed.
+    pub fn track(&mut self, escrow: Pubkey) -> Result<bool> {
+        if self.escrows.contains(&escrow) {
+            return Ok(true);
+        }
+        if self.escrows.len() >= MAX_ARBITER_INDEX_ENTRIES {
+            self.untracked = self
+                .untracked
+                .checked_add(1)
+                .ok_or(EscrowError::ArithmeticOverflow)?;
+            return Ok(false);
+        }
+        self.escrows.push(escrow);
+        Ok(true)
+    }
+
+    /// Drop `escrow` from the list; a no-op for escrows that weren't listed
+    pub fn untrack(&mut self, escrow: &Pubkey) {
+        self.escrows.retain(|listed| listed != escrow);
+    }
+}
+
+/// Per-arbiter resolution record, updated by arbiter_resolve when passed in
+#[account]
+pub struct ArbiterStats {
+    /// Arbiter this record belongs to
+    pub arbiter: Pubkey,          // 32 bytes
+    
+    /// Decisions recorded through arbiter_resolve
+    pub total_resolved: u64,      // 8 bytes
+    
+    /// Sum of seconds from dispute start to decision, over all resolutions
+    pub total_latency: u64,       // 8 bytes
+    
+    /// Bump seed for PDA derivation
+    pub bump: u8,                 // 1 byte
+}
+
+impl ArbiterStats {
+    /// arbiter (32) + total_resolved (8) + total_latency (8) + bump (1)
+    pub const LEN: usize = 32 + 8 + 8 + 1;
+
+    /// Track one decision that took `latency` seconds
+    pub fn record_resolution(&mut self, latency: u64) -> Result<()> {
+        self.total_resolved = self
+            .total_resolved
+            .checked_add(1)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        self.total_latency = self
+            .total_latency
+            .checked_add(latency)
+            .ok_or(EscrowError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+/// Per buyer/seller pair record of the last cancel, used for the re-init cooldown
+#[account]
+pub struct CooldownMarker {
+    /// Unix timestamp of the most recent cancel for this pair
+    pub cancelled_at: i64,        // 8 bytes
+    
+    /// Bump seed for PDA derivation
+    pub bump: u8,                 // 1 byte
+}
+
+impl CooldownMarker {
+    /// cancelled_at (8) + bump (1)
+    pub const LEN: usize = 8 + 1;
 }
 
 /// Escrow lifecycle states
@@ -52,4 +983,420 @@ pub enum EscrowState {
     
     /// Escrow cancelled (before funding)
     Cancelled,
+    
+    /// Some installments paid, waiting for the rest before it counts as Funded
+    PartiallyFunded,
+}
+
+/// Why an unfunded escrow was cancelled, passed to cancel_escrow as a u8
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CancelReason {
+    /// The buyer no longer wants the deal
+    BuyerWithdrew,
+    
+    /// The seller turned the deal down
+    SellerDeclined,
+    
+    /// The deal lapsed before it was funded
+    Expired,
+    
+    /// Both parties agreed to call it off
+    MutualAgreement,
+}
+
+impl TryFrom<u8> for CancelReason {
+    type Error = anchor_lang::error::Error;
+
+    fn try_from(code: u8) -> Result<Self> {
+        match code {
+            0 => Ok(CancelReason::BuyerWithdrew),
+            1 => Ok(CancelReason::SellerDeclined),
+            2 => Ok(CancelReason::Expired),
+            3 => Ok(CancelReason::MutualAgreement),
+            _ => err!(EscrowError::InvalidCancelReason),
+        }
+    }
+}
+
+/// Kind of deal an escrow is for, passed to initialize_escrow as a u8
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum EscrowCategory {
+    /// Physical or digital goods
+    #[default]
+    Goods,
+    
+    /// Work delivered by the seller
+    Services,
+    
+    /// Temporary use of the seller's property
+    Rental,
+    
+    /// Anything else
+    Other,
+}
+
+impl TryFrom<u8> for EscrowCategory {
+    type Error = anchor_lang::error::Error;
+
+    fn try_from(code: u8) -> Result<Self> {
+        match code {
+            0 => Ok(EscrowCategory::Goods),
+            1 => Ok(EscrowCategory::Services),
+            2 => Ok(EscrowCategory::Rental),
+            3 => Ok(EscrowCategory::Other),
+            _ => err!(EscrowError::InvalidCategory),
+        }
+    }
+}
+
+/// Mutation codes hashed into `Escrow::last_event_hash`. The discriminants
+/// are part of the chain format, so only ever append new ones.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum EscrowAction {
+    Initialized = 0,
+    TermsBound = 1,
+    ArbiterFeeUpdated = 2,
+    ArbiterReplaced = 3,
+    DisputeOpened = 4,
+    ArbiterRenounced = 5,
+    OperatorSet = 6,
+    Pinged = 7,
+    Funded = 8,
+    InstallmentPaid = 9,
+    Contributed = 10,
+    ContributionReclaimed = 11,
+    Released = 12,
+    Refunded = 13,
+    ExcessWithdrawn = 14,
+    RentToppedUp = 15,
+    VestedClaimed = 16,
+    ArbiterResolved = 17,
+    Appealed = 18,
+    ResolutionFinalized = 19,
+    AppealResolved = 20,
+    SoftRefunded = 21,
+    GoalSettled = 22,
+    ArbiterFeeClaimed = 23,
+    ReceiptMinted = 24,
+    ExpirySettled = 25,
+    VoucherIssued = 26,
+    Reopened = 27,
+    TermsAmended = 28,
+    BumpRepaired = 29,
+    TrancheScheduleSet = 30,
+    TrancheClaimed = 31,
+    InstallmentsRefunded = 32,
+}
+
+/// Dispute progress, orthogonal to the funding lifecycle in `EscrowState`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DisputeState {
+    /// No dispute raised
+    #[default]
+    None,
+    
+    /// A party opened a dispute for the arbiter
+    Open,
+    
+    /// Decision appealed, payout frozen until the appeal arbiter decides
+    UnderAppeal,
+    
+    /// Arbiter decided; while still Funded the payout waits for the appeal window
+    Resolved,
+}
+
+/// Optional settings passed to initialize_escrow
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct EscrowOptions {
+    /// Unix timestamp before which funding is rejected (0 = immediately)
+    pub start_time: i64,
+    
+    /// Vest funds linearly to the seller over the timeout period
+    pub vesting: bool,
+    
+    /// Block funding until buyer and seller have called bind_terms
+    pub requires_terms: bool,
+    
+    /// Support reference stored on the escrow, opaque null-padded bytes
+    pub label: [u8; 16],
+    
+    /// Non-zero makes the escrow crowdfunded, contributions close at this timestamp
+    pub funding_deadline: i64,
+    
+    /// Subscription escrow, pre-funded for `period_count` periods of `amount`
+    pub recurring: bool,
+    
+    /// Number of periods for a recurring escrow (ignored otherwise)
+    pub period_count: u32,
+    
+    /// Who may call refund_to_buyer
+    pub refund_policy: RefundPolicy,
+    
+    /// Unix timestamp after which late releases are penalized (0 = never)
+    pub soft_deadline: i64,
+    
+    /// Share of `amount` returned to the buyer per day past the soft deadline
+    pub penalty_rate_bps_per_day: u16,
+    
+    /// Affiliate paid a cut of each release (None = no referral)
+    pub referrer: Option<Pubkey>,
+    
+    /// Referrer's cut of each release, in basis points
+    pub referral_fee_bps: u16,
+    
+    /// Joint purchase: settle_goal releases once contributions hit the target
+    /// (requires a funding_deadline)
+    pub release_on_goal: bool,
+    
+    /// Seconds after the timeout during which only the arbiter (not the
+    /// seller) can release; 0 lets the seller claim right at the timeout
+    pub arbiter_window: i64,
+    
+    /// Mint the buyer a 1-supply receipt token after funding. Whoever holds
+    /// it takes over the buyer's refund rights, making the position tradeable.
+    pub mint_receipt: bool,
+    
+    /// Fail init unless the buyer can already cover the escrowed amount plus
+    /// the funding transaction fee
+    pub check_buyer_balance: bool,
+    
+    /// Buyer-controlled release: the seller never self-claims, even after
+    /// the timeout
+    pub seller_autoclaim_disabled: bool,
+    
+    /// Chain this escrow behind another: release is blocked until that
+    /// escrow has released
+    pub depends_on: Option<Pubkey>,
+    
+    /// Refuse funding until an arbiter is assigned, so the buyer never pays
+    /// into an escrow without dispute protection
+    pub requires_arbiter: bool,
+    
+    /// Unix timestamp after which settle_expired can settle a still-funded
+    /// escrow to `expiry_default` (0 = never expires)
+    pub expiry: i64,
+    
+    /// Who an abandoned escrow settles to at the expiry
+    pub expiry_default: ExpiryDefault,
+    
+    /// Deal type as an `EscrowCategory` code
+    pub category: u8,
+}
+
+/// Party settle_expired pays once an escrow has expired
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ExpiryDefault {
+    /// Refund the buyer
+    #[default]
+    Buyer,
+    
+    /// Release to the seller
+    Seller,
+}
+
+/// One step of the fast-release protocol fee discount
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FeeDiscountTier {
+    /// Releases at most this many seconds after funding get the discount
+    pub max_elapsed: i64,
+    
+    /// Share of the protocol fee waived, in basis points
+    pub discount_bps: u16,
+}
+
+/// Rounding direction for basis-point fee math
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FeeRounding {
+    /// Truncate, in favor of the payee
+    #[default]
+    Down,
+    
+    /// Round any fraction up, in favor of the fee recipient
+    Up,
+    
+    /// Round to the nearest lamport, halves up
+    Nearest,
+}
+
+/// Who may trigger refund_to_buyer on an escrow
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum RefundPolicy {
+    /// Buyer, seller or arbiter
+    #[default]
+    Any,
+    
+    /// Seller or arbiter, the buyer can't refund itself
+    SellerOrArbiter,
+    
+    /// Only the arbiter
+    ArbiterOnly,
+    
+    /// Buyer and seller together, one as caller and one as counterparty
+    MutualOnly,
+}
+
+/// Result of simulate_release
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct ReleaseEligibility {
+    /// Whether release_to_seller would succeed for the given caller
+    pub allowed: bool,
+    
+    /// 0 when allowed, otherwise the error code release_to_seller would fail with
+    pub reason: u16,
+    
+    /// Lamports the seller would receive
+    pub releasable: u64,
+}
+
+/// Result of derive_escrow_address
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct EscrowAddress {
+    pub address: Pubkey,
+    pub bump: u8,
+}
+
+/// Snapshot of an escrow returned by describe_escrow
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct EscrowDescription {
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub arbiter: Option<Pubkey>,
+    pub amount: u64,
+    pub created_at: i64,
+    pub timeout_period: i64,
+    pub state: EscrowState,
+    pub category: EscrowCategory,
+    pub label: [u8; 16],
+    pub last_event_hash: [u8; 32],
+}
+
+/// Outcome chosen by the arbiter in arbiter_resolve
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ArbiterDecision {
+    /// Pay the escrowed funds to the seller
+    ReleaseToSeller,
+    
+    /// Return the escrowed funds to the buyer
+    RefundToBuyer,
+}
+
+impl EscrowState {
+    /// Central table of valid lifecycle transitions
+    pub fn can_transition_to(self, next: EscrowState) -> bool {
+        use EscrowState::*;
+
+        matches!(
+            (self, next),
+            (Created, Funded)
+                | (Created, Cancelled)
+                | (Funded, Released)
+                | (Funded, Refunded)
+                // Soft refund and renegotiation: funds go back but the
+                // agreement stays open
+                | (Funded, Created)
+                // Failed joint purchase: contributors reclaim their shares
+                | (Created, Refunded)
+                // Installments: paid in stages, Funded once the target is reached
+                | (Created, PartiallyFunded)
+                | (PartiallyFunded, Funded)
+                // Installments stopped: what was paid goes back to the buyer
+                | (PartiallyFunded, Refunded)
+        )
+    }
+}
+
+impl DisputeState {
+    /// Central table of valid dispute transitions
+    pub fn can_transition_to(self, next: DisputeState) -> bool {
+        use DisputeState::*;
+
+        matches!(
+            (self, next),
+            (None, Open)
+                // The arbiter may decide with or without a dispute being opened
+                | (None, Resolved)
+                | (Open, Resolved)
+                // Appeals: a pending decision is escalated, then decided again
+                | (Resolved, UnderAppeal)
+                | (UnderAppeal, Resolved)
+        )
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// An escrow with every field zeroed, as `init` leaves it before the
+    /// handler fills it in
+    pub(crate) fn zeroed_escrow() -> Escrow {
+        Escrow::deserialize(&mut &[0u8; Escrow::LEN][..]).unwrap()
+    }
+
+    fn funded_escrow(amount: u64) -> Escrow {
+        let mut escrow = zeroed_escrow();
+        escrow.amount = amount;
+        escrow.funded_amount = amount;
+        escrow.periods_remaining = 1;
+        escrow.state = EscrowState::Funded;
+        escrow
+    }
+
+    #[test]
+    fn expiry_window_counts_from_the_last_activity() {
+        let mut escrow = zeroed_escrow();
+        assert_eq!(escrow.expires_at(), 0);
+
+        escrow.created_at = 1_000;
+        escrow.last_activity = 1_000;
+        escrow.expiry = 1_600;
+        assert_eq!(escrow.expires_at(), 1_600);
+
+        escrow.last_activity = 1_400;
+        assert_eq!(escrow.expires_at(), 2_000);
+    }
+
+    #[test]
+    fn held_amount_excludes_claimed_vesting() {
+        let mut escrow = funded_escrow(1_000);
+        escrow.vesting = true;
+        escrow.vested_claimed = 400;
+
+        assert_eq!(escrow.held_amount().unwrap(), 600);
+    }
+
+    #[test]
+    fn held_amount_counts_paid_installments_less_claimed_tranches() {
+        let mut escrow = funded_escrow(1_000);
+        escrow.state = EscrowState::PartiallyFunded;
+        escrow.funded_amount = 700;
+        escrow.vested_claimed = 300;
+
+        assert_eq!(escrow.held_amount().unwrap(), 400);
+    }
+
+    #[test]
+    fn held_amount_counts_contributions_until_reclaimed() {
+        let mut escrow = zeroed_escrow();
+        escrow.amount = 1_000;
+        escrow.periods_remaining = 1;
+        escrow.funding_deadline = 1;
+        escrow.funded_amount = 250;
+
+        assert_eq!(escrow.held_amount().unwrap(), 250);
+        escrow.state = EscrowState::Refunded;
+        assert_eq!(escrow.held_amount().unwrap(), 250);
+    }
+
+    #[test]
+    fn held_amount_adds_arbiter_and_dispute_fees() {
+        let mut escrow = funded_escrow(1_000);
+        escrow.accrued_arbiter_fee = 20;
+        escrow.dispute_fee = 5;
+
+        assert_eq!(escrow.held_amount().unwrap(), 1_025);
+        escrow.state = EscrowState::Released;
+        assert_eq!(escrow.held_amount().unwrap(), 25);
+    }
 }
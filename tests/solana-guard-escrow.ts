@@ -1,8 +1,24 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
 import { SolanaGuardEscrow } from "../target/types/solana_guard_escrow";
-import { PublicKey, Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import {
+    PublicKey,
+    Keypair,
+    SystemProgram,
+    LAMPORTS_PER_SOL,
+    Ed25519Program,
+    SYSVAR_INSTRUCTIONS_PUBKEY,
+} from "@solana/web3.js";
+import {
+    createMint,
+    getAccount,
+    getAssociatedTokenAddressSync,
+    getOrCreateAssociatedTokenAccount,
+    mintTo,
+    transfer,
+} from "@solana/spl-token";
 import { assert, expect } from "chai";
+import { createHash } from "crypto";
 
 describe("SolanaGuard Escrow Protocol", () => {
     // Configure the client to use the local cluster
@@ -18,6 +34,15 @@ describe("SolanaGuard Escrow Protocol", () => {
     let escrowPda: PublicKey;
     let escrowBump: number;
 
+    const [globalStats] = PublicKey.findProgramAddressSync(
+        [Buffer.from("global_stats")],
+        program.programId
+    );
+    const [config] = PublicKey.findProgramAddressSync(
+        [Buffer.from("config")],
+        program.programId
+    );
+
     const escrowAmount = new BN(1 * LAMPORTS_PER_SOL); // 1 SOL
     const timeoutPeriod = new BN(60); // 60 seconds
 
@@ -34,12 +59,142 @@ describe("SolanaGuard Escrow Protocol", () => {
         );
         await provider.connection.confirmTransaction(signature);
 
+        // Program-wide stats PDA must exist before any escrow is funded
+        await program.methods
+            .initializeGlobalStats()
+            .accounts({
+                globalStats,
+                payer: provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+
+        // Provider wallet acts as the config authority for the whole suite
+        await program.methods
+            .initializeConfig()
+            .accounts({
+                config,
+                authority: provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+
         console.log("\n🎭 Test Setup Complete");
         console.log(`Buyer: ${buyer.publicKey.toString()}`);
         console.log(`Seller: ${seller.publicKey.toString()}`);
         console.log(`Arbiter: ${arbiter.publicKey.toString()}\n`);
     });
 
+    async function airdrop(pubkey: PublicKey, lamports: number) {
+        const sig = await provider.connection.requestAirdrop(pubkey, lamports);
+        await provider.connection.confirmTransaction(sig);
+    }
+
+    function findEscrowPda(buyerKey: PublicKey, sellerKey: PublicKey): PublicKey {
+        const [pda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("escrow"), buyerKey.toBuffer(), sellerKey.toBuffer()],
+            program.programId
+        );
+        return pda;
+    }
+
+    function findCooldownPda(buyerKey: PublicKey, sellerKey: PublicKey): PublicKey {
+        const [pda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("cooldown"), buyerKey.toBuffer(), sellerKey.toBuffer()],
+            program.programId
+        );
+        return pda;
+    }
+
+    // Default initialize_escrow options, with per-test overrides
+    function escrowOptions(overrides: Record<string, unknown> = {}) {
+        return {
+            startTime: new BN(0),
+            vesting: false,
+            requiresTerms: false,
+            label: Array(16).fill(0),
+            fundingDeadline: new BN(0),
+            recurring: false,
+            periodCount: 0,
+            refundPolicy: { any: {} },
+            softDeadline: new BN(0),
+            penaltyRateBpsPerDay: 0,
+            referrer: null,
+            referralFeeBps: 0,
+            releaseOnGoal: false,
+            arbiterWindow: new BN(0),
+            mintReceipt: false,
+            checkBuyerBalance: false,
+            sellerAutoclaimDisabled: false,
+            dependsOn: null,
+            requiresArbiter: false,
+            expiry: new BN(0),
+            expiryDefault: { buyer: {} },
+            category: 0,
+            ...overrides,
+        };
+    }
+
+    async function chainTime(): Promise<number> {
+        const slot = await provider.connection.getSlot();
+        return (await provider.connection.getBlockTime(slot)) as number;
+    }
+
+    const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+    // Initializes an escrow between freshly generated parties. Without an
+    // explicit arbiter the buyer's key is passed, which means "no arbiter".
+    async function setupEscrow(opts: {
+        amount?: BN;
+        timeoutPeriod?: BN;
+        arbiter?: PublicKey;
+        arbiterStake?: PublicKey;
+        arbiterIndex?: PublicKey;
+        arbiterSigner?: Keypair;
+        options?: Record<string, unknown>;
+    } = {}) {
+        const escrowBuyer = Keypair.generate();
+        const escrowSeller = Keypair.generate();
+        await airdrop(escrowBuyer.publicKey, 3 * LAMPORTS_PER_SOL);
+
+        const escrow = findEscrowPda(escrowBuyer.publicKey, escrowSeller.publicKey);
+
+        await program.methods
+            .initializeEscrow(
+                opts.amount ?? new BN(0.5 * LAMPORTS_PER_SOL),
+                opts.timeoutPeriod ?? new BN(60),
+                escrowOptions(opts.options)
+            )
+            .accounts({
+                escrow,
+                buyer: escrowBuyer.publicKey,
+                seller: escrowSeller.publicKey,
+                arbiter: opts.arbiter ?? escrowBuyer.publicKey,
+                cooldown: findCooldownPda(escrowBuyer.publicKey, escrowSeller.publicKey),
+                config,
+                arbiterStake: opts.arbiterStake ?? null,
+                arbiterIndex: opts.arbiterIndex ?? null,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers(opts.arbiterSigner ? [escrowBuyer, opts.arbiterSigner] : [escrowBuyer])
+            .rpc();
+
+        return { buyer: escrowBuyer, seller: escrowSeller, escrow };
+    }
+
+    async function fundEscrow(escrow: PublicKey, escrowBuyer: Keypair) {
+        await program.methods
+            .fundEscrow()
+            .accounts({
+                escrow,
+                globalStats,
+                buyer: escrowBuyer.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([escrowBuyer])
+            .rpc();
+    }
+
     describe("Escrow Initialization", () => {
         it("Successfully initializes an escrow", async () => {
             [escrowPda, escrowBump] = PublicKey.findProgramAddressSync(
@@ -52,12 +207,16 @@ describe("SolanaGuard Escrow Protocol", () => {
             );
 
             await program.methods
-                .initializeEscrow(escrowAmount, timeoutPeriod)
+                .initializeEscrow(escrowAmount, timeoutPeriod, escrowOptions())
                 .accounts({
                     escrow: escrowPda,
                     buyer: buyer.publicKey,
                     seller: seller.publicKey,
                     arbiter: arbiter.publicKey,
+                    cooldown: findCooldownPda(buyer.publicKey, seller.publicKey),
+                    config,
+                    arbiterStake: null,
+                    arbiterIndex: null,
                     systemProgram: SystemProgram.programId,
                 })
                 .signers([buyer])
@@ -97,12 +256,16 @@ describe("SolanaGuard Escrow Protocol", () => {
 
             try {
                 await program.methods
-                    .initializeEscrow(new BN(0), timeoutPeriod)
+                    .initializeEscrow(new BN(0), timeoutPeriod, escrowOptions())
                     .accounts({
                         escrow: escrowPda2,
                         buyer: buyer2.publicKey,
                         seller: seller2.publicKey,
                         arbiter: buyer2.publicKey,
+                        cooldown: findCooldownPda(buyer2.publicKey, seller2.publicKey),
+                        config,
+                        arbiterStake: null,
+                        arbiterIndex: null,
                         systemProgram: SystemProgram.programId,
                     })
                     .signers([buyer2])
@@ -136,12 +299,16 @@ describe("SolanaGuard Escrow Protocol", () => {
 
             try {
                 await program.methods
-                    .initializeEscrow(escrowAmount, new BN(0))
+                    .initializeEscrow(escrowAmount, new BN(0), escrowOptions())
                     .accounts({
                         escrow: escrowPda2,
                         buyer: buyer2.publicKey,
                         seller: seller2.publicKey,
                         arbiter: buyer2.publicKey,
+                        cooldown: findCooldownPda(buyer2.publicKey, seller2.publicKey),
+                        config,
+                        arbiterStake: null,
+                        arbiterIndex: null,
                         systemProgram: SystemProgram.programId,
                     })
                     .signers([buyer2])
@@ -153,6 +320,44 @@ describe("SolanaGuard Escrow Protocol", () => {
                 console.log("✅ Correctly rejected zero timeout");
             }
         });
+
+        it("Accepts a timeout exactly at the 60 second floor", async () => {
+            const { escrow } = await setupEscrow({ timeoutPeriod: new BN(60) });
+            const escrowAccount = await program.account.escrow.fetch(escrow);
+            assert.ok(escrowAccount.timeoutPeriod.eqn(60));
+            console.log("✅ Timeout at the floor accepted");
+        });
+
+        it("Rejects a timeout below the 60 second floor", async () => {
+            try {
+                await setupEscrow({ timeoutPeriod: new BN(59) });
+                assert.fail("Should have failed with a timeout below the floor");
+            } catch (error) {
+                expect(error.toString()).to.include("TimeoutTooShort");
+                console.log("✅ Timeout below the floor rejected");
+            }
+        });
+
+        it("Rejects an amount the buyer can't cover when the balance check is on", async () => {
+            // setupEscrow airdrops the buyer 3 SOL
+            try {
+                await setupEscrow({
+                    amount: new BN(3 * LAMPORTS_PER_SOL),
+                    options: { checkBuyerBalance: true },
+                });
+                assert.fail("Should have failed the balance pre-flight");
+            } catch (error) {
+                expect(error.toString()).to.include("InsufficientBuyerBalance");
+            }
+
+            const { escrow } = await setupEscrow({
+                amount: new BN(2 * LAMPORTS_PER_SOL),
+                options: { checkBuyerBalance: true },
+            });
+            const escrowAccount = await program.account.escrow.fetch(escrow);
+            expect(escrowAccount.state).to.deep.equal({ created: {} });
+            console.log("✅ Buyer balance checked at init");
+        });
     });
 
     describe("Escrow Funding", () => {
@@ -164,6 +369,7 @@ describe("SolanaGuard Escrow Protocol", () => {
                 .fundEscrow()
                 .accounts({
                     escrow: escrowPda,
+                    globalStats,
                     buyer: buyer.publicKey,
                     systemProgram: SystemProgram.programId,
                 })
@@ -184,23 +390,49 @@ describe("SolanaGuard Escrow Protocol", () => {
             console.log(`   Escrow balance: ${escrowBalanceAfter / LAMPORTS_PER_SOL} SOL`);
         });
 
-        it("Fails to fund already funded escrow", async () => {
-            try {
-                await program.methods
-                    .fundEscrow()
-                    .accounts({
-                        escrow: escrowPda,
-                        buyer: buyer.publicKey,
-                        systemProgram: SystemProgram.programId,
+        it("Treats funding an already funded escrow as a no-op", async () => {
+            const balanceBefore = await provider.connection.getBalance(escrowPda);
+
+            await program.methods
+                .fundEscrow()
+                .accounts({
+                    escrow: escrowPda,
+                    globalStats,
+                    buyer: buyer.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([buyer])
+                .rpc();
+
+            const balanceAfter = await provider.connection.getBalance(escrowPda);
+            assert.equal(balanceAfter, balanceBefore);
+            const account = await program.account.escrow.fetch(escrowPda);
+            assert.deepEqual(account.state, { funded: {} });
+            console.log("✅ Retried funding did not double-fund");
+        });
+
+        it("Tops up only the shortfall of a partially funded escrow", async () => {
+            const amount = new BN(0.5 * LAMPORTS_PER_SOL);
+            const { buyer: b, escrow } = await setupEscrow({ amount });
+
+            // A stray deposit covers part of the amount before funding lands
+            const partial = 0.2 * LAMPORTS_PER_SOL;
+            await provider.sendAndConfirm(
+                new anchor.web3.Transaction().add(
+                    SystemProgram.transfer({
+                        fromPubkey: provider.wallet.publicKey,
+                        toPubkey: escrow,
+                        lamports: partial,
                     })
-                    .signers([buyer])
-                    .rpc();
+                )
+            );
 
-                assert.fail("Should have failed on double funding");
-            } catch (error) {
-                expect(error.toString()).to.include("InvalidState");
-                console.log("✅ Correctly rejected double funding");
-            }
+            const escrowBefore = await provider.connection.getBalance(escrow);
+            await fundEscrow(escrow, b);
+            const escrowAfter = await provider.connection.getBalance(escrow);
+
+            assert.equal(escrowAfter - escrowBefore, amount.toNumber() - partial);
+            console.log("✅ Funding topped up only the shortfall");
         });
     });
 
@@ -229,12 +461,16 @@ describe("SolanaGuard Escrow Protocol", () => {
             );
 
             await program.methods
-                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60))
+                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60), escrowOptions())
                 .accounts({
                     escrow: newEscrow,
                     buyer: newBuyer.publicKey,
                     seller: newSeller.publicKey,
                     arbiter: newBuyer.publicKey,
+                    cooldown: findCooldownPda(newBuyer.publicKey, newSeller.publicKey),
+                    config,
+                    arbiterStake: null,
+                    arbiterIndex: null,
                     systemProgram: SystemProgram.programId,
                 })
                 .signers([newBuyer])
@@ -244,6 +480,7 @@ describe("SolanaGuard Escrow Protocol", () => {
                 .fundEscrow()
                 .accounts({
                     escrow: newEscrow,
+                    globalStats,
                     buyer: newBuyer.publicKey,
                     systemProgram: SystemProgram.programId,
                 })
@@ -255,9 +492,17 @@ describe("SolanaGuard Escrow Protocol", () => {
             const sellerBalanceBefore = await provider.connection.getBalance(newSeller.publicKey);
 
             await program.methods
-                .releaseToSeller()
+                .releaseToSeller(null)
                 .accounts({
                     escrow: newEscrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
                     seller: newSeller.publicKey,
                     caller: newBuyer.publicKey,
                 })
@@ -295,12 +540,16 @@ describe("SolanaGuard Escrow Protocol", () => {
             );
 
             await program.methods
-                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60))
+                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60), escrowOptions())
                 .accounts({
                     escrow: newEscrow2,
                     buyer: newBuyer2.publicKey,
                     seller: newSeller2.publicKey,
                     arbiter: newArbiter2.publicKey,
+                    cooldown: findCooldownPda(newBuyer2.publicKey, newSeller2.publicKey),
+                    config,
+                    arbiterStake: null,
+                    arbiterIndex: null,
                     systemProgram: SystemProgram.programId,
                 })
                 .signers([newBuyer2])
@@ -310,6 +559,7 @@ describe("SolanaGuard Escrow Protocol", () => {
                 .fundEscrow()
                 .accounts({
                     escrow: newEscrow2,
+                    globalStats,
                     buyer: newBuyer2.publicKey,
                     systemProgram: SystemProgram.programId,
                 })
@@ -319,9 +569,17 @@ describe("SolanaGuard Escrow Protocol", () => {
             const sellerBalanceBefore = await provider.connection.getBalance(newSeller2.publicKey);
 
             await program.methods
-                .releaseToSeller()
+                .releaseToSeller(null)
                 .accounts({
                     escrow: newEscrow2,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
                     seller: newSeller2.publicKey,
                     caller: newArbiter2.publicKey,
                 })
@@ -360,12 +618,16 @@ describe("SolanaGuard Escrow Protocol", () => {
             );
 
             await program.methods
-                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60))
+                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60), escrowOptions())
                 .accounts({
                     escrow: refundEscrow,
                     buyer: refundBuyer.publicKey,
                     seller: refundSeller.publicKey,
                     arbiter: refundBuyer.publicKey,
+                    cooldown: findCooldownPda(refundBuyer.publicKey, refundSeller.publicKey),
+                    config,
+                    arbiterStake: null,
+                    arbiterIndex: null,
                     systemProgram: SystemProgram.programId,
                 })
                 .signers([refundBuyer])
@@ -375,6 +637,7 @@ describe("SolanaGuard Escrow Protocol", () => {
                 .fundEscrow()
                 .accounts({
                     escrow: refundEscrow,
+                    globalStats,
                     buyer: refundBuyer.publicKey,
                     systemProgram: SystemProgram.programId,
                 })
@@ -389,7 +652,13 @@ describe("SolanaGuard Escrow Protocol", () => {
                 .refundToBuyer()
                 .accounts({
                     escrow: refundEscrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    counterparty: null,
+                    receipt: null,
                     buyer: refundBuyer.publicKey,
+                    seller: refundSeller.publicKey,
                     caller: refundSeller.publicKey,
                 })
                 .signers([refundSeller])
@@ -427,23 +696,30 @@ describe("SolanaGuard Escrow Protocol", () => {
             );
 
             await program.methods
-                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60))
+                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60), escrowOptions())
                 .accounts({
                     escrow: cancelEscrow,
                     buyer: cancelBuyer.publicKey,
                     seller: cancelSeller.publicKey,
                     arbiter: cancelBuyer.publicKey,
+                    cooldown: findCooldownPda(cancelBuyer.publicKey, cancelSeller.publicKey),
+                    config,
+                    arbiterStake: null,
+                    arbiterIndex: null,
                     systemProgram: SystemProgram.programId,
                 })
                 .signers([cancelBuyer])
                 .rpc();
 
             await program.methods
-                .cancelEscrow()
+                .cancelEscrow(0)
                 .accounts({
                     escrow: cancelEscrow,
                     buyer: cancelBuyer.publicKey,
+                    cooldown: findCooldownPda(cancelBuyer.publicKey, cancelSeller.publicKey),
+                    config,
                     caller: cancelBuyer.publicKey,
+                    systemProgram: SystemProgram.programId,
                 })
                 .signers([cancelBuyer])
                 .rpc();
@@ -478,12 +754,16 @@ describe("SolanaGuard Escrow Protocol", () => {
             );
 
             await program.methods
-                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60))
+                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60), escrowOptions())
                 .accounts({
                     escrow: cancelEscrow2,
                     buyer: cancelBuyer2.publicKey,
                     seller: cancelSeller2.publicKey,
                     arbiter: cancelBuyer2.publicKey,
+                    cooldown: findCooldownPda(cancelBuyer2.publicKey, cancelSeller2.publicKey),
+                    config,
+                    arbiterStake: null,
+                    arbiterIndex: null,
                     systemProgram: SystemProgram.programId,
                 })
                 .signers([cancelBuyer2])
@@ -493,6 +773,7 @@ describe("SolanaGuard Escrow Protocol", () => {
                 .fundEscrow()
                 .accounts({
                     escrow: cancelEscrow2,
+                    globalStats,
                     buyer: cancelBuyer2.publicKey,
                     systemProgram: SystemProgram.programId,
                 })
@@ -501,11 +782,14 @@ describe("SolanaGuard Escrow Protocol", () => {
 
             try {
                 await program.methods
-                    .cancelEscrow()
+                    .cancelEscrow(0)
                     .accounts({
                         escrow: cancelEscrow2,
                         buyer: cancelBuyer2.publicKey,
+                        cooldown: findCooldownPda(cancelBuyer2.publicKey, cancelSeller2.publicKey),
+                        config,
                         caller: cancelBuyer2.publicKey,
+                        systemProgram: SystemProgram.programId,
                     })
                     .signers([cancelBuyer2])
                     .rpc();
@@ -516,6 +800,6334 @@ describe("SolanaGuard Escrow Protocol", () => {
                 console.log("✅ Correctly rejected cancellation of funded escrow");
             }
         });
+
+        const cancelWithReason = (escrow: PublicKey, b: Keypair, sl: PublicKey, reasonCode: number) =>
+            program.methods
+                .cancelEscrow(reasonCode)
+                .accounts({
+                    escrow,
+                    buyer: b.publicKey,
+                    cooldown: findCooldownPda(b.publicKey, sl),
+                    config,
+                    caller: b.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b])
+                .rpc({ commitment: "confirmed" });
+
+        it("Surfaces the cancel reason in the EscrowCancelled event", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+
+            const signature = await cancelWithReason(escrow, b, sl.publicKey, 2);
+            const tx = await provider.connection.getTransaction(signature, { commitment: "confirmed" });
+            const parser = new anchor.EventParser(program.programId, program.coder);
+            const events = Array.from(parser.parseLogs(tx.meta.logMessages));
+            const cancelled = events.find((e) => e.name === "escrowCancelled");
+
+            assert.ok(cancelled);
+            expect(cancelled.data.reason).to.deep.equal({ expired: {} });
+            expect(cancelled.data.cancelledBy.toString()).to.equal(b.publicKey.toString());
+            console.log("✅ Cancel reason emitted");
+        });
+
+        it("Rejects an unknown cancel reason code", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+
+            try {
+                await cancelWithReason(escrow, b, sl.publicKey, 4);
+                assert.fail("Should reject an unknown reason code");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidCancelReason");
+                console.log("✅ Unknown cancel reason rejected");
+            }
+        });
+    });
+
+    describe("Scheduled Escrows", () => {
+        it("Rejects funding before the start time and accepts it after", async () => {
+            const schedBuyer = Keypair.generate();
+            const schedSeller = Keypair.generate();
+            await airdrop(schedBuyer.publicKey, 2 * LAMPORTS_PER_SOL);
+
+            const schedEscrow = findEscrowPda(schedBuyer.publicKey, schedSeller.publicKey);
+            const startTime = new BN((await chainTime()) + 4);
+
+            await program.methods
+                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60), escrowOptions({ startTime }))
+                .accounts({
+                    escrow: schedEscrow,
+                    buyer: schedBuyer.publicKey,
+                    seller: schedSeller.publicKey,
+                    arbiter: schedBuyer.publicKey,
+                    cooldown: findCooldownPda(schedBuyer.publicKey, schedSeller.publicKey),
+                    config,
+                    arbiterStake: null,
+                    arbiterIndex: null,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([schedBuyer])
+                .rpc();
+
+            try {
+                await program.methods
+                    .fundEscrow()
+                    .accounts({
+                        escrow: schedEscrow,
+                        globalStats,
+                        buyer: schedBuyer.publicKey,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([schedBuyer])
+                    .rpc();
+
+                assert.fail("Should not fund before start time");
+            } catch (error) {
+                expect(error.toString()).to.include("NotYetActive");
+            }
+
+            await sleep(6000);
+
+            await program.methods
+                .fundEscrow()
+                .accounts({
+                    escrow: schedEscrow,
+                    globalStats,
+                    buyer: schedBuyer.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([schedBuyer])
+                .rpc();
+
+            const escrowAccount = await program.account.escrow.fetch(schedEscrow);
+            assert.ok(escrowAccount.startTime.eq(startTime));
+            assert.equal(escrowAccount.state.funded !== undefined, true);
+            console.log("✅ Scheduled escrow only fundable after start time");
+        });
+
+        it("Fails to initialize with a negative start time", async () => {
+            const schedBuyer = Keypair.generate();
+            const schedSeller = Keypair.generate();
+            await airdrop(schedBuyer.publicKey, 2 * LAMPORTS_PER_SOL);
+
+            try {
+                await program.methods
+                    .initializeEscrow(
+                        new BN(0.5 * LAMPORTS_PER_SOL),
+                        new BN(60),
+                        escrowOptions({ startTime: new BN(-1) })
+                    )
+                    .accounts({
+                        escrow: findEscrowPda(schedBuyer.publicKey, schedSeller.publicKey),
+                        buyer: schedBuyer.publicKey,
+                        seller: schedSeller.publicKey,
+                        arbiter: schedBuyer.publicKey,
+                        cooldown: findCooldownPda(schedBuyer.publicKey, schedSeller.publicKey),
+                        config,
+                        arbiterStake: null,
+                        arbiterIndex: null,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([schedBuyer])
+                    .rpc();
+
+                assert.fail("Should have failed with negative start time");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidStartTime");
+                console.log("✅ Correctly rejected negative start time");
+            }
+        });
+
+        it("Rejects a start time at or after the funding deadline or expiry", async () => {
+            const now = await chainTime();
+            const cases = [
+                { startTime: new BN(now + 7200), fundingDeadline: new BN(now + 3600) },
+                { startTime: new BN(now + 7200), expiry: new BN(now + 7200) },
+            ];
+
+            for (const options of cases) {
+                try {
+                    await setupEscrow({ options });
+                    assert.fail("Start time must come before the deadline");
+                } catch (error) {
+                    expect(error.toString()).to.include("InvalidTimeout");
+                }
+            }
+            console.log("✅ Start time after the deadline rejected");
+        });
+    });
+
+    describe("Global Stats", () => {
+        it("Tracks escrowed lamports across fund and release", async () => {
+            const statsBuyer = Keypair.generate();
+            const statsSeller = Keypair.generate();
+            await airdrop(statsBuyer.publicKey, 2 * LAMPORTS_PER_SOL);
+
+            const statsEscrow = findEscrowPda(statsBuyer.publicKey, statsSeller.publicKey);
+            const amount = new BN(0.5 * LAMPORTS_PER_SOL);
+
+            await program.methods
+                .initializeEscrow(amount, new BN(60), escrowOptions())
+                .accounts({
+                    escrow: statsEscrow,
+                    buyer: statsBuyer.publicKey,
+                    seller: statsSeller.publicKey,
+                    arbiter: statsBuyer.publicKey,
+                    cooldown: findCooldownPda(statsBuyer.publicKey, statsSeller.publicKey),
+                    config,
+                    arbiterStake: null,
+                    arbiterIndex: null,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([statsBuyer])
+                .rpc();
+
+            const before = await program.methods.getGlobalStats().accounts({ globalStats }).view();
+
+            await program.methods
+                .fundEscrow()
+                .accounts({
+                    escrow: statsEscrow,
+                    globalStats,
+                    buyer: statsBuyer.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([statsBuyer])
+                .rpc();
+
+            const funded = await program.methods.getGlobalStats().accounts({ globalStats }).view();
+            assert.ok(funded.totalEscrowed.eq(before.totalEscrowed.add(amount)));
+            assert.ok(funded.activeEscrows.eq(before.activeEscrows.addn(1)));
+
+            await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow: statsEscrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: statsSeller.publicKey,
+                    caller: statsBuyer.publicKey,
+                })
+                .signers([statsBuyer])
+                .rpc();
+
+            const released = await program.methods.getGlobalStats().accounts({ globalStats }).view();
+            assert.ok(released.totalEscrowed.eq(before.totalEscrowed));
+            assert.ok(released.activeEscrows.eq(before.activeEscrows));
+            console.log("✅ Global stats follow the escrow lifecycle");
+        });
+    });
+
+    describe("Config Health Check", () => {
+        const getConfig = () => program.methods.getConfig().accounts({ config }).view();
+
+        it("Returns every config field as stored", async () => {
+            const reported = await getConfig();
+            const stored = await program.account.config.fetch(config);
+
+            expect(Object.keys(reported)).to.have.members(Object.keys(stored));
+            expect(JSON.stringify(reported)).to.equal(JSON.stringify(stored));
+            assert.ok(reported.authority.equals(provider.wallet.publicKey));
+            console.log("✅ get_config round-trips the config account");
+        });
+
+        it("Reflects config updates", async () => {
+            const fee = new BN(12_345);
+            const previous = (await getConfig()).disputeFee;
+
+            await program.methods
+                .setDisputeFee(fee)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+            try {
+                const reported = await getConfig();
+                assert.ok(reported.disputeFee.eq(fee));
+                expect(JSON.stringify(reported)).to.equal(
+                    JSON.stringify(await program.account.config.fetch(config))
+                );
+            } finally {
+                await program.methods
+                    .setDisputeFee(previous)
+                    .accounts({ config, authority: provider.wallet.publicKey })
+                    .rpc();
+            }
+            console.log("✅ get_config follows set_* updates");
+        });
+    });
+
+    describe("Delivery Confirmation", () => {
+        const confirmDelivery = (escrow: PublicKey, sl: PublicKey, caller: Keypair) =>
+            program.methods
+                .confirmDelivery()
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl,
+                    caller: caller.publicKey,
+                })
+                .signers([caller])
+                .rpc({ commitment: "confirmed" });
+
+        it("Releases to the seller and emits DeliveryConfirmed", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            const sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            const signature = await confirmDelivery(escrow, sl.publicKey, b);
+
+            const account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { released: {} });
+            assert.ok((await provider.connection.getBalance(sl.publicKey)) > sellerBefore);
+
+            const tx = await provider.connection.getTransaction(signature, { commitment: "confirmed" });
+            const parser = new anchor.EventParser(program.programId, program.coder);
+            const names = Array.from(parser.parseLogs(tx.meta.logMessages)).map((e) => e.name);
+            expect(names).to.include("deliveryConfirmed");
+            expect(names).to.include("escrowReleased");
+
+            console.log("✅ Delivery confirmed and funds released");
+        });
+
+        it("Rejects a confirmation from the seller", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await confirmDelivery(escrow, sl.publicKey, sl);
+                assert.fail("Only the buyer may confirm delivery");
+            } catch (error) {
+                expect(error.toString()).to.include("UnauthorizedOperation");
+                console.log("✅ Seller confirmation rejected");
+            }
+        });
+
+        it("Rejects a confirmation before funding", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+
+            try {
+                await confirmDelivery(escrow, sl.publicKey, b);
+                assert.fail("Should not confirm an unfunded escrow");
+            } catch (error) {
+                expect(error.toString()).to.include("EscrowNotFunded");
+                console.log("✅ Unfunded confirmation rejected");
+            }
+        });
+    });
+
+    describe("Owner Checks", () => {
+        it("Rejects a wallet passed as the escrow to release", async () => {
+            const { buyer: b, seller: sl } = await setupEscrow();
+
+            try {
+                await program.methods
+                    .releaseToSeller(null)
+                    .accounts({
+                        escrow: b.publicKey,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: sl.publicKey,
+                        caller: b.publicKey,
+                    })
+                    .signers([b])
+                    .rpc();
+                assert.fail("Should reject an escrow not owned by the program");
+            } catch (error) {
+                expect(error.toString()).to.include("AccountOwnedByWrongProgram");
+                console.log("✅ Foreign-owned escrow rejected by release");
+            }
+        });
+
+        it("Rejects a foreign-owned account in a batch fund", async () => {
+            const { buyer: b } = await setupEscrow();
+
+            try {
+                await program.methods
+                    .fundEscrowsBatch([new BN(0.5 * LAMPORTS_PER_SOL)])
+                    .accounts({
+                        buyer: b.publicKey,
+                        globalStats,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .remainingAccounts([{ pubkey: provider.wallet.publicKey, isSigner: false, isWritable: true }])
+                    .signers([b])
+                    .rpc();
+                assert.fail("Should reject an escrow not owned by the program");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidAccountOwner");
+                console.log("✅ Foreign-owned escrow rejected by batch fund");
+            }
+        });
+    });
+
+    describe("Referral Fee", () => {
+        const amount = new BN(LAMPORTS_PER_SOL);
+
+        async function releaseTo(escrow: PublicKey, b: Keypair, sl: Keypair, referrer: PublicKey | null) {
+            await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl.publicKey,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+        }
+
+        it("Pays the referrer its cut on release", async () => {
+            const referrer = Keypair.generate();
+            await airdrop(referrer.publicKey, LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount,
+                options: { referrer: referrer.publicKey, referralFeeBps: 500 },
+            });
+            await fundEscrow(escrow, b);
+
+            const referrerBefore = await provider.connection.getBalance(referrer.publicKey);
+            const sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            await releaseTo(escrow, b, sl, referrer.publicKey);
+
+            const referral = (await provider.connection.getBalance(referrer.publicKey)) - referrerBefore;
+            const paid = (await provider.connection.getBalance(sl.publicKey)) - sellerBefore;
+            expect(referral).to.equal(amount.toNumber() / 20);
+            expect(paid).to.equal(amount.toNumber() - referral);
+
+            console.log("✅ Referral fee paid on release");
+        });
+
+        it("Pays the seller in full without a referrer", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount });
+            await fundEscrow(escrow, b);
+
+            const sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            await releaseTo(escrow, b, sl, null);
+
+            expect((await provider.connection.getBalance(sl.publicKey)) - sellerBefore).to.equal(amount.toNumber());
+            console.log("✅ No referral fee without a referrer");
+        });
+
+        it("Rejects fees that together exceed 100%", async () => {
+            const referrer = Keypair.generate();
+            try {
+                await setupEscrow({ options: { referrer: referrer.publicKey, referralFeeBps: 10_001 } });
+                assert.fail("Should reject a referral fee above 100%");
+            } catch (error) {
+                expect(error.toString()).to.include("FeesExceedTotal");
+            }
+
+            const feeArbiter = Keypair.generate();
+            const { escrow } = await setupEscrow({
+                arbiter: feeArbiter.publicKey,
+                options: { referrer: referrer.publicKey, referralFeeBps: 9_000 },
+            });
+            try {
+                await program.methods
+                    .updateArbiterFee(2_000)
+                    .accounts({ escrow, config, caller: feeArbiter.publicKey, counterparty: null })
+                    .signers([feeArbiter])
+                    .rpc();
+                assert.fail("Should reject arbiter + referral above 100%");
+            } catch (error) {
+                expect(error.toString()).to.include("FeesExceedTotal");
+                console.log("✅ Combined fee overflow rejected");
+            }
+        });
+    });
+
+    describe("Fee Vault", () => {
+        const [feeVault] = PublicKey.findProgramAddressSync(
+            [Buffer.from("fee_vault")],
+            program.programId
+        );
+
+        const setProtocolFee = (bps: number) =>
+            program.methods
+                .setProtocolFee(bps)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+        const releaseWithVault = (escrow: PublicKey, b: Keypair, sl: PublicKey, vault: PublicKey | null) =>
+            program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: vault,
+                    dependency: null,
+                    seller: sl,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+
+        const withdrawFees = (amount: BN, treasury: PublicKey) =>
+            program.methods
+                .withdrawFees(amount)
+                .accounts({ config, feeVault, treasury, authority: provider.wallet.publicKey })
+                .rpc();
+
+        before(async () => {
+            await program.methods
+                .initializeFeeVault()
+                .accounts({ config, feeVault, authority: provider.wallet.publicKey })
+                .rpc();
+            await setProtocolFee(100);
+        });
+
+        const setDiscountTiers = (tiers: { maxElapsed: BN; discountBps: number }[]) =>
+            program.methods
+                .setFeeDiscountTiers(tiers)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+        after(async () => {
+            await setProtocolFee(0);
+            await setDiscountTiers([]);
+        });
+
+        it("Accrues protocol fees over several releases, then withdraws them", async () => {
+            const amount = new BN(LAMPORTS_PER_SOL);
+            const fee = amount.toNumber() / 100;
+            const before = await program.account.feeVault.fetch(feeVault);
+
+            for (let i = 0; i < 2; i++) {
+                const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount });
+                await fundEscrow(escrow, b);
+                await releaseWithVault(escrow, b, sl.publicKey, feeVault);
+                expect(await provider.connection.getBalance(sl.publicKey)).to.equal(amount.toNumber() - fee);
+            }
+
+            const accrued = await program.account.feeVault.fetch(feeVault);
+            expect(accrued.balance.sub(before.balance).toNumber()).to.equal(2 * fee);
+
+            const treasury = Keypair.generate().publicKey;
+            try {
+                await withdrawFees(accrued.balance.addn(1), treasury);
+                assert.fail("Should not withdraw more than the vault holds");
+            } catch (error) {
+                expect(error.toString()).to.include("InsufficientFeeBalance");
+            }
+
+            await withdrawFees(accrued.balance, treasury);
+            expect(await provider.connection.getBalance(treasury)).to.equal(accrued.balance.toNumber());
+            const drained = await program.account.feeVault.fetch(feeVault);
+            expect(drained.balance.toNumber()).to.equal(0);
+            expect(drained.totalCollected.toNumber()).to.equal(accrued.totalCollected.toNumber());
+
+            console.log("✅ Protocol fees accrued in the vault and withdrawn");
+        });
+
+        it("Requires the vault while a protocol fee is set", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await releaseWithVault(escrow, b, sl.publicKey, null);
+                assert.fail("Should require the fee vault");
+            } catch (error) {
+                expect(error.toString()).to.include("FeeVaultRequired");
+                console.log("✅ Release without the fee vault rejected");
+            }
+        });
+
+        it("Only lets the config authority withdraw", async () => {
+            const outsider = Keypair.generate();
+            try {
+                await program.methods
+                    .withdrawFees(new BN(1))
+                    .accounts({ config, feeVault, treasury: outsider.publicKey, authority: outsider.publicKey })
+                    .signers([outsider])
+                    .rpc();
+                assert.fail("Only the authority can withdraw");
+            } catch (error) {
+                expect(error.toString()).to.include("ConstraintHasOne");
+                console.log("✅ Non-authority withdrawal rejected");
+            }
+        });
+
+        it("Discounts the protocol fee by how soon after funding the release lands", async () => {
+            const amount = new BN(LAMPORTS_PER_SOL);
+            const fullFee = amount.toNumber() / 100;
+            await setDiscountTiers([
+                { maxElapsed: new BN(4), discountBps: 5_000 },
+                { maxElapsed: new BN(12), discountBps: 2_500 },
+            ]);
+
+            const parties = [
+                await setupEscrow({ amount }),
+                await setupEscrow({ amount }),
+                await setupEscrow({ amount }),
+            ];
+            for (const { buyer: b, escrow } of parties) {
+                await fundEscrow(escrow, b);
+            }
+
+            const releaseAndMeasure = async (index: number) => {
+                const { buyer: b, seller: sl, escrow } = parties[index];
+                const before = await program.account.feeVault.fetch(feeVault);
+                await releaseWithVault(escrow, b, sl.publicKey, feeVault);
+                const after = await program.account.feeVault.fetch(feeVault);
+                return after.balance.sub(before.balance).toNumber();
+            };
+
+            // Inside the first tier: half the fee waived
+            expect(await releaseAndMeasure(0)).to.equal(fullFee / 2);
+
+            // Past the first window, inside the second
+            await sleep(6_000);
+            expect(await releaseAndMeasure(1)).to.equal(fullFee * 3 / 4);
+
+            // Past every window: the full fee
+            await sleep(8_000);
+            expect(await releaseAndMeasure(2)).to.equal(fullFee);
+
+            await setDiscountTiers([]);
+            console.log("✅ Fast releases paid a discounted protocol fee");
+        });
+
+        it("Rejects discount tiers without increasing windows", async () => {
+            try {
+                await setDiscountTiers([
+                    { maxElapsed: new BN(10), discountBps: 5_000 },
+                    { maxElapsed: new BN(10), discountBps: 2_500 },
+                ]);
+                assert.fail("Windows must strictly increase");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidFeeTiers");
+                console.log("✅ Non-increasing tiers rejected");
+            }
+        });
+    });
+
+    describe("Event Hash Chain", () => {
+        it("Advances the chain by sha256(prev || action || timestamp)", async () => {
+            const { buyer: b, escrow } = await setupEscrow();
+
+            const initialized = await program.account.escrow.fetch(escrow);
+            const prevHash = Buffer.from(initialized.lastEventHash);
+            assert.ok(!prevHash.equals(Buffer.alloc(32)));
+
+            const signature = await program.methods
+                .fundEscrow()
+                .accounts({
+                    escrow,
+                    globalStats,
+                    buyer: b.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b])
+                .rpc({ commitment: "confirmed" });
+            const tx = await provider.connection.getTransaction(signature, { commitment: "confirmed" });
+
+            // Funded is action code 8
+            const timestamp = Buffer.alloc(8);
+            timestamp.writeBigInt64LE(BigInt(tx.blockTime));
+            const expected = createHash("sha256")
+                .update(Buffer.concat([prevHash, Buffer.from([8]), timestamp]))
+                .digest();
+
+            const description = await program.methods
+                .describeEscrow()
+                .accounts({ escrow })
+                .view();
+            expect(Buffer.from(description.lastEventHash).toString("hex")).to.equal(expected.toString("hex"));
+
+            console.log("✅ Event hash chain advanced on funding");
+        });
+    });
+
+    describe("Installments", () => {
+        const amount = new BN(LAMPORTS_PER_SOL);
+
+        async function payInstallment(escrow: PublicKey, b: Keypair, installment: BN) {
+            await program.methods
+                .fundInstallment(installment)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    buyer: b.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b])
+                .rpc();
+        }
+
+        it("Stays PartiallyFunded until installments reach the amount", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount });
+
+            await payInstallment(escrow, b, new BN(0.4 * LAMPORTS_PER_SOL));
+            let account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ partiallyFunded: {} });
+            expect(account.fundedAmount.toNumber()).to.equal(0.4 * LAMPORTS_PER_SOL);
+
+            try {
+                await program.methods
+                    .releaseToSeller(null)
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: sl.publicKey,
+                        caller: b.publicKey,
+                    })
+                    .signers([b])
+                    .rpc();
+                assert.fail("Should not release a partially funded escrow");
+            } catch (error) {
+                expect(error.toString()).to.include("EscrowNotFunded");
+            }
+
+            await payInstallment(escrow, b, new BN(0.6 * LAMPORTS_PER_SOL));
+            account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ funded: {} });
+            expect(account.fundedAmount.toNumber()).to.equal(amount.toNumber());
+
+            console.log("✅ Installments funded the escrow in stages");
+        });
+
+        it("Refunds an escrow abandoned halfway through its installments", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount });
+            const paid = new BN(0.4 * LAMPORTS_PER_SOL);
+            await payInstallment(escrow, b, paid);
+
+            const refundInstallments = (caller: Keypair) =>
+                program.methods
+                    .refundInstallments()
+                    .accounts({ escrow, buyer: b.publicKey, caller: caller.publicKey })
+                    .signers([caller])
+                    .rpc();
+
+            // The buyer has to wait out the timeout, the seller doesn't
+            try {
+                await refundInstallments(b);
+                assert.fail("Buyer can't walk away before the timeout");
+            } catch (error) {
+                expect(error.toString()).to.include("UnauthorizedOperation");
+            }
+
+            const buyerBefore = await provider.connection.getBalance(b.publicKey);
+            await refundInstallments(sl);
+            const refunded = (await provider.connection.getBalance(b.publicKey)) - buyerBefore;
+            expect(refunded).to.equal(paid.toNumber());
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ refunded: {} });
+            console.log("✅ Abandoned installments refunded to the buyer");
+        });
+
+        it("Rejects an installment beyond the amount", async () => {
+            const { buyer: b, escrow } = await setupEscrow({ amount });
+            await payInstallment(escrow, b, new BN(0.5 * LAMPORTS_PER_SOL));
+
+            try {
+                await payInstallment(escrow, b, new BN(0.6 * LAMPORTS_PER_SOL));
+                assert.fail("Should reject an installment past the amount");
+            } catch (error) {
+                expect(error.toString()).to.include("InstallmentExceedsTarget");
+                console.log("✅ Over-target installment rejected");
+            }
+        });
+
+        it("Fails with ArithmeticOverflow instead of wrapping the funded total", async () => {
+            const { buyer: b, escrow } = await setupEscrow({ amount });
+            await payInstallment(escrow, b, new BN(1));
+
+            try {
+                // 1 + u64::MAX would wrap to 0 without checked arithmetic
+                await payInstallment(escrow, b, new BN("18446744073709551615"));
+                assert.fail("Should overflow");
+            } catch (error) {
+                expect(error.toString()).to.include("ArithmeticOverflow");
+            }
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.fundedAmount.toNumber()).to.equal(1);
+            console.log("✅ Installment total overflow rejected");
+        });
+
+        it("Fails with ArithmeticOverflow when the locked volume exceeds u64", async () => {
+            // Three periods of just over u64::MAX / 3 can't be locked up front
+            const { buyer: b, escrow } = await setupEscrow({
+                amount: new BN("6148914691236517206"),
+                options: { recurring: true, periodCount: 3 },
+            });
+
+            try {
+                await fundEscrow(escrow, b);
+                assert.fail("Should overflow the locked amount");
+            } catch (error) {
+                expect(error.toString()).to.include("ArithmeticOverflow");
+                console.log("✅ Locked volume overflow rejected");
+            }
+        });
+    });
+
+    describe("Tranche Schedules", () => {
+        const tranche = new BN(0.3 * LAMPORTS_PER_SOL);
+
+        const findSchedulePda = (escrow: PublicKey) =>
+            PublicKey.findProgramAddressSync([Buffer.from("tranches"), escrow.toBuffer()], program.programId)[0];
+
+        const setSchedule = (escrow: PublicKey, b: Keypair, sl: Keypair, unlockTimes: number[], amounts?: BN[]) =>
+            program.methods
+                .setTrancheSchedule(
+                    unlockTimes.map((unlockTime, i) => ({
+                        amount: amounts ? amounts[i] : tranche,
+                        unlockTime: new BN(unlockTime),
+                    }))
+                )
+                .accounts({
+                    escrow,
+                    trancheSchedule: findSchedulePda(escrow),
+                    buyer: b.publicKey,
+                    seller: sl.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b, sl])
+                .rpc();
+
+        const payInstallment = (escrow: PublicKey, b: Keypair, installment: BN) =>
+            program.methods
+                .fundInstallment(installment)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    buyer: b.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b])
+                .rpc();
+
+        const claimTranche = (escrow: PublicKey, sl: Keypair, index: number) =>
+            program.methods
+                .claimTranche(index)
+                .accounts({ escrow, trancheSchedule: findSchedulePda(escrow), seller: sl.publicKey, globalStats })
+                .signers([sl])
+                .rpc();
+
+        async function expectClaimError(escrow: PublicKey, sl: Keypair, index: number, code: string) {
+            try {
+                await claimTranche(escrow, sl, index);
+                assert.fail(`Claim of tranche ${index} should fail with ${code}`);
+            } catch (error) {
+                expect(error.toString()).to.include(code);
+            }
+        }
+
+        it("Pays each tranche as it is funded and unlocks", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount: tranche.muln(3) });
+            await airdrop(sl.publicKey, LAMPORTS_PER_SOL);
+            const start = await chainTime();
+            await setSchedule(escrow, b, sl, [start, start + 4, start + 8]);
+
+            const schedule = await program.account.trancheSchedule.fetch(findSchedulePda(escrow));
+            expect(schedule.tranches.length).to.equal(3);
+            expect(schedule.tranches.every((t) => !t.released)).to.be.true;
+
+            // First installment covers the first tranche, already unlocked
+            await payInstallment(escrow, b, tranche);
+            let sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            await claimTranche(escrow, sl, 0);
+            expect((await provider.connection.getBalance(sl.publicKey)) - sellerBefore).to.equal(tranche.toNumber());
+            let account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ partiallyFunded: {} });
+
+            await expectClaimError(escrow, sl, 1, "TrancheLocked");
+
+            // Completing the funding only tops up what the claim didn't take
+            await payInstallment(escrow, b, tranche.muln(2));
+            account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ funded: {} });
+
+            await sleep(5000);
+            await claimTranche(escrow, sl, 1);
+            await expectClaimError(escrow, sl, 1, "TrancheAlreadyClaimed");
+            await expectClaimError(escrow, sl, 2, "TrancheLocked");
+
+            await sleep(5000);
+            sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            await claimTranche(escrow, sl, 2);
+            expect((await provider.connection.getBalance(sl.publicKey)) - sellerBefore).to.equal(tranche.toNumber());
+
+            account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ released: {} });
+            expect(account.vestedClaimed.toString()).to.equal(tranche.muln(3).toString());
+            const settled = await program.account.trancheSchedule.fetch(findSchedulePda(escrow));
+            expect(settled.tranches.every((t) => t.released)).to.be.true;
+            console.log("✅ Tranches claimed across time, escrow settled on the last one");
+        });
+
+        it("Withholds an unlocked tranche the installments don't cover yet", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount: tranche.muln(2) });
+            await airdrop(sl.publicKey, LAMPORTS_PER_SOL);
+            const start = await chainTime();
+            await setSchedule(escrow, b, sl, [start, start]);
+
+            await expectClaimError(escrow, sl, 0, "EscrowNotFunded");
+            await payInstallment(escrow, b, tranche);
+            await expectClaimError(escrow, sl, 1, "TrancheNotFunded");
+            await expectClaimError(escrow, sl, 2, "InvalidTrancheIndex");
+            await claimTranche(escrow, sl, 0);
+            console.log("✅ Only funded tranches can be claimed");
+        });
+
+        it("Rejects a schedule that doesn't add up to the escrow amount", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount: tranche.muln(3) });
+            const start = await chainTime();
+
+            try {
+                await setSchedule(escrow, b, sl, [start, start + 10]);
+                assert.fail("Schedule must cover the whole amount");
+            } catch (error) {
+                expect(error.toString()).to.include("TrancheSumMismatch");
+            }
+
+            try {
+                await setSchedule(escrow, b, sl, [start + 10, start], [tranche, tranche.muln(2)]);
+                assert.fail("Unlock times must not decrease");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidTrancheSchedule");
+                console.log("✅ Invalid tranche schedules rejected");
+            }
+        });
+
+        it("Rejects tranches on vesting and recurring escrows", async () => {
+            const start = await chainTime();
+            for (const options of [{ vesting: true }, { recurring: true, periodCount: 3 }]) {
+                const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount: tranche.muln(3), options });
+
+                try {
+                    await setSchedule(escrow, b, sl, [start, start + 4, start + 8]);
+                    assert.fail("Tranches conflict with the escrow's own payout schedule");
+                } catch (error) {
+                    expect(error.toString()).to.include("ConflictingPayoutModes");
+                }
+            }
+            console.log("✅ Tranches don't combine with vesting or recurring payouts");
+        });
+    });
+
+    describe("Transfer Cap", () => {
+        const amount = new BN(0.5 * LAMPORTS_PER_SOL);
+
+        async function releaseUnderCap(cap: BN) {
+            await program.methods
+                .setMaxTransfer(cap)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount });
+            await fundEscrow(escrow, b);
+
+            await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl.publicKey,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+            return escrow;
+        }
+
+        after(async () => {
+            await program.methods
+                .setMaxTransfer(new BN("18446744073709551615"))
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+        });
+
+        it("Allows a release of exactly the cap", async () => {
+            const escrow = await releaseUnderCap(amount);
+            const escrowAccount = await program.account.escrow.fetch(escrow);
+            assert.equal(escrowAccount.state.released !== undefined, true);
+            console.log("✅ Release at the cap allowed");
+        });
+
+        it("Rejects a release above the cap", async () => {
+            try {
+                await releaseUnderCap(amount.subn(1));
+
+                assert.fail("Should reject release above the cap");
+            } catch (error) {
+                expect(error.toString()).to.include("TransferCapExceeded");
+                console.log("✅ Release above the cap rejected");
+            }
+        });
+
+        it("Rejects a zero cap", async () => {
+            try {
+                await program.methods
+                    .setMaxTransfer(new BN(0))
+                    .accounts({ config, authority: provider.wallet.publicKey })
+                    .rpc();
+
+                assert.fail("A zero cap would block every transfer");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidAmount");
+                console.log("✅ Zero transfer cap rejected");
+            }
+        });
+    });
+
+    describe("Fee Rounding", () => {
+        // 1 bps of 1_005_000 lamports is 100.5 lamports
+        const amount = new BN(1_005_000);
+
+        async function arbiterFeeUnder(feeRounding: Record<string, unknown>) {
+            await program.methods
+                .setFeeRounding(feeRounding)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+            const arbiterKp = Keypair.generate();
+            await airdrop(arbiterKp.publicKey, LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount,
+                arbiter: arbiterKp.publicKey,
+            });
+            await program.methods
+                .updateArbiterFee(1)
+                .accounts({ escrow, config, caller: arbiterKp.publicKey, counterparty: null })
+                .signers([arbiterKp])
+                .rpc();
+            await fundEscrow(escrow, b);
+
+            const before = await provider.connection.getBalance(arbiterKp.publicKey);
+            await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl.publicKey,
+                    caller: arbiterKp.publicKey,
+                })
+                .signers([arbiterKp])
+                .rpc();
+            return (await provider.connection.getBalance(arbiterKp.publicKey)) - before;
+        }
+
+        after(async () => {
+            await program.methods
+                .setFeeRounding({ down: {} })
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+        });
+
+        it("Rounds fees down by default", async () => {
+            expect(await arbiterFeeUnder({ down: {} })).to.equal(100);
+            console.log("✅ Fee rounded down");
+        });
+
+        it("Rounds fees up when configured", async () => {
+            expect(await arbiterFeeUnder({ up: {} })).to.equal(101);
+            console.log("✅ Fee rounded up");
+        });
+
+        it("Rounds fees to the nearest lamport when configured", async () => {
+            expect(await arbiterFeeUnder({ nearest: {} })).to.equal(101);
+            console.log("✅ Fee rounded to nearest");
+        });
+    });
+
+    describe("Release To PDA", () => {
+        const releaseToPda = (escrow: PublicKey, b: Keypair, sl: PublicKey, owner: PublicKey) =>
+            program.methods
+                .releaseToPda(owner, null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+
+        it("Pays out to an account owned by the expected program", async () => {
+            // Any program-owned account works as a destination; the stats PDA is handy
+            const b = Keypair.generate();
+            await airdrop(b.publicKey, 2 * LAMPORTS_PER_SOL);
+            const escrow = findEscrowPda(b.publicKey, globalStats);
+            await program.methods
+                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60), escrowOptions())
+                .accounts({
+                    escrow,
+                    buyer: b.publicKey,
+                    seller: globalStats,
+                    arbiter: b.publicKey,
+                    cooldown: findCooldownPda(b.publicKey, globalStats),
+                    config,
+                    arbiterStake: null,
+                    arbiterIndex: null,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b])
+                .rpc();
+            await fundEscrow(escrow, b);
+
+            const before = await provider.connection.getBalance(globalStats);
+            await releaseToPda(escrow, b, globalStats, program.programId);
+
+            const after = await provider.connection.getBalance(globalStats);
+            expect(after - before).to.be.at.least(0.5 * LAMPORTS_PER_SOL);
+            console.log("✅ Released into a program-owned account");
+        });
+
+        it("Rejects a wallet destination", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await releaseToPda(escrow, b, sl.publicKey, SystemProgram.programId);
+                assert.fail("A system-owned seller is not a PDA destination");
+            } catch (error) {
+                expect(error.toString()).to.include("DestinationNotProgramOwned");
+                console.log("✅ Wallet destination rejected");
+            }
+        });
+    });
+
+    describe("Rent Top-up", () => {
+        it("Refuses a top-up when the escrow is already rent-exempt", async () => {
+            const { buyer: b, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await program.methods
+                    .topUpRent(new BN(1))
+                    .accounts({
+                        escrow,
+                        payer: provider.wallet.publicKey,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .rpc();
+
+                assert.fail("Nothing to top up on a current-size escrow");
+            } catch (error) {
+                expect(error.toString()).to.include("NoRentShortfall");
+                console.log("✅ No top-up without a shortfall");
+            }
+        });
+
+        it("Rejects a zero top-up", async () => {
+            const { buyer: b, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await program.methods
+                    .topUpRent(new BN(0))
+                    .accounts({
+                        escrow,
+                        payer: provider.wallet.publicKey,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .rpc();
+
+                assert.fail("A zero top-up should be rejected");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidAmount");
+                console.log("✅ Zero top-up rejected");
+            }
+        });
+    });
+
+    describe("Fee Statistics", () => {
+        it("Accumulates arbiter fees across releases", async () => {
+            const arbiterKp = Keypair.generate();
+            await airdrop(arbiterKp.publicKey, 1 * LAMPORTS_PER_SOL);
+
+            const before = await program.methods.getFeesCollected().accounts({ globalStats }).view();
+            const arbiterBefore = await provider.connection.getBalance(arbiterKp.publicKey);
+
+            for (let i = 0; i < 2; i++) {
+                const { buyer: b, seller: sl, escrow } = await setupEscrow({ arbiter: arbiterKp.publicKey });
+                await program.methods
+                    .updateArbiterFee(250)
+                    .accounts({ escrow, config, caller: arbiterKp.publicKey, counterparty: null })
+                    .signers([arbiterKp])
+                    .rpc();
+                await fundEscrow(escrow, b);
+
+                await program.methods
+                    .releaseToSeller(null)
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: sl.publicKey,
+                        caller: arbiterKp.publicKey,
+                    })
+                    .signers([arbiterKp])
+                    .rpc();
+            }
+
+            const after = await program.methods.getFeesCollected().accounts({ globalStats }).view();
+            const earned = (await provider.connection.getBalance(arbiterKp.publicKey)) - arbiterBefore;
+            assert.ok(earned > 0);
+            assert.ok(after.sub(before).eqn(earned));
+            console.log("✅ Fee total matches what the arbiter earned");
+        });
+    });
+
+    describe("Buyer Operator", () => {
+        it("Lets the operator fund and release for the buyer", async () => {
+            const operator = Keypair.generate();
+            await airdrop(operator.publicKey, 2 * LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+
+            await program.methods
+                .setOperator(operator.publicKey)
+                .accounts({ escrow, buyer: b.publicKey })
+                .signers([b])
+                .rpc();
+
+            await fundEscrow(escrow, operator);
+            await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl.publicKey,
+                    caller: operator.publicKey,
+                })
+                .signers([operator])
+                .rpc();
+
+            const account = await program.account.escrow.fetch(escrow);
+            assert.ok(account.buyerOperator.equals(operator.publicKey));
+            assert.deepEqual(account.state, { released: {} });
+            console.log("✅ Operator funded and released");
+        });
+
+        it("Rejects funding by a key that is neither buyer nor operator", async () => {
+            const stranger = Keypair.generate();
+            await airdrop(stranger.publicKey, 2 * LAMPORTS_PER_SOL);
+            const { escrow } = await setupEscrow();
+
+            try {
+                await fundEscrow(escrow, stranger);
+                assert.fail("Only the buyer or its operator may fund");
+            } catch (error) {
+                expect(error.toString()).to.include("NotEscrowBuyer");
+                console.log("✅ Stranger funding rejected");
+            }
+        });
+    });
+
+    describe("Withdraw Excess", () => {
+        it("Returns only the surplus above the agreed amount", async () => {
+            const amount = new BN(0.5 * LAMPORTS_PER_SOL);
+            const { buyer: b, escrow } = await setupEscrow({ amount });
+            await fundEscrow(escrow, b);
+
+            // Accidental top-up straight to the PDA
+            const surplus = 0.1 * LAMPORTS_PER_SOL;
+            await provider.sendAndConfirm(
+                new anchor.web3.Transaction().add(
+                    SystemProgram.transfer({
+                        fromPubkey: b.publicKey,
+                        toPubkey: escrow,
+                        lamports: surplus,
+                    })
+                ),
+                [b]
+            );
+
+            const escrowBalanceBefore = await provider.connection.getBalance(escrow);
+
+            await program.methods
+                .withdrawExcess()
+                .accounts({ escrow, buyer: b.publicKey })
+                .signers([b])
+                .rpc();
+
+            const escrowBalanceAfter = await provider.connection.getBalance(escrow);
+            const escrowAccount = await program.account.escrow.fetch(escrow);
+
+            assert.equal(escrowBalanceBefore - escrowBalanceAfter, surplus);
+            assert.equal(escrowAccount.state.funded !== undefined, true);
+            console.log("✅ Surplus returned, agreed amount kept in escrow");
+        });
+
+        it("Fails when there is no surplus", async () => {
+            const { buyer: b, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await program.methods
+                    .withdrawExcess()
+                    .accounts({ escrow, buyer: b.publicKey })
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Should have failed without surplus");
+            } catch (error) {
+                expect(error.toString()).to.include("NoExcessFunds");
+                console.log("✅ Correctly rejected withdrawal without surplus");
+            }
+        });
+    });
+
+    describe("Authorization Monitoring", () => {
+        it("Logs an auth denied line before rejecting", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+            await airdrop(sl.publicKey, LAMPORTS_PER_SOL);
+
+            try {
+                await program.methods
+                    .releaseToSeller(null)
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: sl.publicKey,
+                        caller: sl.publicKey,
+                    })
+                    .signers([sl])
+                    .rpc();
+
+                assert.fail("Seller should not release before timeout");
+            } catch (error) {
+                expect(error.toString()).to.include("UnauthorizedOperation");
+                const logs: string[] = error.logs ?? [];
+                const denied = logs.find((line) => line.includes("auth denied"));
+                assert.ok(denied, "expected an auth denied log line");
+                expect(denied).to.include("action=release");
+                expect(denied).to.include(`caller=${sl.publicKey.toString()}`);
+                expect(denied).to.include(`escrow=${escrow.toString()}`);
+                console.log("✅ Denied attempt logged for monitoring");
+            }
+        });
+    });
+
+    describe("Release Memo", () => {
+        it("Logs the memo attached to a release", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            const memo = "invoice-4821";
+            const signature = await program.methods
+                .releaseToSeller(memo)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl.publicKey,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc({ commitment: "confirmed" });
+
+            const tx = await provider.connection.getTransaction(signature, {
+                commitment: "confirmed",
+                maxSupportedTransactionVersion: 0,
+            });
+            const logs = tx?.meta?.logMessages ?? [];
+            assert.ok(logs.some((line) => line.includes(`Release memo: ${memo}`)));
+            console.log("✅ Release memo surfaced in logs");
+        });
+
+        it("Rejects an oversized memo", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await program.methods
+                    .releaseToSeller("x".repeat(129))
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: sl.publicKey,
+                        caller: b.publicKey,
+                    })
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Should have rejected oversized memo");
+            } catch (error) {
+                expect(error.toString()).to.include("MemoTooLong");
+                console.log("✅ Correctly rejected oversized memo");
+            }
+        });
+    });
+
+    describe("High-Value Cosigner Policy", () => {
+        const threshold = new BN(LAMPORTS_PER_SOL);
+        const cosigner = Keypair.generate();
+
+        before(async () => {
+            await program.methods
+                .setHighValuePolicy(threshold, cosigner.publicKey)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+        });
+
+        after(async () => {
+            await program.methods
+                .setHighValuePolicy(new BN(0), null)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+        });
+
+        it("Does not require the cosigner at the threshold", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount: threshold });
+            await fundEscrow(escrow, b);
+
+            await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl.publicKey,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+
+            const escrowAccount = await program.account.escrow.fetch(escrow);
+            assert.equal(escrowAccount.state.released !== undefined, true);
+            console.log("✅ Escrow at threshold released without cosigner");
+        });
+
+        it("Requires the cosigner just above the threshold", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount: threshold.addn(1),
+            });
+            await fundEscrow(escrow, b);
+
+            try {
+                await program.methods
+                    .releaseToSeller(null)
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: sl.publicKey,
+                        caller: b.publicKey,
+                    })
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Should require cosigner");
+            } catch (error) {
+                expect(error.toString()).to.include("CosignerRequired");
+            }
+
+            await program.methods
+                .refundToBuyer()
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: cosigner.publicKey,
+                    counterparty: null,
+                    receipt: null,
+                    buyer: b.publicKey,
+                    seller: sl.publicKey,
+                    caller: b.publicKey,
+                })
+                .signers([b, cosigner])
+                .rpc();
+
+            const escrowAccount = await program.account.escrow.fetch(escrow);
+            assert.equal(escrowAccount.state.refunded !== undefined, true);
+            console.log("✅ Cosigner required above threshold");
+        });
+    });
+
+    describe("Soft Refund", () => {
+        it("Refunds the buyer and allows re-funding afterward", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            const buyerBalanceBefore = await provider.connection.getBalance(b.publicKey);
+
+            await program.methods
+                .softRefund()
+                .accounts({
+                    escrow,
+                    buyer: b.publicKey,
+                    seller: sl.publicKey,
+                    globalStats,
+                })
+                .signers([b, sl])
+                .rpc();
+
+            let escrowAccount = await program.account.escrow.fetch(escrow);
+            assert.equal(escrowAccount.state.created !== undefined, true);
+            const buyerBalanceAfter = await provider.connection.getBalance(b.publicKey);
+            assert.ok(buyerBalanceAfter > buyerBalanceBefore);
+
+            await fundEscrow(escrow, b);
+
+            escrowAccount = await program.account.escrow.fetch(escrow);
+            assert.equal(escrowAccount.state.funded !== undefined, true);
+            console.log("✅ Soft refund kept the escrow open for re-funding");
+        });
+
+        it("Refunds only the unclaimed remainder and resets the vesting progress", async () => {
+            const amount = new BN(LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount,
+                timeoutPeriod: new BN(60),
+                options: { vesting: true },
+            });
+            await airdrop(sl.publicKey, LAMPORTS_PER_SOL);
+            await fundEscrow(escrow, b);
+            await sleep(3000);
+            await program.methods
+                .claimVested()
+                .accounts({ escrow, seller: sl.publicKey, globalStats, config })
+                .signers([sl])
+                .rpc();
+            const claimed = (await program.account.escrow.fetch(escrow)).vestedClaimed;
+
+            const buyerBefore = await provider.connection.getBalance(b.publicKey);
+            await program.methods
+                .softRefund()
+                .accounts({ escrow, buyer: b.publicKey, seller: sl.publicKey, globalStats })
+                .signers([b, sl])
+                .rpc();
+            const refunded = (await provider.connection.getBalance(b.publicKey)) - buyerBefore;
+            expect(refunded.toString()).to.equal(amount.sub(claimed).toString());
+
+            const reset = await program.account.escrow.fetch(escrow);
+            expect(reset.vestedClaimed.toNumber()).to.equal(0);
+            expect(reset.fundedAmount.toNumber()).to.equal(0);
+            console.log("✅ Soft refund returned the unclaimed funds and reset vested_claimed");
+        });
+
+        it("Fails without the real seller's signature", async () => {
+            const { buyer: b, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+            const impostor = Keypair.generate();
+
+            try {
+                await program.methods
+                    .softRefund()
+                    .accounts({
+                        escrow,
+                        buyer: b.publicKey,
+                        seller: impostor.publicKey,
+                        globalStats,
+                    })
+                    .signers([b, impostor])
+                    .rpc();
+
+                assert.fail("Should require the escrow's seller");
+            } catch (error) {
+                expect(error.toString()).to.include("ConstraintHasOne");
+                console.log("✅ Soft refund requires both parties");
+            }
+        });
+    });
+
+    describe("Renegotiation", () => {
+        const reopen = (escrow: PublicKey, b: Keypair, sl: Keypair) =>
+            program.methods
+                .reopenForRenegotiation()
+                .accounts({ escrow, buyer: b.publicKey, seller: sl.publicKey, globalStats })
+                .signers([b, sl])
+                .rpc();
+
+        const amendTerms = (escrow: PublicKey, b: Keypair, sl: Keypair, amount: BN, timeoutPeriod: BN) =>
+            program.methods
+                .amendTerms(amount, timeoutPeriod)
+                .accounts({ escrow, buyer: b.publicKey, seller: sl.publicKey })
+                .signers([b, sl])
+                .rpc();
+
+        const bindTerms = (escrow: PublicKey, b: Keypair, sl: Keypair, fill: number) =>
+            program.methods
+                .bindTerms(Array.from(Buffer.alloc(32, fill)))
+                .accounts({ escrow, buyer: b.publicKey, seller: sl.publicKey })
+                .signers([b, sl])
+                .rpc();
+
+        it("Reopens, amends and re-funds, then refunds on the new terms", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await bindTerms(escrow, b, sl, 1);
+            await fundEscrow(escrow, b);
+
+            const buyerFunded = await provider.connection.getBalance(b.publicKey);
+            await reopen(escrow, b, sl);
+
+            let account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { created: {} });
+            expect(account.termsBound).to.equal(false);
+            expect(account.fundedAt.toNumber()).to.equal(0);
+            expect(await provider.connection.getBalance(b.publicKey)).to.equal(
+                buyerFunded + 0.5 * LAMPORTS_PER_SOL
+            );
+
+            const newAmount = new BN(0.8 * LAMPORTS_PER_SOL);
+            await amendTerms(escrow, b, sl, newAmount, new BN(120));
+            await bindTerms(escrow, b, sl, 2);
+            await fundEscrow(escrow, b);
+
+            account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { funded: {} });
+            assert.ok(account.amount.eq(newAmount));
+            assert.ok(account.fundedAmount.eq(newAmount));
+            expect(account.timeoutPeriod.toNumber()).to.equal(120);
+            expect(Array.from(account.termsHash)).to.deep.equal(Array(32).fill(2));
+
+            const buyerRefunded = await provider.connection.getBalance(b.publicKey);
+            await program.methods
+                .refundToBuyer()
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    counterparty: null,
+                    receipt: null,
+                    buyer: b.publicKey,
+                    seller: sl.publicKey,
+                    caller: sl.publicKey,
+                })
+                .signers([sl])
+                .rpc();
+
+            account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { refunded: {} });
+            expect(await provider.connection.getBalance(b.publicKey)).to.equal(
+                buyerRefunded + newAmount.toNumber()
+            );
+            console.log("✅ Renegotiated escrow refunded at the amended amount");
+        });
+
+        it("Won't amend terms while the escrow holds funds", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await amendTerms(escrow, b, sl, new BN(LAMPORTS_PER_SOL), new BN(60));
+                assert.fail("Funded escrows have to be reopened first");
+            } catch (error) {
+                expect(error.toString()).to.include("EscrowAlreadyFunded");
+                console.log("✅ Amending a funded escrow rejected");
+            }
+        });
+
+        it("Won't amend terms that are still bound", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await bindTerms(escrow, b, sl, 3);
+
+            try {
+                await amendTerms(escrow, b, sl, new BN(LAMPORTS_PER_SOL), new BN(60));
+                assert.fail("Bound terms can't be amended");
+            } catch (error) {
+                expect(error.toString()).to.include("TermsAlreadyBound");
+                console.log("✅ Amending bound terms rejected");
+            }
+        });
+
+        it("Won't amend a timeout that runs past the expiry", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                options: { expiry: new BN((await chainTime()) + 3600) },
+            });
+
+            try {
+                await amendTerms(escrow, b, sl, new BN(LAMPORTS_PER_SOL), new BN(7200));
+                assert.fail("The timeout has to run out before the expiry");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidTimeout");
+                console.log("✅ Amended timeout past the expiry rejected");
+            }
+
+            await amendTerms(escrow, b, sl, new BN(LAMPORTS_PER_SOL), new BN(600));
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.timeoutPeriod.toNumber()).to.equal(600);
+        });
+
+        it("Won't reopen an escrow that isn't funded", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+
+            try {
+                await reopen(escrow, b, sl);
+                assert.fail("Nothing to refund before funding");
+            } catch (error) {
+                expect(error.toString()).to.include("EscrowNotFunded");
+                console.log("✅ Reopening an unfunded escrow rejected");
+            }
+        });
+    });
+
+    describe("Recipient Validation", () => {
+        it("Rejects cancelling with rent redirected to another account", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            const attacker = Keypair.generate();
+
+            try {
+                await program.methods
+                    .cancelEscrow(0)
+                    .accounts({
+                        escrow,
+                        buyer: attacker.publicKey,
+                        cooldown: findCooldownPda(b.publicKey, sl.publicKey),
+                        config,
+                        caller: b.publicKey,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Should reject a mismatched rent recipient");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidRecipient");
+                console.log("✅ Rent refund can't be redirected");
+            }
+        });
+
+        it("Rejects releasing to an account other than the seller", async () => {
+            const { buyer: b, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+            const attacker = Keypair.generate();
+
+            try {
+                await program.methods
+                    .releaseToSeller(null)
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: attacker.publicKey,
+                        caller: b.publicKey,
+                    })
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Should reject a mismatched seller");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidRecipient");
+                console.log("✅ Release can't be redirected");
+            }
+        });
+    });
+
+    describe("Escrow Templates", () => {
+        const findTemplatePda = (creator: PublicKey, templateId: BN) =>
+            PublicKey.findProgramAddressSync(
+                [Buffer.from("template"), creator.toBuffer(), templateId.toArrayLike(Buffer, "le", 8)],
+                program.programId
+            )[0];
+
+        async function createTemplate(timeoutPeriod: BN, arbiter: PublicKey | null, feeBps: number) {
+            const creator = Keypair.generate();
+            await airdrop(creator.publicKey, LAMPORTS_PER_SOL);
+            const templateId = new BN(1);
+            const template = findTemplatePda(creator.publicKey, templateId);
+
+            await program.methods
+                .createTemplate(templateId, timeoutPeriod, arbiter, feeBps)
+                .accounts({ template, creator: creator.publicKey, systemProgram: SystemProgram.programId })
+                .signers([creator])
+                .rpc();
+
+            return template;
+        }
+
+        async function initFromTemplate(template: PublicKey, arbiterStake: PublicKey | null = null) {
+            const tplBuyer = Keypair.generate();
+            const tplSeller = Keypair.generate();
+            await airdrop(tplBuyer.publicKey, LAMPORTS_PER_SOL);
+            const escrow = findEscrowPda(tplBuyer.publicKey, tplSeller.publicKey);
+
+            await program.methods
+                .initializeFromTemplate(new BN(0.5 * LAMPORTS_PER_SOL))
+                .accounts({
+                    escrow,
+                    template,
+                    buyer: tplBuyer.publicKey,
+                    seller: tplSeller.publicKey,
+                    cooldown: findCooldownPda(tplBuyer.publicKey, tplSeller.publicKey),
+                    config,
+                    arbiterStake,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([tplBuyer])
+                .rpc();
+
+            return escrow;
+        }
+
+        it("Instantiates two escrows from one template", async () => {
+            const creator = Keypair.generate();
+            const templateArbiter = Keypair.generate();
+            await airdrop(creator.publicKey, LAMPORTS_PER_SOL);
+            await airdrop(templateArbiter.publicKey, LAMPORTS_PER_SOL);
+
+            const templateId = new BN(1);
+            const [template] = PublicKey.findProgramAddressSync(
+                [
+                    Buffer.from("template"),
+                    creator.publicKey.toBuffer(),
+                    templateId.toArrayLike(Buffer, "le", 8),
+                ],
+                program.programId
+            );
+
+            await program.methods
+                .createTemplate(templateId, new BN(3600), templateArbiter.publicKey, 500)
+                .accounts({
+                    template,
+                    creator: creator.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([creator])
+                .rpc();
+
+            const tplBuyer = Keypair.generate();
+            await airdrop(tplBuyer.publicKey, 3 * LAMPORTS_PER_SOL);
+            const sellers = [Keypair.generate(), Keypair.generate()];
+            const amount = new BN(0.5 * LAMPORTS_PER_SOL);
+
+            for (const tplSeller of sellers) {
+                const escrow = findEscrowPda(tplBuyer.publicKey, tplSeller.publicKey);
+
+                await program.methods
+                    .initializeFromTemplate(amount)
+                    .accounts({
+                        escrow,
+                        template,
+                        buyer: tplBuyer.publicKey,
+                        seller: tplSeller.publicKey,
+                        cooldown: findCooldownPda(tplBuyer.publicKey, tplSeller.publicKey),
+                        config,
+                        arbiterStake: null,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([tplBuyer])
+                    .rpc();
+
+                const escrowAccount = await program.account.escrow.fetch(escrow);
+                assert.ok(escrowAccount.timeoutPeriod.eq(new BN(3600)));
+                assert.ok(escrowAccount.arbiter.equals(templateArbiter.publicKey));
+                assert.equal(escrowAccount.arbiterFeeBps, 500);
+                assert.ok(escrowAccount.amount.eq(amount));
+            }
+
+            // Arbiter resolving the first escrow earns the template fee
+            const escrow = findEscrowPda(tplBuyer.publicKey, sellers[0].publicKey);
+            await fundEscrow(escrow, tplBuyer);
+            const arbiterBalanceBefore = await provider.connection.getBalance(templateArbiter.publicKey);
+
+            await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sellers[0].publicKey,
+                    caller: templateArbiter.publicKey,
+                })
+                .signers([templateArbiter])
+                .rpc();
+
+            const arbiterBalanceAfter = await provider.connection.getBalance(templateArbiter.publicKey);
+            assert.equal(arbiterBalanceAfter - arbiterBalanceBefore, amount.toNumber() * 0.05);
+            console.log("✅ Template parameters copied into both escrows");
+        });
+
+        it("Applies the arbiter stake minimum to a template's arbiter", async () => {
+            const templateArbiter = Keypair.generate();
+            const template = await createTemplate(new BN(3600), templateArbiter.publicKey, 0);
+
+            await program.methods
+                .setMinArbiterStake(new BN(LAMPORTS_PER_SOL))
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+            try {
+                await initFromTemplate(template);
+                assert.fail("Template arbiter without stake should be refused");
+            } catch (error) {
+                expect(error.toString()).to.include("InsufficientArbiterStake");
+                console.log("✅ Template arbiter needs the minimum stake");
+            } finally {
+                await program.methods
+                    .setMinArbiterStake(new BN(0))
+                    .accounts({ config, authority: provider.wallet.publicKey })
+                    .rpc();
+            }
+        });
+
+        it("Applies the minimum timeout to templates", async () => {
+            try {
+                await createTemplate(new BN(30), null, 0);
+                assert.fail("Template timeout below the floor should be rejected");
+            } catch (error) {
+                expect(error.toString()).to.include("TimeoutTooShort");
+                console.log("✅ Template timeout floor enforced");
+            }
+        });
+
+        it("Caps a template's arbiter fee at the config maximum", async () => {
+            const template = await createTemplate(new BN(3600), Keypair.generate().publicKey, 500);
+
+            await program.methods
+                .setMaxArbiterFee(100)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+            try {
+                await initFromTemplate(template);
+                assert.fail("Template fee above the cap should be refused");
+            } catch (error) {
+                expect(error.toString()).to.include("FeeAboveCap");
+                console.log("✅ Template arbiter fee capped");
+            } finally {
+                await program.methods
+                    .setMaxArbiterFee(10_000)
+                    .accounts({ config, authority: provider.wallet.publicKey })
+                    .rpc();
+            }
+        });
+    });
+
+    describe("Re-init Cooldown", () => {
+        before(async () => {
+            await program.methods
+                .setReinitCooldown(new BN(3600))
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+        });
+
+        after(async () => {
+            await program.methods
+                .setReinitCooldown(new BN(0))
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+        });
+
+        it("Rejects re-initializing a pair right after cancel", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            const cooldown = findCooldownPda(b.publicKey, sl.publicKey);
+
+            await program.methods
+                .cancelEscrow(0)
+                .accounts({
+                    escrow,
+                    buyer: b.publicKey,
+                    cooldown,
+                    config,
+                    caller: b.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b])
+                .rpc();
+
+            const marker = await program.account.cooldownMarker.fetch(cooldown);
+            assert.ok(marker.cancelledAt.toNumber() > 0);
+
+            try {
+                await program.methods
+                    .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60), escrowOptions())
+                    .accounts({
+                        escrow,
+                        buyer: b.publicKey,
+                        seller: sl.publicKey,
+                        arbiter: b.publicKey,
+                        cooldown,
+                        config,
+                        arbiterStake: null,
+                        arbiterIndex: null,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Should reject re-init during cooldown");
+            } catch (error) {
+                expect(error.toString()).to.include("CooldownActive");
+                console.log("✅ Re-init blocked during cooldown");
+            }
+        });
+    });
+
+    describe("Close Then Re-init", () => {
+        const reinitialize = (b: Keypair, sl: PublicKey, options = escrowOptions()) =>
+            program.methods
+                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60), options)
+                .accounts({
+                    escrow: findEscrowPda(b.publicKey, sl),
+                    buyer: b.publicKey,
+                    seller: sl,
+                    arbiter: b.publicKey,
+                    cooldown: findCooldownPda(b.publicKey, sl),
+                    config,
+                    arbiterStake: null,
+                    arbiterIndex: null,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b])
+                .rpc();
+
+        // A reused address must hold exactly rent, nothing carried over
+        const expectOnlyRent = async (escrow: PublicKey) => {
+            const info = await provider.connection.getAccountInfo(escrow);
+            const rent = await provider.connection.getMinimumBalanceForRentExemption(info.data.length);
+            expect(info.lamports).to.equal(rent);
+        };
+
+        it("Starts from a clean account after cancel", async () => {
+            const referrer = Keypair.generate().publicKey;
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                arbiter: Keypair.generate().publicKey,
+                options: {
+                    label: Array.from(Buffer.alloc(16, 9)),
+                    vesting: true,
+                    referrer,
+                    referralFeeBps: 500,
+                },
+            });
+
+            await program.methods
+                .cancelEscrow(0)
+                .accounts({
+                    escrow,
+                    buyer: b.publicKey,
+                    cooldown: findCooldownPda(b.publicKey, sl.publicKey),
+                    config,
+                    caller: b.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b])
+                .rpc();
+            expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+
+            await reinitialize(b, sl.publicKey);
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ created: {} });
+            expect(account.arbiter).to.be.null;
+            expect(account.label).to.deep.equal(Array(16).fill(0));
+            expect(account.vesting).to.equal(false);
+            expect(account.referrer).to.be.null;
+            expect(account.referralFeeBps).to.equal(0);
+            await expectOnlyRent(escrow);
+
+            console.log("✅ Re-init after cancel carried nothing over");
+        });
+
+        it("Starts from a clean account after a resolved escrow is closed", async () => {
+            const decisionArbiter = Keypair.generate();
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                arbiter: decisionArbiter.publicKey,
+            });
+            await fundEscrow(escrow, b);
+
+            await program.methods
+                .arbiterResolve({ refundToBuyer: {} }, Array.from(Buffer.alloc(32, 7)))
+                .accounts({
+                    escrow,
+                    seller: sl.publicKey,
+                    buyer: b.publicKey,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    arbiter: decisionArbiter.publicKey,
+                    arbiterStats: null,
+                    arbiterIndex: null,
+                })
+                .signers([decisionArbiter])
+                .rpc();
+
+            await program.methods
+                .closeEscrowsBatch()
+                .accounts({ caller: provider.wallet.publicKey })
+                .remainingAccounts([
+                    { pubkey: escrow, isWritable: true, isSigner: false },
+                    { pubkey: b.publicKey, isWritable: true, isSigner: false },
+                ])
+                .rpc();
+
+            await reinitialize(b, sl.publicKey);
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ created: {} });
+            expect(account.arbiterDecision).to.be.null;
+            expect(account.reasonHash).to.deep.equal(Array(32).fill(0));
+            expect(account.fundedAmount.toNumber()).to.equal(0);
+            expect(account.fundedAt.toNumber()).to.equal(0);
+            expect(account.accruedArbiterFee.toNumber()).to.equal(0);
+            await expectOnlyRent(escrow);
+
+            // Nothing from the old escrow counts towards funding the new one
+            const buyerBefore = await provider.connection.getBalance(b.publicKey);
+            await fundEscrow(escrow, b);
+            const buyerAfter = await provider.connection.getBalance(b.publicKey);
+            expect(buyerBefore - buyerAfter).to.be.at.least(0.5 * LAMPORTS_PER_SOL);
+
+            console.log("✅ Re-init after close carried nothing over");
+        });
+    });
+
+    describe("Arbiter Decisions", () => {
+        it("Records the decision and reasoning hash before refunding", async () => {
+            const decisionArbiter = Keypair.generate();
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                arbiter: decisionArbiter.publicKey,
+            });
+            await fundEscrow(escrow, b);
+
+            const reasonHash = Array.from(Buffer.alloc(32, 7));
+            const buyerBalanceBefore = await provider.connection.getBalance(b.publicKey);
+
+            await program.methods
+                .arbiterResolve({ refundToBuyer: {} }, reasonHash)
+                .accounts({
+                    escrow,
+                    seller: sl.publicKey,
+                    buyer: b.publicKey,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    arbiter: decisionArbiter.publicKey,
+                    arbiterStats: null,
+                    arbiterIndex: null,
+                })
+                .signers([decisionArbiter])
+                .rpc();
+
+            const escrowAccount = await program.account.escrow.fetch(escrow);
+            assert.equal(escrowAccount.state.refunded !== undefined, true);
+            assert.ok(escrowAccount.arbiterDecision.refundToBuyer !== undefined);
+            assert.deepEqual(Array.from(escrowAccount.reasonHash), reasonHash);
+            assert.ok((await provider.connection.getBalance(b.publicKey)) > buyerBalanceBefore);
+            console.log("✅ Arbiter decision recorded with reasoning hash");
+        });
+
+        it("Rejects a resolution from anyone but the arbiter", async () => {
+            const decisionArbiter = Keypair.generate();
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                arbiter: decisionArbiter.publicKey,
+            });
+            await fundEscrow(escrow, b);
+
+            try {
+                await program.methods
+                    .arbiterResolve({ releaseToSeller: {} }, Array.from(Buffer.alloc(32)))
+                    .accounts({
+                        escrow,
+                        seller: sl.publicKey,
+                        buyer: b.publicKey,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        arbiter: b.publicKey,
+                        arbiterStats: null,
+                        arbiterIndex: null,
+                    })
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Buyer should not resolve as arbiter");
+            } catch (error) {
+                expect(error.toString()).to.include("UnauthorizedOperation");
+                console.log("✅ Only the arbiter can resolve");
+            }
+        });
+    });
+
+    describe("Vesting", () => {
+        it("Reports the claimable amount along the vesting schedule", async () => {
+            const amount = new BN(LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount,
+                timeoutPeriod: new BN(60),
+                options: { vesting: true },
+            });
+            await airdrop(sl.publicKey, LAMPORTS_PER_SOL);
+
+            const claimableNow = async () =>
+                (await program.methods.vestedClaimable().accounts({ escrow }).view()) as BN;
+            const claim = () =>
+                program.methods
+                    .claimVested()
+                    .accounts({ escrow, seller: sl.publicKey, globalStats, config })
+                    .signers([sl])
+                    .rpc();
+
+            // Nothing is claimable before funding
+            expect((await claimableNow()).toNumber()).to.equal(0);
+
+            await fundEscrow(escrow, b);
+            await sleep(6000);
+            const early = await claimableNow();
+            assert.ok(early.gtn(0) && early.lt(amount));
+
+            // The claim pays at least what was reported, and resets the counter
+            await claim();
+            const claimed = (await program.account.escrow.fetch(escrow)).vestedClaimed;
+            assert.ok(claimed.gte(early));
+            assert.ok((await claimableNow()).lt(early));
+
+            // Once fully vested the query and the claim agree exactly
+            await sleep(60_000);
+            const rest = await claimableNow();
+            expect(rest.toString()).to.equal(amount.sub(claimed).toString());
+            const sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            await claim();
+            const received = (await provider.connection.getBalance(sl.publicKey)) - sellerBefore;
+            expect(received).to.equal(rest.toNumber());
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ released: {} });
+            expect((await claimableNow()).toNumber()).to.equal(0);
+            console.log("✅ Vested claimable amount matches claim_vested");
+        });
+
+        it("Lets the seller claim vested funds and refunds only the remainder", async () => {
+            const amount = new BN(LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount,
+                timeoutPeriod: new BN(60),
+                options: { vesting: true },
+            });
+            await airdrop(sl.publicKey, LAMPORTS_PER_SOL);
+            await fundEscrow(escrow, b);
+
+            await sleep(6000);
+
+            const sellerBalanceBefore = await provider.connection.getBalance(sl.publicKey);
+            await program.methods
+                .claimVested()
+                .accounts({ escrow, seller: sl.publicKey, globalStats, config })
+                .signers([sl])
+                .rpc();
+
+            let escrowAccount = await program.account.escrow.fetch(escrow);
+            const claimed = escrowAccount.vestedClaimed;
+            assert.ok(claimed.gtn(0) && claimed.lt(amount));
+            assert.ok((await provider.connection.getBalance(sl.publicKey)) > sellerBalanceBefore);
+
+            const buyerBalanceBefore = await provider.connection.getBalance(b.publicKey);
+            await program.methods
+                .refundToBuyer()
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    counterparty: null,
+                    receipt: null,
+                    buyer: b.publicKey,
+                    seller: sl.publicKey,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+
+            escrowAccount = await program.account.escrow.fetch(escrow);
+            const refunded = (await provider.connection.getBalance(b.publicKey)) - buyerBalanceBefore;
+            assert.equal(escrowAccount.state.refunded !== undefined, true);
+            // Buyer only gets back what had not vested by the time of the refund
+            assert.ok(refunded < amount.sub(claimed).toNumber());
+            console.log("✅ Vested funds claimed, remainder refunded");
+        });
+
+        it("Has the arbiter decide only the remainder after a vested claim", async () => {
+            const amount = new BN(LAMPORTS_PER_SOL);
+            const vestingArbiter = Keypair.generate();
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount,
+                timeoutPeriod: new BN(60),
+                arbiter: vestingArbiter.publicKey,
+                options: { vesting: true },
+            });
+            await fundEscrow(escrow, b);
+            await sleep(6000);
+
+            await program.methods
+                .claimVested()
+                .accounts({ escrow, seller: sl.publicKey, globalStats, config })
+                .signers([sl])
+                .rpc();
+            const claimed = (await program.account.escrow.fetch(escrow)).vestedClaimed;
+            assert.ok(claimed.gtn(0) && claimed.lt(amount));
+
+            await program.methods
+                .openDispute()
+                .accounts({ escrow, config, caller: b.publicKey, systemProgram: SystemProgram.programId })
+                .signers([b])
+                .rpc();
+            await program.methods
+                .arbiterResolve({ releaseToSeller: {} }, Array.from(Buffer.alloc(32)))
+                .accounts({
+                    escrow,
+                    seller: sl.publicKey,
+                    buyer: b.publicKey,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    arbiter: vestingArbiter.publicKey,
+                    arbiterStats: null,
+                    arbiterIndex: null,
+                })
+                .signers([vestingArbiter])
+                .rpc();
+
+            // Claimed part plus resolved remainder add up to the amount, once
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ released: {} });
+            expect(await provider.connection.getBalance(sl.publicKey)).to.equal(amount.toNumber());
+            console.log("✅ Arbiter resolved only the unclaimed remainder");
+        });
+
+        it("Rejects claims on a non-vesting escrow", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await program.methods
+                    .claimVested()
+                    .accounts({ escrow, seller: sl.publicKey, globalStats, config })
+                    .signers([sl])
+                    .rpc();
+
+                assert.fail("Should reject claim on non-vesting escrow");
+            } catch (error) {
+                expect(error.toString()).to.include("NotVesting");
+                console.log("✅ Claims limited to vesting escrows");
+            }
+        });
+    });
+
+    describe("Minimum Partial Release", () => {
+        const amount = new BN(LAMPORTS_PER_SOL);
+
+        const setMinPartialRelease = (min: BN) =>
+            program.methods
+                .setMinPartialRelease(min)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+        const setupVesting = async () => {
+            const parties = await setupEscrow({ amount, timeoutPeriod: new BN(60), options: { vesting: true } });
+            await airdrop(parties.seller.publicKey, LAMPORTS_PER_SOL);
+            await fundEscrow(parties.escrow, parties.buyer);
+            return parties;
+        };
+
+        const claim = (escrow: PublicKey, sl: Keypair) =>
+            program.methods
+                .claimVested()
+                .accounts({ escrow, seller: sl.publicKey, globalStats, config })
+                .signers([sl])
+                .rpc();
+
+        // Above the whole escrow, so only a claim that empties it can pass
+        before(async () => {
+            await setMinPartialRelease(amount.muln(2));
+        });
+
+        after(async () => {
+            await setMinPartialRelease(new BN(0));
+        });
+
+        it("Rejects a partial claim below the minimum", async () => {
+            const { seller: sl, escrow } = await setupVesting();
+            await sleep(3000);
+
+            try {
+                await claim(escrow, sl);
+                assert.fail("Dust claim should be rejected");
+            } catch (error) {
+                expect(error.toString()).to.include("BelowMinimumRelease");
+            }
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.vestedClaimed.toNumber()).to.equal(0);
+            console.log("✅ Partial claim below the minimum rejected");
+        });
+
+        it("Allows a final claim below the minimum that empties the escrow", async () => {
+            const { seller: sl, escrow } = await setupVesting();
+            await sleep(62_000);
+
+            const sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            await claim(escrow, sl);
+            const received = (await provider.connection.getBalance(sl.publicKey)) - sellerBefore;
+            expect(received).to.equal(amount.toNumber());
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ released: {} });
+            console.log("✅ Final claim below the minimum allowed");
+        });
+    });
+
+    describe("System Program Validation", () => {
+        it("Rejects funding with a spoofed system program", async () => {
+            const { buyer: b, escrow } = await setupEscrow();
+            const spoofed = Keypair.generate();
+
+            try {
+                await program.methods
+                    .fundEscrow()
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        buyer: b.publicKey,
+                        systemProgram: spoofed.publicKey,
+                    })
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Should reject a spoofed system program");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidProgramId");
+                console.log("✅ Spoofed system program rejected");
+            }
+        });
+
+        it("Rejects initializing with a spoofed system program", async () => {
+            const spoofBuyer = Keypair.generate();
+            const spoofSeller = Keypair.generate();
+            await airdrop(spoofBuyer.publicKey, LAMPORTS_PER_SOL);
+
+            try {
+                await program.methods
+                    .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60), escrowOptions())
+                    .accounts({
+                        escrow: findEscrowPda(spoofBuyer.publicKey, spoofSeller.publicKey),
+                        buyer: spoofBuyer.publicKey,
+                        seller: spoofSeller.publicKey,
+                        arbiter: spoofBuyer.publicKey,
+                        cooldown: findCooldownPda(spoofBuyer.publicKey, spoofSeller.publicKey),
+                        config,
+                        arbiterStake: null,
+                        arbiterIndex: null,
+                        systemProgram: program.programId,
+                    })
+                    .signers([spoofBuyer])
+                    .rpc();
+
+                assert.fail("Should reject a spoofed system program");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidProgramId");
+                console.log("✅ Spoofed system program rejected at init");
+            }
+        });
+    });
+
+    describe("Terms Binding", () => {
+        it("Gates funding on both parties binding the terms", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                options: { requiresTerms: true },
+            });
+
+            try {
+                await fundEscrow(escrow, b);
+                assert.fail("Should not fund before terms are bound");
+            } catch (error) {
+                expect(error.toString()).to.include("TermsNotBound");
+            }
+
+            const termsHash = Array.from(Buffer.alloc(32, 1));
+            await program.methods
+                .bindTerms(termsHash)
+                .accounts({ escrow, buyer: b.publicKey, seller: sl.publicKey })
+                .signers([b, sl])
+                .rpc();
+
+            await fundEscrow(escrow, b);
+
+            const escrowAccount = await program.account.escrow.fetch(escrow);
+            assert.equal(escrowAccount.termsBound, true);
+            assert.deepEqual(Array.from(escrowAccount.termsHash), termsHash);
+            assert.equal(escrowAccount.state.funded !== undefined, true);
+            console.log("✅ Funding unlocked once terms were bound");
+        });
+
+        it("Rejects re-binding to different terms", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+
+            await program.methods
+                .bindTerms(Array.from(Buffer.alloc(32, 1)))
+                .accounts({ escrow, buyer: b.publicKey, seller: sl.publicKey })
+                .signers([b, sl])
+                .rpc();
+
+            try {
+                await program.methods
+                    .bindTerms(Array.from(Buffer.alloc(32, 2)))
+                    .accounts({ escrow, buyer: b.publicKey, seller: sl.publicKey })
+                    .signers([b, sl])
+                    .rpc();
+
+                assert.fail("Should reject different terms");
+            } catch (error) {
+                expect(error.toString()).to.include("TermsAlreadyBound");
+                console.log("✅ Bound terms are immutable");
+            }
+        });
+    });
+
+    describe("Unusable Refund Recipient", () => {
+        it("Surfaces a clear error when the buyer wallet was drained", async () => {
+            // Small enough that the refund alone can't make an empty wallet rent-exempt
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount: new BN(100_000),
+            });
+            await fundEscrow(escrow, b);
+            await airdrop(sl.publicKey, LAMPORTS_PER_SOL);
+
+            // Empty the buyer wallet entirely, the provider pays the fee
+            const remaining = await provider.connection.getBalance(b.publicKey);
+            await provider.sendAndConfirm(
+                new anchor.web3.Transaction().add(
+                    SystemProgram.transfer({
+                        fromPubkey: b.publicKey,
+                        toPubkey: provider.wallet.publicKey,
+                        lamports: remaining,
+                    })
+                ),
+                [b]
+            );
+
+            try {
+                await program.methods
+                    .refundToBuyer()
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        counterparty: null,
+                        receipt: null,
+                        buyer: b.publicKey,
+                        seller: sl.publicKey,
+                        caller: sl.publicKey,
+                    })
+                    .signers([sl])
+                    .rpc();
+
+                assert.fail("Should reject refund to a drained wallet");
+            } catch (error) {
+                expect(error.toString()).to.include("RefundRecipientUnusable");
+                console.log("✅ Unusable refund recipient reported clearly");
+            }
+        });
+    });
+
+    describe("Fresh Seller Account", () => {
+        const releaseToFresh = (escrow: PublicKey, b: Keypair, sl: PublicKey) =>
+            program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+
+        it("Creates a never-seen seller account at a rent-exempt balance", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+            expect(await provider.connection.getBalance(sl.publicKey)).to.equal(0);
+
+            await releaseToFresh(escrow, b, sl.publicKey);
+
+            const rent = await provider.connection.getMinimumBalanceForRentExemption(0);
+            expect(await provider.connection.getBalance(sl.publicKey)).to.be.at.least(rent);
+            console.log("✅ Fresh seller account is rent-exempt");
+        });
+
+        it("Rejects a payout too small to make a fresh seller rent-exempt", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount: new BN(100_000) });
+            await fundEscrow(escrow, b);
+
+            try {
+                await releaseToFresh(escrow, b, sl.publicKey);
+                assert.fail("Should reject a sub-rent payout to a fresh seller");
+            } catch (error) {
+                expect(error.toString()).to.include("SellerBelowRent");
+                console.log("✅ Sub-rent payout to a fresh seller rejected");
+            }
+        });
+    });
+
+    describe("Escrow Labels", () => {
+        it("Stores the label and exposes it through describe_escrow", async () => {
+            const label = Array.from(Buffer.concat([Buffer.from("order-4821"), Buffer.alloc(6)]));
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                options: { label },
+            });
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.label).to.deep.equal(label);
+
+            const description = await program.methods
+                .describeEscrow()
+                .accounts({ escrow })
+                .view();
+            expect(description.label).to.deep.equal(label);
+            expect(description.buyer.toString()).to.equal(b.publicKey.toString());
+            expect(description.seller.toString()).to.equal(sl.publicKey.toString());
+            expect(description.state).to.deep.equal({ created: {} });
+
+            console.log("✅ Label stored and described");
+        });
+
+        it("Resolves the buyer sentinel to no effective arbiter", async () => {
+            const arbiterKey = Keypair.generate().publicKey;
+            const withArbiter = await setupEscrow({ arbiter: arbiterKey });
+            // setupEscrow passes the buyer as arbiter by default
+            const withoutArbiter = await setupEscrow();
+
+            const effective = await program.methods
+                .effectiveArbiter()
+                .accounts({ escrow: withArbiter.escrow })
+                .view();
+            expect(effective.toString()).to.equal(arbiterKey.toString());
+
+            const none = await program.methods
+                .effectiveArbiter()
+                .accounts({ escrow: withoutArbiter.escrow })
+                .view();
+            expect(none).to.be.null;
+
+            const description = await program.methods
+                .describeEscrow()
+                .accounts({ escrow: withoutArbiter.escrow })
+                .view();
+            expect(description.arbiter).to.be.null;
+
+            console.log("✅ Effective arbiter resolved");
+        });
+    });
+
+    describe("Escrow Categories", () => {
+        it("Stores, emits and describes the category", async () => {
+            const b = Keypair.generate();
+            const sl = Keypair.generate();
+            await airdrop(b.publicKey, LAMPORTS_PER_SOL);
+            const escrow = findEscrowPda(b.publicKey, sl.publicKey);
+
+            const signature = await program.methods
+                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60), escrowOptions({ category: 2 }))
+                .accounts({
+                    escrow,
+                    buyer: b.publicKey,
+                    seller: sl.publicKey,
+                    arbiter: b.publicKey,
+                    cooldown: findCooldownPda(b.publicKey, sl.publicKey),
+                    config,
+                    arbiterStake: null,
+                    arbiterIndex: null,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b])
+                .rpc({ commitment: "confirmed" });
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.category).to.deep.equal({ rental: {} });
+
+            const tx = await provider.connection.getTransaction(signature, { commitment: "confirmed" });
+            const parser = new anchor.EventParser(program.programId, program.coder);
+            const initialized = Array.from(parser.parseLogs(tx.meta.logMessages)).find(
+                (e) => e.name === "escrowInitialized"
+            );
+            assert.ok(initialized);
+            expect(initialized.data.category).to.deep.equal({ rental: {} });
+
+            const description = await program.methods.describeEscrow().accounts({ escrow }).view();
+            expect(description.category).to.deep.equal({ rental: {} });
+            console.log("✅ Category stored, emitted and described");
+        });
+
+        it("Defaults to Goods", async () => {
+            const { escrow } = await setupEscrow();
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.category).to.deep.equal({ goods: {} });
+            console.log("✅ Default category is Goods");
+        });
+
+        it("Rejects a category code outside the enum", async () => {
+            try {
+                await setupEscrow({ options: { category: 4 } });
+                assert.fail("Unknown category should fail");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidCategory");
+                console.log("✅ Unknown category rejected");
+            }
+        });
+    });
+
+    describe("Batch Close", () => {
+        it("Closes several released escrows and returns rent to each buyer", async () => {
+            const parties = [await setupEscrow(), await setupEscrow()];
+
+            for (const { buyer: b, seller: sl, escrow } of parties) {
+                await fundEscrow(escrow, b);
+                await program.methods
+                    .releaseToSeller(null)
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: sl.publicKey,
+                        caller: b.publicKey,
+                    })
+                    .signers([b])
+                    .rpc();
+            }
+
+            const balancesBefore = await Promise.all(
+                parties.map(({ buyer: b }) => provider.connection.getBalance(b.publicKey))
+            );
+
+            await program.methods
+                .closeEscrowsBatch()
+                .accounts({ caller: provider.wallet.publicKey })
+                .remainingAccounts(
+                    parties.flatMap(({ buyer: b, escrow }) => [
+                        { pubkey: escrow, isWritable: true, isSigner: false },
+                        { pubkey: b.publicKey, isWritable: true, isSigner: false },
+                    ])
+                )
+                .rpc();
+
+            for (const [i, { buyer: b, escrow }] of parties.entries()) {
+                expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+                const balanceAfter = await provider.connection.getBalance(b.publicKey);
+                expect(balanceAfter).to.be.greaterThan(balancesBefore[i]);
+            }
+
+            console.log("✅ Settled escrows closed in one call");
+        });
+
+        it("Rejects escrows that are still open", async () => {
+            const { buyer: b, escrow } = await setupEscrow();
+
+            try {
+                await program.methods
+                    .closeEscrowsBatch()
+                    .accounts({ caller: provider.wallet.publicKey })
+                    .remainingAccounts([
+                        { pubkey: escrow, isWritable: true, isSigner: false },
+                        { pubkey: b.publicKey, isWritable: true, isSigner: false },
+                    ])
+                    .rpc();
+
+                assert.fail("Should not close an unsettled escrow");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidState");
+                console.log("✅ Open escrow not closeable");
+            }
+        });
+    });
+
+    describe("Escrow Archive", () => {
+        const findArchivePda = (escrow: PublicKey, createdAt: BN) =>
+            PublicKey.findProgramAddressSync(
+                [Buffer.from("archive"), escrow.toBuffer(), createdAt.toArrayLike(Buffer, "le", 8)],
+                program.programId
+            )[0];
+
+        const archiveEscrow = (escrow: PublicKey, archive: PublicKey, b: Keypair) =>
+            program.methods
+                .archiveEscrow()
+                .accounts({ escrow, archive, buyer: b.publicKey, systemProgram: SystemProgram.programId })
+                .signers([b])
+                .rpc();
+
+        it("Archives a released escrow's summary, then closes it", async () => {
+            const label = Array.from(Buffer.from("archive-0001".padEnd(16, "\0")));
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ options: { label } });
+            await fundEscrow(escrow, b);
+            await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl.publicKey,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+
+            const settled = await program.account.escrow.fetch(escrow);
+            const archive = findArchivePda(escrow, settled.createdAt);
+            await archiveEscrow(escrow, archive, b);
+
+            expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+            const summary = await program.account.escrowArchive.fetch(archive);
+            expect(summary.escrow.toString()).to.equal(escrow.toString());
+            expect(summary.buyer.toString()).to.equal(b.publicKey.toString());
+            expect(summary.seller.toString()).to.equal(sl.publicKey.toString());
+            expect(summary.arbiter).to.be.null;
+            expect(summary.amount.toString()).to.equal(settled.amount.toString());
+            expect(summary.fundedAmount.toString()).to.equal(settled.fundedAmount.toString());
+            expect(summary.finalState).to.deep.equal({ released: {} });
+            expect(summary.createdAt.toString()).to.equal(settled.createdAt.toString());
+            expect(summary.fundedAt.toString()).to.equal(settled.fundedAt.toString());
+            expect(summary.label).to.deep.equal(label);
+            expect(summary.lastEventHash).to.deep.equal(settled.lastEventHash);
+
+            console.log("✅ Escrow archived and closed");
+        });
+
+        it("Refuses to archive an escrow that hasn't settled", async () => {
+            const { buyer: b, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+            const open = await program.account.escrow.fetch(escrow);
+
+            try {
+                await archiveEscrow(escrow, findArchivePda(escrow, open.createdAt), b);
+                assert.fail("Only settled escrows can be archived");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidState");
+                console.log("✅ Open escrow not archivable");
+            }
+        });
+    });
+
+    describe("Batch Fund", () => {
+        async function setupBuyerEscrows(count: number) {
+            const b = Keypair.generate();
+            await airdrop(b.publicKey, 3 * LAMPORTS_PER_SOL);
+            const escrows: PublicKey[] = [];
+            for (let i = 0; i < count; i++) {
+                const sl = Keypair.generate().publicKey;
+                const escrow = findEscrowPda(b.publicKey, sl);
+                await program.methods
+                    .initializeEscrow(new BN(0.2 * LAMPORTS_PER_SOL), new BN(60), escrowOptions())
+                    .accounts({
+                        escrow,
+                        buyer: b.publicKey,
+                        seller: sl,
+                        arbiter: b.publicKey,
+                        cooldown: findCooldownPda(b.publicKey, sl),
+                        config,
+                        arbiterStake: null,
+                        arbiterIndex: null,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([b])
+                    .rpc();
+                escrows.push(escrow);
+            }
+            return { buyer: b, escrows };
+        }
+
+        const asRemaining = (escrows: PublicKey[]) =>
+            escrows.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false }));
+
+        it("Funds several of one buyer's escrows in a single call", async () => {
+            const { buyer: b, escrows } = await setupBuyerEscrows(3);
+
+            await program.methods
+                .fundEscrowsBatch(escrows.map(() => new BN(0.2 * LAMPORTS_PER_SOL)))
+                .accounts({ buyer: b.publicKey, globalStats, systemProgram: SystemProgram.programId })
+                .remainingAccounts(asRemaining(escrows))
+                .signers([b])
+                .rpc();
+
+            for (const escrow of escrows) {
+                const account = await program.account.escrow.fetch(escrow);
+                assert.deepEqual(account.state, { funded: {} });
+            }
+            console.log("✅ Batch funded three escrows");
+        });
+
+        it("Rejects a zero amount in the batch", async () => {
+            const { buyer: b, escrows } = await setupBuyerEscrows(1);
+
+            try {
+                await program.methods
+                    .fundEscrowsBatch([new BN(0)])
+                    .accounts({ buyer: b.publicKey, globalStats, systemProgram: SystemProgram.programId })
+                    .remainingAccounts(asRemaining(escrows))
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Zero batch amount should fail");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidAmount");
+                console.log("✅ Zero batch amount rejected");
+            }
+        });
+
+        it("Rejects a batch whose amounts don't match the escrows", async () => {
+            const { buyer: b, escrows } = await setupBuyerEscrows(2);
+
+            try {
+                await program.methods
+                    .fundEscrowsBatch([new BN(0.2 * LAMPORTS_PER_SOL), new BN(1)])
+                    .accounts({ buyer: b.publicKey, globalStats, systemProgram: SystemProgram.programId })
+                    .remainingAccounts(asRemaining(escrows))
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Mismatched amount should fail the batch");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidAmount");
+                console.log("✅ Mismatched batch amount rejected");
+            }
+        });
+    });
+
+    describe("Crowdfunding", () => {
+        const findContributionPda = (escrow: PublicKey, contributor: PublicKey) =>
+            PublicKey.findProgramAddressSync(
+                [Buffer.from("contribution"), escrow.toBuffer(), contributor.toBuffer()],
+                program.programId
+            )[0];
+
+        async function contribute(escrow: PublicKey, contributor: Keypair, amount: BN) {
+            await program.methods
+                .contribute(amount)
+                .accounts({
+                    escrow,
+                    contribution: findContributionPda(escrow, contributor.publicKey),
+                    contributor: contributor.publicKey,
+                    globalStats,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([contributor])
+                .rpc();
+        }
+
+        it("Rejects a zero contribution", async () => {
+            const fundingDeadline = new BN((await chainTime()) + 3600);
+            const { escrow } = await setupEscrow({ options: { fundingDeadline } });
+            const alice = Keypair.generate();
+            await airdrop(alice.publicKey, LAMPORTS_PER_SOL);
+
+            try {
+                await contribute(escrow, alice, new BN(0));
+                assert.fail("Zero contribution should fail");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidAmount");
+                console.log("✅ Zero contribution rejected");
+            }
+        });
+
+        it("Becomes funded once contributions reach the target", async () => {
+            const fundingDeadline = new BN((await chainTime()) + 3600);
+            const { escrow } = await setupEscrow({
+                amount: new BN(LAMPORTS_PER_SOL),
+                options: { fundingDeadline },
+            });
+
+            const alice = Keypair.generate();
+            const bob = Keypair.generate();
+            await airdrop(alice.publicKey, LAMPORTS_PER_SOL);
+            await airdrop(bob.publicKey, LAMPORTS_PER_SOL);
+
+            await contribute(escrow, alice, new BN(0.4 * LAMPORTS_PER_SOL));
+            let account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ created: {} });
+            expect(account.fundedAmount.toNumber()).to.equal(0.4 * LAMPORTS_PER_SOL);
+
+            await contribute(escrow, bob, new BN(0.6 * LAMPORTS_PER_SOL));
+            account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ funded: {} });
+
+            const share = await program.account.contribution.fetch(
+                findContributionPda(escrow, bob.publicKey)
+            );
+            expect(share.amount.toNumber()).to.equal(0.6 * LAMPORTS_PER_SOL);
+
+            console.log("✅ Crowdfunded escrow funded by two contributors");
+        });
+
+        it("Rejects contributions above the remaining target", async () => {
+            const fundingDeadline = new BN((await chainTime()) + 3600);
+            const { escrow } = await setupEscrow({
+                amount: new BN(0.5 * LAMPORTS_PER_SOL),
+                options: { fundingDeadline },
+            });
+
+            const alice = Keypair.generate();
+            await airdrop(alice.publicKey, LAMPORTS_PER_SOL);
+
+            try {
+                await contribute(escrow, alice, new BN(0.6 * LAMPORTS_PER_SOL));
+                assert.fail("Should not overfill the target");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidAmount");
+                console.log("✅ Overfilling contribution rejected");
+            }
+        });
+
+        it("Lets contributors reclaim after a missed deadline", async () => {
+            const fundingDeadline = new BN((await chainTime()) + 3);
+            const { escrow } = await setupEscrow({
+                amount: new BN(LAMPORTS_PER_SOL),
+                options: { fundingDeadline },
+            });
+
+            const alice = Keypair.generate();
+            await airdrop(alice.publicKey, LAMPORTS_PER_SOL);
+            await contribute(escrow, alice, new BN(0.3 * LAMPORTS_PER_SOL));
+
+            const contribution = findContributionPda(escrow, alice.publicKey);
+            const reclaim = () =>
+                program.methods
+                    .reclaimContribution()
+                    .accounts({ escrow, contribution, contributor: alice.publicKey })
+                    .signers([alice])
+                    .rpc();
+
+            try {
+                await reclaim();
+                assert.fail("Should not reclaim before the deadline");
+            } catch (error) {
+                expect(error.toString()).to.include("TimeoutNotReached");
+            }
+
+            await sleep(5000);
+            const balanceBefore = await provider.connection.getBalance(alice.publicKey);
+            await reclaim();
+            const balanceAfter = await provider.connection.getBalance(alice.publicKey);
+
+            expect(balanceAfter - balanceBefore).to.be.at.least(0.3 * LAMPORTS_PER_SOL);
+            expect(await provider.connection.getAccountInfo(contribution)).to.be.null;
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.fundedAmount.toNumber()).to.equal(0);
+
+            console.log("✅ Contribution reclaimed after missed deadline");
+        });
+
+        it("Rejects fund_escrow on crowdfunded escrows", async () => {
+            const fundingDeadline = new BN((await chainTime()) + 3600);
+            const { buyer: b, escrow } = await setupEscrow({ options: { fundingDeadline } });
+
+            try {
+                await fundEscrow(escrow, b);
+                assert.fail("Should require contribute");
+            } catch (error) {
+                expect(error.toString()).to.include("ContributionsOnly");
+                console.log("✅ Crowdfunded escrow only accepts contributions");
+            }
+        });
+
+        const settleGoal = (escrow: PublicKey, seller: PublicKey) =>
+            program.methods
+                .settleGoal()
+                .accounts({ escrow, seller, globalStats, config, caller: provider.wallet.publicKey })
+                .rpc();
+
+        it("Pays the seller once a joint purchase reaches its goal", async () => {
+            const fundingDeadline = new BN((await chainTime()) + 3600);
+            const { seller: s, escrow } = await setupEscrow({
+                amount: new BN(LAMPORTS_PER_SOL),
+                options: { fundingDeadline, releaseOnGoal: true },
+            });
+
+            const alice = Keypair.generate();
+            const bob = Keypair.generate();
+            await airdrop(alice.publicKey, LAMPORTS_PER_SOL);
+            await airdrop(bob.publicKey, LAMPORTS_PER_SOL);
+
+            await contribute(escrow, alice, new BN(0.4 * LAMPORTS_PER_SOL));
+            try {
+                await settleGoal(escrow, s.publicKey);
+                assert.fail("Should not settle before the goal or deadline");
+            } catch (error) {
+                expect(error.toString()).to.include("TimeoutNotReached");
+            }
+
+            await contribute(escrow, bob, new BN(0.6 * LAMPORTS_PER_SOL));
+            await settleGoal(escrow, s.publicKey);
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ released: {} });
+            expect(await provider.connection.getBalance(s.publicKey)).to.equal(LAMPORTS_PER_SOL);
+
+            console.log("✅ Joint purchase released to the seller");
+        });
+
+        it("Opens reclaims when a joint purchase misses its goal", async () => {
+            const fundingDeadline = new BN((await chainTime()) + 3);
+            const { seller: s, escrow } = await setupEscrow({
+                amount: new BN(LAMPORTS_PER_SOL),
+                options: { fundingDeadline, releaseOnGoal: true },
+            });
+
+            const alice = Keypair.generate();
+            await airdrop(alice.publicKey, LAMPORTS_PER_SOL);
+            await contribute(escrow, alice, new BN(0.3 * LAMPORTS_PER_SOL));
+
+            await sleep(5000);
+            await settleGoal(escrow, s.publicKey);
+
+            let account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ refunded: {} });
+            expect(await provider.connection.getBalance(s.publicKey)).to.equal(0);
+
+            const contribution = findContributionPda(escrow, alice.publicKey);
+            await program.methods
+                .reclaimContribution()
+                .accounts({ escrow, contribution, contributor: alice.publicKey })
+                .signers([alice])
+                .rpc();
+
+            account = await program.account.escrow.fetch(escrow);
+            expect(account.fundedAmount.toNumber()).to.equal(0);
+
+            console.log("✅ Failed joint purchase refunded to contributors");
+        });
+
+        it("Won't settle a met goal while a dispute is open", async () => {
+            const fundingDeadline = new BN((await chainTime()) + 3600);
+            const { buyer: b, seller: s, escrow } = await setupEscrow({
+                amount: new BN(LAMPORTS_PER_SOL),
+                arbiter: Keypair.generate().publicKey,
+                options: { fundingDeadline, releaseOnGoal: true },
+            });
+
+            const alice = Keypair.generate();
+            await airdrop(alice.publicKey, 2 * LAMPORTS_PER_SOL);
+            await contribute(escrow, alice, new BN(LAMPORTS_PER_SOL));
+            await program.methods
+                .openDispute()
+                .accounts({ escrow, config, caller: b.publicKey, systemProgram: SystemProgram.programId })
+                .signers([b])
+                .rpc();
+
+            try {
+                await settleGoal(escrow, s.publicKey);
+                assert.fail("A disputed joint purchase is the arbiter's to settle");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidState");
+            }
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ funded: {} });
+            console.log("✅ Disputed joint purchase not settled by the crank");
+        });
+
+        it("Rejects release_on_goal without a funding deadline", async () => {
+            try {
+                await setupEscrow({ options: { releaseOnGoal: true } });
+                assert.fail("Should require a funding deadline");
+            } catch (error) {
+                expect(error.toString()).to.include("NotCrowdfunded");
+                console.log("✅ release_on_goal requires crowdfunding");
+            }
+        });
+    });
+
+    describe("Release Simulation", () => {
+        it("Reports eligibility and a reason code without moving funds", async () => {
+            const amount = new BN(0.5 * LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount,
+                timeoutPeriod: new BN(3600),
+            });
+            await fundEscrow(escrow, b);
+
+            const simulate = (caller: PublicKey) =>
+                program.methods
+                    .simulateRelease()
+                    .accounts({ escrow, config, cosigner: null, caller })
+                    .view();
+
+            const forBuyer = await simulate(b.publicKey);
+            expect(forBuyer.allowed).to.be.true;
+            expect(forBuyer.reason).to.equal(0);
+            expect(forBuyer.releasable.toString()).to.equal(amount.toString());
+
+            // Seller before the timeout: UnauthorizedOperation (6000 + 2)
+            const forSeller = await simulate(sl.publicKey);
+            expect(forSeller.allowed).to.be.false;
+            expect(forSeller.reason).to.equal(6002);
+            expect(forSeller.releasable.toNumber()).to.equal(0);
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ funded: {} });
+
+            console.log("✅ Release eligibility simulated");
+        });
+
+        it("Reports the seller's payout net of the referral fee", async () => {
+            const amount = new BN(LAMPORTS_PER_SOL);
+            const referrer = Keypair.generate();
+            await airdrop(referrer.publicKey, LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount,
+                options: { referrer: referrer.publicKey, referralFeeBps: 500 },
+            });
+            await fundEscrow(escrow, b);
+
+            const simulated = await program.methods
+                .simulateRelease()
+                .accounts({ escrow, config, cosigner: null, caller: b.publicKey })
+                .view();
+            expect(simulated.releasable.toNumber()).to.equal(0.95 * LAMPORTS_PER_SOL);
+
+            const sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: referrer.publicKey,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl.publicKey,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+
+            const received = (await provider.connection.getBalance(sl.publicKey)) - sellerBefore;
+            expect(received).to.equal(simulated.releasable.toNumber());
+            console.log("✅ Simulated payout matches the release");
+        });
+    });
+
+    describe("Default Arbiter", () => {
+        const houseArbiter = Keypair.generate();
+
+        before(async () => {
+            await program.methods
+                .setDefaultArbiter(houseArbiter.publicKey)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+        });
+
+        after(async () => {
+            await program.methods
+                .setDefaultArbiter(null)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+        });
+
+        it("Assigns the config default when no arbiter is given", async () => {
+            const { escrow } = await setupEscrow();
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.arbiter.toString()).to.equal(houseArbiter.publicKey.toString());
+
+            console.log("✅ Default arbiter applied");
+        });
+
+        it("Lets both parties fall back to the default arbiter", async () => {
+            const compromised = Keypair.generate();
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                arbiter: compromised.publicKey,
+            });
+
+            await program.methods
+                .fallbackToDefaultArbiter()
+                .accounts({ escrow, config, buyer: b.publicKey, seller: sl.publicKey })
+                .signers([b, sl])
+                .rpc();
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.arbiter.toString()).to.equal(houseArbiter.publicKey.toString());
+
+            console.log("✅ Arbiter reassigned to the default");
+        });
+
+        it("Lets an explicit arbiter override the default", async () => {
+            const explicitArbiter = Keypair.generate();
+            const { escrow } = await setupEscrow({ arbiter: explicitArbiter.publicKey });
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.arbiter.toString()).to.equal(explicitArbiter.publicKey.toString());
+
+            console.log("✅ Explicit arbiter takes precedence");
+        });
+    });
+
+    describe("Arbiter Fee Renegotiation", () => {
+        it("Lets the arbiter update its fee before funding", async () => {
+            const arbiterKp = Keypair.generate();
+            const { escrow } = await setupEscrow({ arbiter: arbiterKp.publicKey });
+
+            await program.methods
+                .updateArbiterFee(250)
+                .accounts({ escrow, config, caller: arbiterKp.publicKey, counterparty: null })
+                .signers([arbiterKp])
+                .rpc();
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.arbiterFeeBps).to.equal(250);
+
+            console.log("✅ Arbiter fee renegotiated");
+        });
+
+        it("Requires both buyer and seller when the parties update it", async () => {
+            const arbiterKp = Keypair.generate();
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                arbiter: arbiterKp.publicKey,
+            });
+
+            try {
+                await program.methods
+                    .updateArbiterFee(100)
+                    .accounts({ escrow, config, caller: b.publicKey, counterparty: null })
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Buyer alone should not change the fee");
+            } catch (error) {
+                expect(error.toString()).to.include("UnauthorizedOperation");
+            }
+
+            await program.methods
+                .updateArbiterFee(100)
+                .accounts({ escrow, config, caller: b.publicKey, counterparty: sl.publicKey })
+                .signers([b, sl])
+                .rpc();
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.arbiterFeeBps).to.equal(100);
+
+            console.log("✅ Mutual fee update accepted");
+        });
+
+        it("Rejects fees above the config cap", async () => {
+            const arbiterKp = Keypair.generate();
+            const { escrow } = await setupEscrow({ arbiter: arbiterKp.publicKey });
+
+            await program.methods
+                .setMaxArbiterFee(500)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+            try {
+                await program.methods
+                    .updateArbiterFee(501)
+                    .accounts({ escrow, config, caller: arbiterKp.publicKey, counterparty: null })
+                    .signers([arbiterKp])
+                    .rpc();
+
+                assert.fail("Should reject a fee above the cap");
+            } catch (error) {
+                expect(error.toString()).to.include("FeeAboveCap");
+                console.log("✅ Fee cap enforced");
+            } finally {
+                await program.methods
+                    .setMaxArbiterFee(10_000)
+                    .accounts({ config, authority: provider.wallet.publicKey })
+                    .rpc();
+            }
+        });
+
+        it("Rejects updates once funded", async () => {
+            const arbiterKp = Keypair.generate();
+            const { buyer: b, escrow } = await setupEscrow({ arbiter: arbiterKp.publicKey });
+            await fundEscrow(escrow, b);
+
+            try {
+                await program.methods
+                    .updateArbiterFee(100)
+                    .accounts({ escrow, config, caller: arbiterKp.publicKey, counterparty: null })
+                    .signers([arbiterKp])
+                    .rpc();
+
+                assert.fail("Should reject after funding");
+            } catch (error) {
+                expect(error.toString()).to.include("EscrowAlreadyFunded");
+                console.log("✅ Fee locked after funding");
+            }
+        });
+    });
+
+    describe("NFT Escrows", () => {
+        const findNftEscrowPda = (mint: PublicKey) =>
+            PublicKey.findProgramAddressSync(
+                [Buffer.from("nft_escrow"), mint.toBuffer()],
+                program.programId
+            )[0];
+
+        // Mints a 0-decimal, supply-1 token into a fresh ATA of `owner`
+        async function mintNft(owner: Keypair) {
+            const mint = await createMint(provider.connection, owner, owner.publicKey, null, 0);
+            const ownerToken = await getOrCreateAssociatedTokenAccount(
+                provider.connection,
+                owner,
+                mint,
+                owner.publicKey
+            );
+            await mintTo(provider.connection, owner, mint, ownerToken.address, owner, 1);
+            return { mint, ownerToken: ownerToken.address };
+        }
+
+        async function listNft(price: BN) {
+            const nftSeller = Keypair.generate();
+            const nftBuyer = Keypair.generate();
+            await airdrop(nftSeller.publicKey, LAMPORTS_PER_SOL);
+            await airdrop(nftBuyer.publicKey, 2 * LAMPORTS_PER_SOL);
+
+            const { mint, ownerToken: sellerToken } = await mintNft(nftSeller);
+            const nftEscrow = findNftEscrowPda(mint);
+            const vault = getAssociatedTokenAddressSync(mint, nftEscrow, true);
+
+            await program.methods
+                .initializeNftEscrow(price)
+                .accounts({
+                    nftEscrow,
+                    vault,
+                    mint,
+                    sellerToken,
+                    seller: nftSeller.publicKey,
+                    buyer: nftBuyer.publicKey,
+                })
+                .signers([nftSeller])
+                .rpc();
+
+            return { nftSeller, nftBuyer, mint, sellerToken, nftEscrow, vault };
+        }
+
+        it("Swaps the NFT for the price on settlement", async () => {
+            const price = new BN(0.5 * LAMPORTS_PER_SOL);
+            const { nftSeller, nftBuyer, mint, nftEscrow, vault } = await listNft(price);
+
+            expect(Number((await getAccount(provider.connection, vault)).amount)).to.equal(1);
+
+            await program.methods
+                .fundNftEscrow()
+                .accounts({ nftEscrow, buyer: nftBuyer.publicKey })
+                .signers([nftBuyer])
+                .rpc();
+
+            const sellerBefore = await provider.connection.getBalance(nftSeller.publicKey);
+            const buyerToken = getAssociatedTokenAddressSync(mint, nftBuyer.publicKey);
+
+            await program.methods
+                .settleNftEscrow()
+                .accounts({
+                    nftEscrow,
+                    vault,
+                    mint,
+                    buyerToken,
+                    buyer: nftBuyer.publicKey,
+                    seller: nftSeller.publicKey,
+                    caller: nftBuyer.publicKey,
+                })
+                .signers([nftBuyer])
+                .rpc();
+
+            expect(Number((await getAccount(provider.connection, buyerToken)).amount)).to.equal(1);
+            const sellerAfter = await provider.connection.getBalance(nftSeller.publicKey);
+            expect(sellerAfter - sellerBefore).to.be.at.least(price.toNumber());
+            expect(await provider.connection.getAccountInfo(nftEscrow)).to.be.null;
+
+            console.log("✅ NFT and SOL swapped atomically");
+        });
+
+        it("Returns each asset to its owner when a party backs out", async () => {
+            const price = new BN(0.5 * LAMPORTS_PER_SOL);
+            const { nftSeller, nftBuyer, mint, sellerToken, nftEscrow, vault } = await listNft(price);
+
+            await program.methods
+                .fundNftEscrow()
+                .accounts({ nftEscrow, buyer: nftBuyer.publicKey })
+                .signers([nftBuyer])
+                .rpc();
+
+            const buyerBefore = await provider.connection.getBalance(nftBuyer.publicKey);
+
+            await program.methods
+                .cancelNftEscrow()
+                .accounts({
+                    nftEscrow,
+                    vault,
+                    mint,
+                    sellerToken,
+                    buyer: nftBuyer.publicKey,
+                    seller: nftSeller.publicKey,
+                    caller: nftSeller.publicKey,
+                })
+                .signers([nftSeller])
+                .rpc();
+
+            expect(Number((await getAccount(provider.connection, sellerToken)).amount)).to.equal(1);
+            const buyerAfter = await provider.connection.getBalance(nftBuyer.publicKey);
+            expect(buyerAfter - buyerBefore).to.equal(price.toNumber());
+
+            console.log("✅ NFT escrow unwound");
+        });
+    });
+
+    describe("Default Arbiter Fallback", () => {
+        it("Rejects the fallback when no default is configured", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                arbiter: Keypair.generate().publicKey,
+            });
+
+            try {
+                await program.methods
+                    .fallbackToDefaultArbiter()
+                    .accounts({ escrow, config, buyer: b.publicKey, seller: sl.publicKey })
+                    .signers([b, sl])
+                    .rpc();
+
+                assert.fail("Should require a configured default arbiter");
+            } catch (error) {
+                expect(error.toString()).to.include("NoDefaultArbiter");
+                console.log("✅ Fallback needs a default arbiter");
+            }
+        });
+    });
+
+    describe("Role Validation", () => {
+        const initWithRoles = (b: Keypair, sl: PublicKey, arbiter: PublicKey) =>
+            program.methods
+                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60), escrowOptions())
+                .accounts({
+                    escrow: findEscrowPda(b.publicKey, sl),
+                    buyer: b.publicKey,
+                    seller: sl,
+                    arbiter,
+                    cooldown: findCooldownPda(b.publicKey, sl),
+                    config,
+                    arbiterStake: null,
+                    arbiterIndex: null,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b])
+                .rpc();
+
+        it("Rejects a buyer acting as its own seller", async () => {
+            const b = Keypair.generate();
+            await airdrop(b.publicKey, LAMPORTS_PER_SOL);
+
+            try {
+                await initWithRoles(b, b.publicKey, Keypair.generate().publicKey);
+                assert.fail("Buyer and seller must differ");
+            } catch (error) {
+                expect(error.toString()).to.include("DuplicateRole");
+                console.log("✅ Buyer-as-seller rejected");
+            }
+        });
+
+        it("Rejects the seller as arbiter", async () => {
+            const b = Keypair.generate();
+            const sl = Keypair.generate().publicKey;
+            await airdrop(b.publicKey, LAMPORTS_PER_SOL);
+
+            try {
+                await initWithRoles(b, sl, sl);
+                assert.fail("Seller can't arbitrate its own escrow");
+            } catch (error) {
+                expect(error.toString()).to.include("DuplicateRole");
+                console.log("✅ Seller-as-arbiter rejected");
+            }
+        });
+
+        it("Treats the buyer as arbiter as having no arbiter", async () => {
+            const b = Keypair.generate();
+            const sl = Keypair.generate().publicKey;
+            await airdrop(b.publicKey, LAMPORTS_PER_SOL);
+
+            await initWithRoles(b, sl, b.publicKey);
+            const account = await program.account.escrow.fetch(findEscrowPda(b.publicKey, sl));
+            expect(account.arbiter).to.be.null;
+
+            console.log("✅ Buyer sentinel collapsed to no arbiter");
+        });
+
+        it("Rejects falling back to a default arbiter that is a party", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                arbiter: Keypair.generate().publicKey,
+            });
+
+            for (const party of [sl.publicKey, b.publicKey]) {
+                await program.methods
+                    .setDefaultArbiter(party)
+                    .accounts({ config, authority: provider.wallet.publicKey })
+                    .rpc();
+                try {
+                    await program.methods
+                        .fallbackToDefaultArbiter()
+                        .accounts({ escrow, config, buyer: b.publicKey, seller: sl.publicKey })
+                        .signers([b, sl])
+                        .rpc();
+                    assert.fail("A party can't become the arbiter");
+                } catch (error) {
+                    expect(error.toString()).to.include("DuplicateRole");
+                } finally {
+                    await program.methods
+                        .setDefaultArbiter(null)
+                        .accounts({ config, authority: provider.wallet.publicKey })
+                        .rpc();
+                }
+            }
+            console.log("✅ Party-as-default-arbiter fallback rejected");
+        });
+    });
+
+    describe("Heartbeat", () => {
+        it("Records the last ping from a party", async () => {
+            const { seller: sl, escrow } = await setupEscrow();
+            const before = await program.account.escrow.fetch(escrow);
+            await sleep(1500);
+
+            await program.methods
+                .pingEscrow()
+                .accounts({ escrow, caller: sl.publicKey })
+                .signers([sl])
+                .rpc();
+
+            const after = await program.account.escrow.fetch(escrow);
+            assert.ok(after.lastActivity.gt(before.lastActivity));
+            console.log("✅ Ping updated last activity");
+        });
+    });
+
+    describe("Arbiter Renouncement", () => {
+        it("Lets the arbiter step down and clears the role", async () => {
+            const escrowArbiter = Keypair.generate();
+            const { escrow } = await setupEscrow({ arbiter: escrowArbiter.publicKey });
+
+            await program.methods
+                .renounceArbiter()
+                .accounts({ escrow, arbiter: escrowArbiter.publicKey })
+                .signers([escrowArbiter])
+                .rpc();
+
+            const account = await program.account.escrow.fetch(escrow);
+            assert.isNull(account.arbiter);
+            console.log("✅ Arbiter renounced");
+        });
+
+        it("Rejects renouncement by anyone but the arbiter", async () => {
+            const { seller: sl, escrow } = await setupEscrow({
+                arbiter: Keypair.generate().publicKey,
+            });
+
+            try {
+                await program.methods
+                    .renounceArbiter()
+                    .accounts({ escrow, arbiter: sl.publicKey })
+                    .signers([sl])
+                    .rpc();
+
+                assert.fail("Only the arbiter should renounce");
+            } catch (error) {
+                expect(error.toString()).to.include("UnauthorizedOperation");
+                console.log("✅ Non-arbiter renouncement rejected");
+            }
+        });
+    });
+
+    describe("Compute Budgets", () => {
+        // Documented per-instruction CU budgets. A failure here means an
+        // instruction got more expensive; raise the budget only on purpose.
+        const CU_BUDGETS = {
+            initializeEscrow: 40_000,
+            fundEscrow: 20_000,
+            releaseToSeller: 25_000,
+            refundToBuyer: 25_000,
+            cancelEscrow: 30_000,
+        };
+
+        async function computeUnits(signature: string): Promise<number> {
+            await provider.connection.confirmTransaction(signature, "confirmed");
+            const tx = await provider.connection.getTransaction(signature, {
+                commitment: "confirmed",
+                maxSupportedTransactionVersion: 0,
+            });
+            return tx.meta.computeUnitsConsumed;
+        }
+
+        function expectWithinBudget(name: keyof typeof CU_BUDGETS, units: number) {
+            console.log(`   ${name}: ${units} CU (budget ${CU_BUDGETS[name]})`);
+            expect(units, `${name} CU`).to.be.at.most(CU_BUDGETS[name]);
+        }
+
+        async function initialize(b: Keypair, sl: Keypair) {
+            const escrow = findEscrowPda(b.publicKey, sl.publicKey);
+            const signature = await program.methods
+                .initializeEscrow(new BN(0.5 * LAMPORTS_PER_SOL), new BN(60), escrowOptions())
+                .accounts({
+                    escrow,
+                    buyer: b.publicKey,
+                    seller: sl.publicKey,
+                    arbiter: b.publicKey,
+                    cooldown: findCooldownPda(b.publicKey, sl.publicKey),
+                    config,
+                    arbiterStake: null,
+                    arbiterIndex: null,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b])
+                .rpc();
+            return { escrow, signature };
+        }
+
+        async function fund(escrow: PublicKey, b: Keypair) {
+            return program.methods
+                .fundEscrow()
+                .accounts({
+                    escrow,
+                    globalStats,
+                    buyer: b.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b])
+                .rpc();
+        }
+
+        async function parties() {
+            const b = Keypair.generate();
+            const sl = Keypair.generate();
+            await airdrop(b.publicKey, 3 * LAMPORTS_PER_SOL);
+            return { b, sl };
+        }
+
+        it("Keeps initialize, fund and release within budget", async () => {
+            const { b, sl } = await parties();
+
+            const { escrow, signature: initSig } = await initialize(b, sl);
+            expectWithinBudget("initializeEscrow", await computeUnits(initSig));
+
+            expectWithinBudget("fundEscrow", await computeUnits(await fund(escrow, b)));
+
+            const releaseSig = await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl.publicKey,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+            expectWithinBudget("releaseToSeller", await computeUnits(releaseSig));
+        });
+
+        it("Keeps refund within budget", async () => {
+            const { b, sl } = await parties();
+            const { escrow } = await initialize(b, sl);
+            await fund(escrow, b);
+
+            const refundSig = await program.methods
+                .refundToBuyer()
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    counterparty: null,
+                    receipt: null,
+                    buyer: b.publicKey,
+                    seller: sl.publicKey,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+            expectWithinBudget("refundToBuyer", await computeUnits(refundSig));
+        });
+
+        it("Keeps cancel within budget", async () => {
+            const { b, sl } = await parties();
+            const { escrow } = await initialize(b, sl);
+
+            const cancelSig = await program.methods
+                .cancelEscrow(0)
+                .accounts({
+                    escrow,
+                    buyer: b.publicKey,
+                    cooldown: findCooldownPda(b.publicKey, sl.publicKey),
+                    config,
+                    caller: b.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([b])
+                .rpc();
+            expectWithinBudget("cancelEscrow", await computeUnits(cancelSig));
+        });
+    });
+
+    describe("No-Arbiter Fast Path", () => {
+        const releaseAs = (escrow: PublicKey, sl: PublicKey, caller: Keypair) =>
+            program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl,
+                    caller: caller.publicKey,
+                })
+                .signers([caller])
+                .rpc();
+
+        const setFee = (escrow: PublicKey, caller: Keypair, feeBps: number) =>
+            program.methods
+                .updateArbiterFee(feeBps)
+                .accounts({ escrow, config, caller: caller.publicKey, counterparty: null })
+                .signers([caller])
+                .rpc();
+
+        it("Releases in full on an escrow without an arbiter", async () => {
+            const amount = new BN(0.5 * LAMPORTS_PER_SOL);
+            // The buyer sentinel resolves to no arbiter at init
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount });
+            expect((await program.account.escrow.fetch(escrow)).arbiter).to.be.null;
+            await fundEscrow(escrow, b);
+
+            await releaseAs(escrow, sl.publicKey, b);
+
+            expect(await provider.connection.getBalance(sl.publicKey)).to.equal(amount.toNumber());
+            console.log("✅ No-arbiter release took the fast path without a fee");
+        });
+
+        it("Still rejects outsiders on the fast path", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await releaseAs(escrow, sl.publicKey, Keypair.generate());
+                assert.fail("Only the parties can release without an arbiter");
+            } catch (error) {
+                expect(error.toString()).to.include("UnauthorizedOperation");
+                console.log("✅ Outsider rejected on the fast path");
+            }
+        });
+
+        it("Keeps the arbiter's rights and fee on the full path", async () => {
+            const amount = new BN(0.5 * LAMPORTS_PER_SOL);
+            const arbiterKp = Keypair.generate();
+            await airdrop(arbiterKp.publicKey, LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount,
+                arbiter: arbiterKp.publicKey,
+            });
+            await setFee(escrow, arbiterKp, 500);
+            await fundEscrow(escrow, b);
+
+            const arbiterBefore = await provider.connection.getBalance(arbiterKp.publicKey);
+            await releaseAs(escrow, sl.publicKey, arbiterKp);
+
+            const fee = amount.toNumber() * 0.05;
+            expect(await provider.connection.getBalance(arbiterKp.publicKey)).to.equal(arbiterBefore + fee);
+            expect(await provider.connection.getBalance(sl.publicKey)).to.equal(amount.toNumber() - fee);
+            console.log("✅ Arbiter release took the full path and earned its fee");
+        });
+    });
+
+    describe("Appeals", () => {
+        const appealArbiter = Keypair.generate();
+        const appealWindow = 3;
+
+        before(async () => {
+            await program.methods
+                .setAppealPolicy(appealArbiter.publicKey, new BN(appealWindow))
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+        });
+
+        after(async () => {
+            await program.methods
+                .setAppealPolicy(null, new BN(0))
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+        });
+
+        async function resolvePending(decision: object) {
+            const decisionArbiter = Keypair.generate();
+            await airdrop(decisionArbiter.publicKey, LAMPORTS_PER_SOL);
+            const parties = await setupEscrow({ arbiter: decisionArbiter.publicKey });
+            await fundEscrow(parties.escrow, parties.buyer);
+
+            await program.methods
+                .arbiterResolve(decision, Array.from(Buffer.alloc(32, 1)))
+                .accounts({
+                    escrow: parties.escrow,
+                    seller: parties.seller.publicKey,
+                    buyer: parties.buyer.publicKey,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    arbiter: decisionArbiter.publicKey,
+                    arbiterStats: null,
+                    arbiterIndex: null,
+                })
+                .signers([decisionArbiter])
+                .rpc();
+
+            return { ...parties, decisionArbiter };
+        }
+
+        it("Holds the payout while the appeal window is open", async () => {
+            const { buyer: b, seller: sl, escrow, decisionArbiter } = await resolvePending({
+                releaseToSeller: {},
+            });
+
+            let account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ funded: {} });
+            expect(account.disputeState).to.deep.equal({ resolved: {} });
+
+            const finalize = () =>
+                program.methods
+                    .finalizeResolution()
+                    .accounts({
+                        escrow,
+                        seller: sl.publicKey,
+                        buyer: b.publicKey,
+                        globalStats,
+                        config,
+                    })
+                    .rpc();
+
+            try {
+                await finalize();
+                assert.fail("Should not finalize during the appeal window");
+            } catch (error) {
+                expect(error.toString()).to.include("AppealWindowOpen");
+            }
+
+            await sleep((appealWindow + 2) * 1000);
+            const sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            await finalize();
+
+            account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ released: {} });
+            expect(await provider.connection.getBalance(sl.publicKey)).to.be.greaterThan(sellerBefore);
+
+            console.log("✅ Unappealed decision finalized after the window");
+        });
+
+        it("Lets the losing party escalate to the appeal arbiter", async () => {
+            const { buyer: b, seller: sl, escrow } = await resolvePending({ releaseToSeller: {} });
+
+            // The seller won, so only the buyer can appeal
+            try {
+                await program.methods
+                    .appealDecision()
+                    .accounts({ escrow, appellant: sl.publicKey })
+                    .signers([sl])
+                    .rpc();
+                assert.fail("Winning party should not appeal");
+            } catch (error) {
+                expect(error.toString()).to.include("UnauthorizedOperation");
+            }
+
+            await program.methods
+                .appealDecision()
+                .accounts({ escrow, appellant: b.publicKey })
+                .signers([b])
+                .rpc();
+
+            let account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ funded: {} });
+            expect(account.disputeState).to.deep.equal({ underAppeal: {} });
+
+            const buyerBefore = await provider.connection.getBalance(b.publicKey);
+            await program.methods
+                .resolveAppeal({ refundToBuyer: {} }, Array.from(Buffer.alloc(32, 2)))
+                .accounts({
+                    escrow,
+                    seller: sl.publicKey,
+                    buyer: b.publicKey,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    appealArbiter: appealArbiter.publicKey,
+                })
+                .signers([appealArbiter])
+                .rpc();
+
+            account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ refunded: {} });
+            expect(account.arbiterDecision).to.deep.equal({ refundToBuyer: {} });
+            expect(await provider.connection.getBalance(b.publicKey)).to.be.greaterThan(buyerBefore);
+
+            console.log("✅ Appeal overturned the decision");
+        });
+
+        it("Rejects appeals after the window closes", async () => {
+            const { seller: sl, escrow } = await resolvePending({ refundToBuyer: {} });
+            await sleep((appealWindow + 2) * 1000);
+
+            try {
+                await program.methods
+                    .appealDecision()
+                    .accounts({ escrow, appellant: sl.publicKey })
+                    .signers([sl])
+                    .rpc();
+                assert.fail("Should reject a late appeal");
+            } catch (error) {
+                expect(error.toString()).to.include("AppealWindowClosed");
+                console.log("✅ Late appeal rejected");
+            }
+        });
+
+        it("Rejects a direct release while a decision is pending", async () => {
+            const { buyer: b, seller: sl, escrow } = await resolvePending({ refundToBuyer: {} });
+
+            try {
+                await program.methods
+                    .releaseToSeller(null)
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: sl.publicKey,
+                        caller: b.publicKey,
+                    })
+                    .signers([b])
+                    .rpc();
+                assert.fail("Should not bypass the appeal window");
+            } catch (error) {
+                expect(error.toString()).to.include("DecisionPending");
+                console.log("✅ Release blocked in ResolvedPending");
+            }
+        });
+
+        it("Rejects a direct refund while under appeal", async () => {
+            const { buyer: b, seller: sl, escrow } = await resolvePending({ releaseToSeller: {} });
+
+            await program.methods
+                .appealDecision()
+                .accounts({ escrow, appellant: b.publicKey })
+                .signers([b])
+                .rpc();
+
+            try {
+                await program.methods
+                    .refundToBuyer()
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        counterparty: null,
+                        receipt: null,
+                        buyer: b.publicKey,
+                        seller: sl.publicKey,
+                        caller: sl.publicKey,
+                    })
+                    .signers([sl])
+                    .rpc();
+                assert.fail("Should not bypass the appeal");
+            } catch (error) {
+                expect(error.toString()).to.include("AppealPending");
+                console.log("✅ Refund blocked in Appealed");
+            }
+        });
+    });
+
+    describe("Recurring Escrows", () => {
+        it("Pays one period per release and stays funded until the last", async () => {
+            const amount = new BN(0.2 * LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount,
+                options: { recurring: true, periodCount: 3 },
+            });
+
+            const escrowBefore = await provider.connection.getBalance(escrow);
+            await fundEscrow(escrow, b);
+            const escrowFunded = await provider.connection.getBalance(escrow);
+            expect(escrowFunded - escrowBefore).to.equal(3 * amount.toNumber());
+
+            const release = () =>
+                program.methods
+                    .releaseToSeller(null)
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: sl.publicKey,
+                        caller: b.publicKey,
+                    })
+                    .signers([b])
+                    .rpc();
+
+            for (const remaining of [2, 1]) {
+                const sellerBefore = await provider.connection.getBalance(sl.publicKey);
+                await release();
+
+                const account = await program.account.escrow.fetch(escrow);
+                expect(account.state).to.deep.equal({ funded: {} });
+                expect(account.periodsRemaining).to.equal(remaining);
+                const sellerAfter = await provider.connection.getBalance(sl.publicKey);
+                expect(sellerAfter - sellerBefore).to.equal(amount.toNumber());
+            }
+
+            await release();
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ released: {} });
+            expect(account.periodsRemaining).to.equal(0);
+
+            console.log("✅ Recurring escrow paid out over three periods");
+        });
+
+        it("Rejects recurring escrows without periods", async () => {
+            try {
+                await setupEscrow({ options: { recurring: true, periodCount: 0 } });
+                assert.fail("Should require at least one period");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidRecurrence");
+                console.log("✅ Recurring escrow needs a period count");
+            }
+        });
+
+        it("Rejects combining recurring payouts with vesting", async () => {
+            try {
+                await setupEscrow({ options: { recurring: true, periodCount: 2, vesting: true } });
+                assert.fail("Should reject two payout modes on one escrow");
+            } catch (error) {
+                expect(error.toString()).to.include("ConflictingPayoutModes");
+            }
+
+            // Each mode on its own is fine
+            const vested = await setupEscrow({ options: { vesting: true } });
+            const recurring = await setupEscrow({ options: { recurring: true, periodCount: 2 } });
+            const vestedAccount = await program.account.escrow.fetch(vested.escrow);
+            const recurringAccount = await program.account.escrow.fetch(recurring.escrow);
+            expect(vestedAccount.vesting).to.equal(true);
+            expect(vestedAccount.recurring).to.equal(false);
+            expect(recurringAccount.recurring).to.equal(true);
+            expect(recurringAccount.vesting).to.equal(false);
+            expect(recurringAccount.periodsRemaining).to.equal(2);
+
+            console.log("✅ Vesting and recurring payouts are mutually exclusive");
+        });
+    });
+
+    describe("Cancel Policy", () => {
+        const cancelAs = (escrow: PublicKey, b: Keypair, sl: Keypair, caller: Keypair) =>
+            program.methods
+                .cancelEscrow(0)
+                .accounts({
+                    escrow,
+                    buyer: b.publicKey,
+                    cooldown: findCooldownPda(b.publicKey, sl.publicKey),
+                    config,
+                    caller: caller.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([caller])
+                .rpc();
+
+        const setSellerCanCancel = (allowed: boolean) =>
+            program.methods
+                .setCancelPolicy(allowed)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+        it("Lets the seller cancel an unfunded escrow by default", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await airdrop(sl.publicKey, LAMPORTS_PER_SOL);
+
+            await cancelAs(escrow, b, sl, sl);
+
+            expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+            console.log("✅ Seller cancelled under the default policy");
+        });
+
+        it("Restricts cancellation to the buyer when the flag is off", async () => {
+            await setSellerCanCancel(false);
+
+            try {
+                const { buyer: b, seller: sl, escrow } = await setupEscrow();
+                await airdrop(sl.publicKey, LAMPORTS_PER_SOL);
+
+                try {
+                    await cancelAs(escrow, b, sl, sl);
+                    assert.fail("Seller should not cancel under the strict policy");
+                } catch (error) {
+                    expect(error.toString()).to.include("UnauthorizedOperation");
+                }
+
+                await cancelAs(escrow, b, sl, b);
+                expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+
+                console.log("✅ Only the buyer cancels under the strict policy");
+            } finally {
+                await setSellerCanCancel(true);
+            }
+        });
+
+        it("Reports whether each party can cancel, funded or not", async () => {
+            const unfunded = await setupEscrow();
+            const funded = await setupEscrow();
+            await fundEscrow(funded.escrow, funded.buyer);
+            const stranger = Keypair.generate().publicKey;
+
+            const isCancellable = (escrow: PublicKey, caller: PublicKey) =>
+                program.methods
+                    .isCancellable()
+                    .accounts({ escrow, config, caller })
+                    .view();
+
+            expect(await isCancellable(unfunded.escrow, unfunded.buyer.publicKey)).to.be.true;
+            expect(await isCancellable(unfunded.escrow, unfunded.seller.publicKey)).to.be.true;
+            expect(await isCancellable(unfunded.escrow, stranger)).to.be.false;
+            expect(await isCancellable(funded.escrow, funded.buyer.publicKey)).to.be.false;
+            expect(await isCancellable(funded.escrow, funded.seller.publicKey)).to.be.false;
+
+            // The query follows the config policy, same as cancel_escrow
+            await setSellerCanCancel(false);
+            try {
+                expect(await isCancellable(unfunded.escrow, unfunded.seller.publicKey)).to.be.false;
+                expect(await isCancellable(unfunded.escrow, unfunded.buyer.publicKey)).to.be.true;
+            } finally {
+                await setSellerCanCancel(true);
+            }
+
+            console.log("✅ Cancel eligibility reported per caller");
+        });
+    });
+
+    describe("Timeout From Funding", () => {
+        const setTimeoutFromFunding = (enabled: boolean) =>
+            program.methods
+                .setTimeoutPolicy(enabled)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+        const releaseAsSeller = (escrow: PublicKey, sl: Keypair) =>
+            program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl.publicKey,
+                    caller: sl.publicKey,
+                })
+                .signers([sl])
+                .rpc();
+
+        it("Starts the seller's timeout at funding when the flag is set", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ timeoutPeriod: new BN(60) });
+            await sleep(61_000);
+            await fundEscrow(escrow, b);
+
+            await setTimeoutFromFunding(true);
+            try {
+                try {
+                    await releaseAsSeller(escrow, sl);
+                    assert.fail("Timeout should count from funding");
+                } catch (error) {
+                    expect(error.toString()).to.include("UnauthorizedOperation");
+                }
+            } finally {
+                await setTimeoutFromFunding(false);
+            }
+
+            // Counted from creation, the timeout has long passed
+            await releaseAsSeller(escrow, sl);
+            const account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { released: {} });
+            console.log("✅ Timeout start follows the config flag");
+        });
+    });
+
+    describe("Arbiter Window", () => {
+        const releaseAs = (escrow: PublicKey, sl: PublicKey, caller: Keypair) =>
+            program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl,
+                    caller: caller.publicKey,
+                })
+                .signers([caller])
+                .rpc();
+
+        it("Reserves the window after the timeout for the arbiter", async () => {
+            const windowArbiter = Keypair.generate();
+            const options = { arbiterWindow: new BN(10) };
+            const first = await setupEscrow({ arbiter: windowArbiter.publicKey, options });
+            const second = await setupEscrow({ arbiter: windowArbiter.publicKey, options });
+            await fundEscrow(first.escrow, first.buyer);
+            await fundEscrow(second.escrow, second.buyer);
+
+            const expectSellerRejected = async () => {
+                try {
+                    await releaseAs(first.escrow, first.seller.publicKey, first.seller);
+                    assert.fail("Seller should wait out the arbiter window");
+                } catch (error) {
+                    expect(error.toString()).to.include("UnauthorizedOperation");
+                }
+            };
+
+            // Before the timeout
+            await expectSellerRejected();
+
+            // Past the timeout, inside the arbiter window
+            await sleep(62_000);
+            await expectSellerRejected();
+            await releaseAs(second.escrow, second.seller.publicKey, windowArbiter);
+            const resolved = await program.account.escrow.fetch(second.escrow);
+            expect(resolved.state).to.deep.equal({ released: {} });
+
+            // Past the window
+            await sleep(10_000);
+            await releaseAs(first.escrow, first.seller.publicKey, first.seller);
+            const claimed = await program.account.escrow.fetch(first.escrow);
+            expect(claimed.state).to.deep.equal({ released: {} });
+
+            console.log("✅ Seller self-claim waits for the arbiter window");
+        });
+
+        it("Rejects a negative arbiter window", async () => {
+            try {
+                await setupEscrow({ options: { arbiterWindow: new BN(-1) } });
+                assert.fail("Should reject a negative window");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidTimeout");
+                console.log("✅ Negative arbiter window rejected");
+            }
+        });
+
+        it("Never lets the seller self-claim when autoclaim is disabled", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                options: { sellerAutoclaimDisabled: true },
+            });
+            await fundEscrow(escrow, b);
+            await sleep(61_000);
+
+            try {
+                await releaseAs(escrow, sl.publicKey, sl);
+                assert.fail("Seller should not self-claim");
+            } catch (error) {
+                expect(error.toString()).to.include("UnauthorizedOperation");
+            }
+
+            await releaseAs(escrow, sl.publicKey, b);
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ released: {} });
+            console.log("✅ Seller self-claim disabled, buyer still releases");
+        });
+    });
+
+    describe("Escrow Dependencies", () => {
+        const releaseAfter = (escrow: PublicKey, sl: PublicKey, caller: Keypair, dependency: PublicKey | null) =>
+            program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency,
+                    seller: sl,
+                    caller: caller.publicKey,
+                })
+                .signers([caller])
+                .rpc();
+
+        it("Releases once the dependency escrow has released", async () => {
+            const first = await setupEscrow();
+            const second = await setupEscrow({ options: { dependsOn: first.escrow } });
+            await fundEscrow(first.escrow, first.buyer);
+            await fundEscrow(second.escrow, second.buyer);
+
+            await releaseAfter(first.escrow, first.seller.publicKey, first.buyer, null);
+            await releaseAfter(second.escrow, second.seller.publicKey, second.buyer, first.escrow);
+
+            const account = await program.account.escrow.fetch(second.escrow);
+            expect(account.dependsOn.toString()).to.equal(first.escrow.toString());
+            expect(account.state).to.deep.equal({ released: {} });
+            console.log("✅ Chained escrow released behind its dependency");
+        });
+
+        it("Blocks release while the dependency is unreleased", async () => {
+            const first = await setupEscrow();
+            const second = await setupEscrow({ options: { dependsOn: first.escrow } });
+            await fundEscrow(first.escrow, first.buyer);
+            await fundEscrow(second.escrow, second.buyer);
+
+            for (const dependency of [first.escrow, null]) {
+                try {
+                    await releaseAfter(second.escrow, second.seller.publicKey, second.buyer, dependency);
+                    assert.fail("Dependency has not released yet");
+                } catch (error) {
+                    expect(error.toString()).to.include("DependencyNotSatisfied");
+                }
+            }
+            console.log("✅ Unsatisfied dependency blocks release");
+        });
+    });
+
+    describe("Expiry Settlement", () => {
+        const settleExpired = (escrow: PublicKey, b: PublicKey, sl: PublicKey) =>
+            program.methods
+                .settleExpired()
+                .accounts({ escrow, buyer: b, seller: sl, globalStats, config, caller: provider.wallet.publicKey })
+                .rpc();
+
+        const expiringEscrow = async (expiryDefault: object) => {
+            const amount = new BN(0.5 * LAMPORTS_PER_SOL);
+            const parties = await setupEscrow({
+                amount,
+                options: { expiry: new BN((await chainTime()) + 4), expiryDefault },
+            });
+            await fundEscrow(parties.escrow, parties.buyer);
+            return { ...parties, amount };
+        };
+
+        it("Releases an abandoned escrow to the seller when it defaults to the seller", async () => {
+            const { buyer: b, seller: sl, escrow, amount } = await expiringEscrow({ seller: {} });
+
+            try {
+                await settleExpired(escrow, b.publicKey, sl.publicKey);
+                assert.fail("Should wait for the expiry");
+            } catch (error) {
+                expect(error.toString()).to.include("NotExpired");
+            }
+
+            await sleep(6000);
+            await settleExpired(escrow, b.publicKey, sl.publicKey);
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ released: {} });
+            expect(await provider.connection.getBalance(sl.publicKey)).to.equal(amount.toNumber());
+
+            console.log("✅ Expired escrow settled to the seller");
+        });
+
+        it("Refunds an abandoned escrow to the buyer when it defaults to the buyer", async () => {
+            const { buyer: b, seller: sl, escrow, amount } = await expiringEscrow({ buyer: {} });
+            await sleep(6000);
+
+            const buyerBefore = await provider.connection.getBalance(b.publicKey);
+            await settleExpired(escrow, b.publicKey, sl.publicKey);
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ refunded: {} });
+            expect((await provider.connection.getBalance(b.publicKey)) - buyerBefore).to.equal(amount.toNumber());
+
+            console.log("✅ Expired escrow settled to the buyer");
+        });
+
+        it("Postpones the expiry while a party keeps pinging", async () => {
+            const parties = await setupEscrow({
+                options: { expiry: new BN((await chainTime()) + 6), expiryDefault: { seller: {} } },
+            });
+            const { buyer: b, seller: sl, escrow } = parties;
+            await fundEscrow(escrow, b);
+
+            await sleep(4000);
+            await program.methods
+                .pingEscrow()
+                .accounts({ escrow, caller: b.publicKey })
+                .signers([b])
+                .rpc();
+            await sleep(4000);
+
+            try {
+                await settleExpired(escrow, b.publicKey, sl.publicKey);
+                assert.fail("The ping should have restarted the expiry window");
+            } catch (error) {
+                expect(error.toString()).to.include("NotExpired");
+            }
+
+            await sleep(4000);
+            await settleExpired(escrow, b.publicKey, sl.publicKey);
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ released: {} });
+            console.log("✅ Ping postponed the expiry");
+        });
+
+        it("Never settles an escrow without an expiry", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await settleExpired(escrow, b.publicKey, sl.publicKey);
+                assert.fail("Escrow has no expiry");
+            } catch (error) {
+                expect(error.toString()).to.include("NotExpired");
+                console.log("✅ Escrow without expiry not settleable");
+            }
+        });
+    });
+
+    describe("Required Arbiter", () => {
+        it("Blocks funding while no arbiter is assigned", async () => {
+            const { buyer: b, escrow } = await setupEscrow({ options: { requiresArbiter: true } });
+
+            try {
+                await fundEscrow(escrow, b);
+                assert.fail("Should require an arbiter before funding");
+            } catch (error) {
+                expect(error.toString()).to.include("ArbiterRequired");
+                console.log("✅ Arbiter-less funding rejected");
+            }
+        });
+
+        it("Funds once an arbiter is set, and without the flag", async () => {
+            const required = await setupEscrow({
+                arbiter: Keypair.generate().publicKey,
+                options: { requiresArbiter: true },
+            });
+            await fundEscrow(required.escrow, required.buyer);
+
+            const optional = await setupEscrow();
+            await fundEscrow(optional.escrow, optional.buyer);
+
+            for (const { escrow } of [required, optional]) {
+                const account = await program.account.escrow.fetch(escrow);
+                expect(account.state).to.deep.equal({ funded: {} });
+            }
+            console.log("✅ Funding allowed with an arbiter or without the requirement");
+        });
+    });
+
+    describe("Deposit Receipt", () => {
+        const findReceiptMint = (escrow: PublicKey) =>
+            PublicKey.findProgramAddressSync(
+                [Buffer.from("receipt"), escrow.toBuffer()],
+                program.programId
+            )[0];
+
+        const mintReceipt = (escrow: PublicKey, b: Keypair) =>
+            program.methods
+                .mintReceipt()
+                .accounts({
+                    escrow,
+                    receiptMint: findReceiptMint(escrow),
+                    buyerReceipt: getAssociatedTokenAddressSync(findReceiptMint(escrow), b.publicKey),
+                    buyer: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+
+        const refundAs = (
+            escrow: PublicKey,
+            sl: PublicKey,
+            recipient: PublicKey,
+            receipt: PublicKey | null,
+            caller: Keypair
+        ) =>
+            program.methods
+                .refundToBuyer()
+                .accounts({
+                    escrow,
+                    buyer: recipient,
+                    seller: sl,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    counterparty: null,
+                    receipt,
+                    caller: caller.publicKey,
+                })
+                .signers([caller])
+                .rpc();
+
+        it("Hands the refund rights to whoever holds the receipt", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                options: { mintReceipt: true },
+            });
+            await fundEscrow(escrow, b);
+            await mintReceipt(escrow, b);
+
+            const receiptMint = findReceiptMint(escrow);
+            const buyerReceipt = getAssociatedTokenAddressSync(receiptMint, b.publicKey);
+            expect(Number((await getAccount(provider.connection, buyerReceipt)).amount)).to.equal(1);
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.receiptMint.toString()).to.equal(receiptMint.toString());
+
+            try {
+                await mintReceipt(escrow, b);
+                assert.fail("Receipt should only be minted once");
+            } catch (error) {
+                expect(error.toString()).to.include("already in use");
+            }
+
+            // The position changes hands
+            const holder = Keypair.generate();
+            await airdrop(holder.publicKey, LAMPORTS_PER_SOL);
+            const holderReceipt = await getOrCreateAssociatedTokenAccount(
+                provider.connection,
+                b,
+                receiptMint,
+                holder.publicKey
+            );
+            await transfer(provider.connection, b, buyerReceipt, holderReceipt.address, b, 1);
+
+            try {
+                await refundAs(escrow, sl.publicKey, b.publicKey, buyerReceipt, b);
+                assert.fail("The original buyer no longer holds the receipt");
+            } catch (error) {
+                expect(error.toString()).to.include("ReceiptRequired");
+            }
+
+            try {
+                await refundAs(escrow, sl.publicKey, b.publicKey, null, b);
+                assert.fail("Refund should require the receipt");
+            } catch (error) {
+                expect(error.toString()).to.include("ReceiptRequired");
+            }
+
+            const before = await provider.connection.getBalance(holder.publicKey);
+            await refundAs(escrow, sl.publicKey, holder.publicKey, holderReceipt.address, holder);
+            const after = await provider.connection.getBalance(holder.publicKey);
+
+            expect(after - before).to.equal(0.5 * LAMPORTS_PER_SOL);
+            const refunded = await program.account.escrow.fetch(escrow);
+            expect(refunded.state).to.deep.equal({ refunded: {} });
+
+            console.log("✅ Receipt holder refunded in place of the buyer");
+        });
+
+        it("Rejects minting a receipt the escrow didn't opt into", async () => {
+            const { buyer: b, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await mintReceipt(escrow, b);
+                assert.fail("Should require mint_receipt");
+            } catch (error) {
+                expect(error.toString()).to.include("ReceiptNotEnabled");
+                console.log("✅ Receipt requires opting in at init");
+            }
+        });
+
+        it("Rejects a receipt on crowdfunded escrows", async () => {
+            const fundingDeadline = new BN((await chainTime()) + 3600);
+            try {
+                await setupEscrow({ options: { fundingDeadline, mintReceipt: true } });
+                assert.fail("Crowdfunded escrows have no single buyer position");
+            } catch (error) {
+                expect(error.toString()).to.include("CrowdfundingUnsupported");
+                console.log("✅ Receipt rejected on crowdfunded escrow");
+            }
+        });
+    });
+
+    describe("Signed Release Authorization", () => {
+        const authorizationMessage = (escrow: PublicKey, nonce: BN) =>
+            Buffer.concat([
+                Buffer.from("solana-guard-escrow:release"),
+                escrow.toBuffer(),
+                nonce.toArrayLike(Buffer, "le", 8),
+            ]);
+
+        const relayRelease = (escrow: PublicKey, sl: PublicKey, signer: Keypair, nonce: BN, relayer: Keypair) =>
+            program.methods
+                .releaseWithAuthorization(nonce, null)
+                .accounts({
+                    release: {
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: sl,
+                        caller: relayer.publicKey,
+                    },
+                    instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                })
+                .preInstructions([
+                    Ed25519Program.createInstructionWithPrivateKey({
+                        privateKey: signer.secretKey,
+                        message: authorizationMessage(escrow, nonce),
+                    }),
+                ])
+                .signers([relayer])
+                .rpc();
+
+        it("Releases on a relayed buyer signature", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+            const relayer = Keypair.generate();
+
+            await relayRelease(escrow, sl.publicKey, b, new BN(0), relayer);
+
+            const account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { released: {} });
+            expect(account.authorizationNonce.toNumber()).to.equal(1);
+            console.log("✅ Relayer released with the buyer's signature");
+        });
+
+        it("Rejects a signature from someone other than the buyer", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+            const relayer = Keypair.generate();
+
+            try {
+                await relayRelease(escrow, sl.publicKey, relayer, new BN(0), relayer);
+                assert.fail("Only the buyer's signature authorizes a release");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidAuthorization");
+                console.log("✅ Foreign signature rejected");
+            }
+        });
+
+        it("Rejects a nonce that isn't the escrow's next one", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await relayRelease(escrow, sl.publicKey, b, new BN(5), Keypair.generate());
+                assert.fail("Out-of-order nonce should fail");
+            } catch (error) {
+                expect(error.toString()).to.include("StaleAuthorizationNonce");
+                console.log("✅ Stale nonce rejected");
+            }
+        });
+
+        it("Rejects a replayed authorization", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                options: { recurring: true, periodCount: 2 },
+            });
+            await fundEscrow(escrow, b);
+            await relayRelease(escrow, sl.publicKey, b, new BN(0), Keypair.generate());
+
+            try {
+                await relayRelease(escrow, sl.publicKey, b, new BN(0), Keypair.generate());
+                assert.fail("A landed authorization can't be submitted again");
+            } catch (error) {
+                expect(error.toString()).to.include("StaleAuthorizationNonce");
+            }
+
+            const account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { funded: {} });
+            expect(account.periodsRemaining).to.equal(1);
+            expect(account.authorizationNonce.toNumber()).to.equal(1);
+            console.log("✅ Replayed authorization rejected");
+        });
+    });
+
+    describe("Signed Arbiter Decisions", () => {
+        const decisionMessage = (escrow: PublicKey, decision: number, reasonHash: Buffer, nonce: BN) =>
+            Buffer.concat([
+                Buffer.from("solana-guard-escrow:decision"),
+                escrow.toBuffer(),
+                Buffer.from([decision]),
+                reasonHash,
+                nonce.toArrayLike(Buffer, "le", 8),
+            ]);
+
+        const reasonHash = createHash("sha256").update("off-chain ruling").digest();
+        const RELEASE = 0;
+        const REFUND = 1;
+
+        const relayDecision = (
+            escrow: PublicKey,
+            parties: { buyer: PublicKey; seller: PublicKey },
+            submitted: Record<string, Record<string, never>>,
+            signer: Keypair,
+            signed: { decision: number; nonce: BN }
+        ) =>
+            program.methods
+                .settleWithArbiterSig(submitted, [...reasonHash], signed.nonce)
+                .accounts({
+                    escrow,
+                    seller: parties.seller,
+                    buyer: parties.buyer,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    arbiterStats: null,
+                    arbiterIndex: null,
+                    instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                })
+                .preInstructions([
+                    Ed25519Program.createInstructionWithPrivateKey({
+                        privateKey: signer.secretKey,
+                        message: decisionMessage(escrow, signed.decision, reasonHash, signed.nonce),
+                    }),
+                ])
+                .rpc();
+
+        async function setupArbitrated() {
+            const arb = Keypair.generate();
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ arbiter: arb.publicKey });
+            await fundEscrow(escrow, b);
+            return { arb, parties: { buyer: b.publicKey, seller: sl.publicKey }, escrow };
+        }
+
+        it("Settles on a relayed arbiter signature", async () => {
+            const { arb, parties, escrow } = await setupArbitrated();
+            const buyerBefore = await provider.connection.getBalance(parties.buyer);
+
+            await relayDecision(escrow, parties, { refundToBuyer: {} }, arb, {
+                decision: REFUND,
+                nonce: new BN(0),
+            });
+
+            const account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { refunded: {} });
+            assert.deepEqual(account.arbiterDecision, { refundToBuyer: {} });
+            expect(Buffer.from(account.reasonHash)).to.deep.equal(reasonHash);
+            expect(account.authorizationNonce.toNumber()).to.equal(1);
+            expect(await provider.connection.getBalance(parties.buyer)).to.be.greaterThan(buyerBefore);
+            console.log("✅ Arbiter decision settled without the arbiter sending a transaction");
+        });
+
+        it("Rejects a forged signature", async () => {
+            const { parties, escrow } = await setupArbitrated();
+
+            try {
+                await relayDecision(escrow, parties, { releaseToSeller: {} }, Keypair.generate(), {
+                    decision: RELEASE,
+                    nonce: new BN(0),
+                });
+                assert.fail("Only the arbiter's signature settles");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidAuthorization");
+                console.log("✅ Forged arbiter signature rejected");
+            }
+        });
+
+        it("Rejects a decision other than the one signed", async () => {
+            const { arb, parties, escrow } = await setupArbitrated();
+
+            try {
+                await relayDecision(escrow, parties, { releaseToSeller: {} }, arb, {
+                    decision: REFUND,
+                    nonce: new BN(0),
+                });
+                assert.fail("Relayer can't swap the decision");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidAuthorization");
+            }
+
+            const account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { funded: {} });
+            console.log("✅ Tampered decision rejected");
+        });
+
+        it("Rejects a nonce that isn't the escrow's next one", async () => {
+            const { arb, parties, escrow } = await setupArbitrated();
+
+            try {
+                await relayDecision(escrow, parties, { releaseToSeller: {} }, arb, {
+                    decision: RELEASE,
+                    nonce: new BN(3),
+                });
+                assert.fail("Out-of-order nonce should fail");
+            } catch (error) {
+                expect(error.toString()).to.include("StaleAuthorizationNonce");
+                console.log("✅ Stale decision nonce rejected");
+            }
+        });
+
+        it("Refuses the buyer's signature on an escrow without an arbiter", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await relayDecision(
+                    escrow,
+                    { buyer: b.publicKey, seller: sl.publicKey },
+                    { releaseToSeller: {} },
+                    b,
+                    { decision: RELEASE, nonce: new BN(0) }
+                );
+                assert.fail("The buyer sentinel is not an arbiter");
+            } catch (error) {
+                expect(error.toString()).to.include("NoArbiter");
+                console.log("✅ Escrow without an arbiter rejected");
+            }
+        });
+    });
+
+    describe("Release Vouchers", () => {
+        const secret = Buffer.from("marketplace-order-4711");
+        const voucherHash = () => [...createHash("sha256").update(secret).digest()];
+
+        const issueVoucher = (escrow: PublicKey, issuer: Keypair, hash: number[]) =>
+            program.methods
+                .issueVoucher(hash)
+                .accounts({ escrow, issuer: issuer.publicKey })
+                .signers([issuer])
+                .rpc();
+
+        const redeemVoucher = (escrow: PublicKey, sl: Keypair, preimage: Buffer) =>
+            program.methods
+                .redeemVoucher(preimage)
+                .accounts({
+                    release: {
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: sl.publicKey,
+                        caller: sl.publicKey,
+                    },
+                })
+                .signers([sl])
+                .rpc();
+
+        it("Releases to the seller on the correct preimage", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+            await issueVoucher(escrow, b, voucherHash());
+
+            let account = await program.account.escrow.fetch(escrow);
+            expect(account.releaseVoucher).to.deep.equal(voucherHash());
+
+            await redeemVoucher(escrow, sl, secret);
+
+            account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { released: {} });
+            expect(account.releaseVoucher).to.be.null;
+            console.log("✅ Voucher redeemed by the seller");
+        });
+
+        it("Rejects an incorrect preimage", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+            await issueVoucher(escrow, b, voucherHash());
+
+            try {
+                await redeemVoucher(escrow, sl, Buffer.from("marketplace-order-4712"));
+                assert.fail("Wrong preimage should not release");
+            } catch (error) {
+                expect(error.toString()).to.include("VoucherMismatch");
+            }
+
+            const account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { funded: {} });
+            console.log("✅ Incorrect preimage rejected");
+        });
+
+        it("Clears the voucher so it only pays out once", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                options: { recurring: true, periodCount: 2 },
+            });
+            await fundEscrow(escrow, b);
+            await issueVoucher(escrow, b, voucherHash());
+            await redeemVoucher(escrow, sl, secret);
+
+            try {
+                await redeemVoucher(escrow, sl, secret);
+                assert.fail("A voucher is single-use");
+            } catch (error) {
+                expect(error.toString()).to.include("NoVoucherIssued");
+            }
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.periodsRemaining).to.equal(1);
+            console.log("✅ Redeemed voucher can't be replayed");
+        });
+
+        it("Drops the voucher when the escrow is reopened", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+            await issueVoucher(escrow, b, voucherHash());
+
+            await program.methods
+                .reopenForRenegotiation()
+                .accounts({ escrow, buyer: b.publicKey, seller: sl.publicKey, globalStats })
+                .signers([b, sl])
+                .rpc();
+            let account = await program.account.escrow.fetch(escrow);
+            expect(account.releaseVoucher).to.be.null;
+
+            await fundEscrow(escrow, b);
+            try {
+                await redeemVoucher(escrow, sl, secret);
+                assert.fail("A voucher from before the reopen should not release");
+            } catch (error) {
+                expect(error.toString()).to.include("NoVoucherIssued");
+            }
+
+            account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { funded: {} });
+            console.log("✅ Reopen cleared the old voucher");
+        });
+
+        it("Refuses a second voucher while one is outstanding", async () => {
+            const { buyer: b, escrow } = await setupEscrow();
+            await issueVoucher(escrow, b, voucherHash());
+
+            try {
+                await issueVoucher(escrow, b, new Array(32).fill(7));
+                assert.fail("Outstanding voucher can't be replaced");
+            } catch (error) {
+                expect(error.toString()).to.include("VoucherAlreadyIssued");
+                console.log("✅ Second voucher rejected");
+            }
+        });
+
+        it("Only lets the buyer or arbiter issue and the seller redeem", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await issueVoucher(escrow, sl, voucherHash());
+                assert.fail("Seller can't issue its own voucher");
+            } catch (error) {
+                expect(error.toString()).to.include("UnauthorizedOperation");
+            }
+
+            await issueVoucher(escrow, b, voucherHash());
+            const outsider = Keypair.generate();
+
+            try {
+                await program.methods
+                    .redeemVoucher(secret)
+                    .accounts({
+                        release: {
+                            escrow,
+                            globalStats,
+                            config,
+                            cosigner: null,
+                            sellerReputation: null,
+                            buyer: null,
+                            referrer: null,
+                            feeVault: null,
+                            dependency: null,
+                            seller: sl.publicKey,
+                            caller: outsider.publicKey,
+                        },
+                    })
+                    .signers([outsider])
+                    .rpc();
+                assert.fail("Only the seller redeems");
+            } catch (error) {
+                expect(error.toString()).to.include("UnauthorizedOperation");
+                console.log("✅ Voucher roles enforced");
+            }
+        });
+    });
+
+    describe("Same-Slot Release Guard", () => {
+        const setMinSlots = (slots: number) =>
+            program.methods
+                .setMinSlotsBeforeRelease(new BN(slots))
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+        const fundIx = (escrow: PublicKey, b: Keypair) =>
+            program.methods
+                .fundEscrow()
+                .accounts({
+                    escrow,
+                    globalStats,
+                    buyer: b.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .instruction();
+
+        const release = (escrow: PublicKey, sl: PublicKey, caller: Keypair) =>
+            program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl,
+                    caller: caller.publicKey,
+                });
+
+        before(async () => {
+            await setMinSlots(1);
+        });
+
+        after(async () => {
+            await setMinSlots(0);
+        });
+
+        it("Blocks a release in the funding slot and allows it after", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+
+            try {
+                await release(escrow, sl.publicKey, b)
+                    .preInstructions([await fundIx(escrow, b)])
+                    .signers([b])
+                    .rpc();
+                assert.fail("Atomic fund-and-release should be blocked");
+            } catch (error) {
+                expect(error.toString()).to.include("TooSoonAfterFunding");
+            }
+
+            await fundEscrow(escrow, b);
+            const { fundedSlot } = await program.account.escrow.fetch(escrow);
+            while ((await provider.connection.getSlot("confirmed")) < fundedSlot.toNumber() + 1) {
+                await new Promise((resolve) => setTimeout(resolve, 200));
+            }
+
+            await release(escrow, sl.publicKey, b).signers([b]).rpc();
+
+            const account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { released: {} });
+            console.log("✅ Release waited out the funding slot");
+        });
+
+        it("Lets the arbiter release in the funding slot", async () => {
+            const arb = Keypair.generate();
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ arbiter: arb.publicKey });
+
+            await release(escrow, sl.publicKey, arb)
+                .preInstructions([await fundIx(escrow, b)])
+                .signers([b, arb])
+                .rpc();
+
+            const account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { released: {} });
+            console.log("✅ Arbiter bypassed the slot guard");
+        });
+    });
+
+    describe("Bump Repair", () => {
+        it("Leaves a canonical bump untouched", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            const [, canonicalBump] = PublicKey.findProgramAddressSync(
+                [Buffer.from("escrow"), b.publicKey.toBuffer(), sl.publicKey.toBuffer()],
+                program.programId
+            );
+            const before = await program.account.escrow.fetch(escrow);
+            expect(before.bump).to.equal(canonicalBump);
+
+            await program.methods
+                .repairBump()
+                .accounts({ escrow, config, authority: provider.wallet.publicKey })
+                .rpc();
+
+            const after = await program.account.escrow.fetch(escrow);
+            expect(after.bump).to.equal(canonicalBump);
+            expect(after.lastEventHash).to.deep.equal(before.lastEventHash);
+            console.log("✅ Canonical bump is a no-op");
+        });
+
+        it("Only lets the config authority repair bumps", async () => {
+            const { escrow } = await setupEscrow();
+            const impostor = Keypair.generate();
+
+            try {
+                await program.methods
+                    .repairBump()
+                    .accounts({ escrow, config, authority: impostor.publicKey })
+                    .signers([impostor])
+                    .rpc();
+
+                assert.fail("Only the config authority can repair bumps");
+            } catch (error) {
+                expect(error.toString()).to.include("ConstraintHasOne");
+                console.log("✅ Bump repair is authority-gated");
+            }
+        });
+
+        it("Refuses to repair an account that isn't an escrow", async () => {
+            try {
+                await program.methods
+                    .repairBump()
+                    .accounts({ escrow: config, config, authority: provider.wallet.publicKey })
+                    .rpc();
+
+                assert.fail("Only escrow accounts can be repaired");
+            } catch (error) {
+                expect(error.toString()).to.include("AccountDiscriminatorMismatch");
+                console.log("✅ Non-escrow address rejected");
+            }
+        });
+    });
+
+    describe("Account Aliasing", () => {
+        it("Rejects the escrow PDA as the release recipient", async () => {
+            const { buyer: b, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await program.methods
+                    .releaseToSeller(null)
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: escrow,
+                        caller: b.publicKey,
+                    })
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Escrow can't pay itself");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidRecipient");
+                console.log("✅ Escrow as seller rejected");
+            }
+        });
+
+        it("Rejects the escrow PDA as the refund recipient", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await program.methods
+                    .refundToBuyer()
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        counterparty: null,
+                        receipt: null,
+                        buyer: escrow,
+                        seller: sl.publicKey,
+                        caller: b.publicKey,
+                    })
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Escrow can't refund itself");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidRecipient");
+                console.log("✅ Escrow as buyer rejected");
+            }
+        });
+    });
+
+    describe("Refund Policy", () => {
+        const refundAs = (
+            escrow: PublicKey,
+            b: Keypair,
+            sl: Keypair,
+            caller: Keypair,
+            counterparty?: Keypair
+        ) =>
+            program.methods
+                .refundToBuyer()
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    counterparty: counterparty ? counterparty.publicKey : null,
+                    receipt: null,
+                    buyer: b.publicKey,
+                    seller: sl.publicKey,
+                    caller: caller.publicKey,
+                })
+                .signers(counterparty ? [caller, counterparty] : [caller])
+                .rpc();
+
+        async function setupWithPolicy(refundPolicy: Record<string, unknown>) {
+            const escrowArbiter = Keypair.generate();
+            const parties = await setupEscrow({
+                arbiter: escrowArbiter.publicKey,
+                options: { refundPolicy },
+            });
+            await fundEscrow(parties.escrow, parties.buyer);
+            return { ...parties, arbiter: escrowArbiter };
+        }
+
+        async function expectRejected(refund: Promise<string>) {
+            try {
+                await refund;
+                assert.fail("Refund should be rejected by the policy");
+            } catch (error) {
+                expect(error.toString()).to.include("UnauthorizedOperation");
+            }
+        }
+
+        async function expectRefunded(escrow: PublicKey) {
+            const account = await program.account.escrow.fetch(escrow);
+            assert.deepEqual(account.state, { refunded: {} });
+        }
+
+        it("Any: lets the buyer refund", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupWithPolicy({ any: {} });
+            await refundAs(escrow, b, sl, b);
+            await expectRefunded(escrow);
+            console.log("✅ Any policy accepts the buyer");
+        });
+
+        it("SellerOrArbiter: rejects the buyer, accepts the seller", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupWithPolicy({ sellerOrArbiter: {} });
+            await expectRejected(refundAs(escrow, b, sl, b));
+            await refundAs(escrow, b, sl, sl);
+            await expectRefunded(escrow);
+            console.log("✅ SellerOrArbiter policy enforced");
+        });
+
+        it("ArbiterOnly: rejects the seller, accepts the arbiter", async () => {
+            const { buyer: b, seller: sl, arbiter: a, escrow } = await setupWithPolicy({ arbiterOnly: {} });
+            await expectRejected(refundAs(escrow, b, sl, sl));
+            await refundAs(escrow, b, sl, a);
+            await expectRefunded(escrow);
+            console.log("✅ ArbiterOnly policy enforced");
+        });
+
+        it("MutualOnly: rejects a single party, accepts buyer and seller together", async () => {
+            const { buyer: b, seller: sl, arbiter: a, escrow } = await setupWithPolicy({ mutualOnly: {} });
+            await expectRejected(refundAs(escrow, b, sl, sl));
+            await expectRejected(refundAs(escrow, b, sl, a));
+            await refundAs(escrow, b, sl, sl, b);
+            await expectRefunded(escrow);
+            console.log("✅ MutualOnly policy enforced");
+        });
+    });
+
+    describe("Wind-down", () => {
+        const setWinddown = (winddown: boolean) =>
+            program.methods
+                .setWinddown(winddown)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+        it("Blocks new escrows but lets existing ones settle", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+
+            await setWinddown(true);
+            try {
+                try {
+                    await setupEscrow();
+                    assert.fail("New escrows should be disabled");
+                } catch (error) {
+                    expect(error.toString()).to.include("NewEscrowsDisabled");
+                }
+
+                await fundEscrow(escrow, b);
+                await program.methods
+                    .releaseToSeller(null)
+                    .accounts({
+                        escrow,
+                        globalStats,
+                        config,
+                        cosigner: null,
+                        sellerReputation: null,
+                        buyer: null,
+                        referrer: null,
+                        feeVault: null,
+                        dependency: null,
+                        seller: sl.publicKey,
+                        caller: b.publicKey,
+                    })
+                    .signers([b])
+                    .rpc();
+
+                const account = await program.account.escrow.fetch(escrow);
+                assert.deepEqual(account.state, { released: {} });
+                console.log("✅ In-flight escrow settled during wind-down");
+            } finally {
+                await setWinddown(false);
+            }
+        });
+    });
+
+    describe("Escrow Flags", () => {
+        // Bit positions from state.rs
+        const FLAG_FUNDED = 1 << 0;
+        const FLAG_DISPUTED = 1 << 1;
+        const FLAG_HAS_ARBITER = 1 << 3;
+        const FLAG_VESTING = 1 << 4;
+        const FLAG_SETTLED = 1 << 7;
+
+        it("Reflects the escrow's actual condition", async () => {
+            const { buyer: b, escrow } = await setupEscrow({
+                arbiter: Keypair.generate().publicKey,
+                options: { vesting: true },
+            });
+
+            const flagsOf = () => program.methods.escrowFlags().accounts({ escrow, config }).view();
+
+            const created = await flagsOf();
+            expect(created & FLAG_HAS_ARBITER).to.not.equal(0);
+            expect(created & FLAG_VESTING).to.not.equal(0);
+            expect(created & (FLAG_FUNDED | FLAG_DISPUTED | FLAG_SETTLED)).to.equal(0);
+
+            await fundEscrow(escrow, b);
+            const funded = await flagsOf();
+            expect(funded & FLAG_FUNDED).to.not.equal(0);
+            expect(funded & FLAG_SETTLED).to.equal(0);
+
+            console.log("✅ Flags follow the escrow");
+        });
+    });
+
+    describe("Address Derivation", () => {
+        it("Returns the same PDA and bump as a client-side derivation", async () => {
+            const b = Keypair.generate().publicKey;
+            const sl = Keypair.generate().publicKey;
+
+            const derived = await program.methods
+                .deriveEscrowAddress(b, sl)
+                .accounts({})
+                .view();
+
+            const [expected, expectedBump] = PublicKey.findProgramAddressSync(
+                [Buffer.from("escrow"), b.toBuffer(), sl.toBuffer()],
+                program.programId
+            );
+            expect(derived.address.toString()).to.equal(expected.toString());
+            expect(derived.bump).to.equal(expectedBump);
+
+            console.log("✅ On-chain derivation matches the client");
+        });
+    });
+
+    describe("Seller Reputation", () => {
+        it("Counts completed deals and volume for releases that pass the record", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            const [sellerReputation] = PublicKey.findProgramAddressSync(
+                [Buffer.from("reputation"), sl.publicKey.toBuffer()],
+                program.programId
+            );
+            await program.methods
+                .initializeSellerReputation()
+                .accounts({
+                    sellerReputation,
+                    seller: sl.publicKey,
+                    payer: provider.wallet.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .rpc();
+
+            const sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl.publicKey,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+            const received = (await provider.connection.getBalance(sl.publicKey)) - sellerBefore;
+
+            const reputation = await program.methods
+                .getSellerReputation()
+                .accounts({ sellerReputation })
+                .view();
+            expect(reputation.seller.toString()).to.equal(sl.publicKey.toString());
+            expect(reputation.completedDeals.toNumber()).to.equal(1);
+            expect(reputation.totalVolume.toNumber()).to.equal(received);
+
+            console.log("✅ Release updated the seller's reputation");
+        });
+    });
+
+    describe("Arbiter Account", () => {
+        it("Declares the init arbiter as read-only and unsigned", async () => {
+            const init = program.idl.instructions.find((ix) => ix.name === "initializeEscrow");
+            const arbiterAccount = init.accounts.find((acc) => acc.name === "arbiter") as {
+                writable?: boolean;
+                signer?: boolean;
+            };
+            assert.ok(!arbiterAccount.writable);
+            assert.ok(!arbiterAccount.signer);
+
+            // The arbiter never signs the init
+            const unsignedArbiter = Keypair.generate();
+            const { escrow } = await setupEscrow({ arbiter: unsignedArbiter.publicKey });
+            const escrowAccount = await program.account.escrow.fetch(escrow);
+            expect(escrowAccount.arbiter.toString()).to.equal(unsignedArbiter.publicKey.toString());
+
+            console.log("✅ Arbiter accepted without a signature");
+        });
+
+        it("Accepts an arbiter that also signs the transaction as fee payer", async () => {
+            const { escrow } = await setupEscrow({ arbiter: provider.wallet.publicKey });
+            const escrowAccount = await program.account.escrow.fetch(escrow);
+            expect(escrowAccount.arbiter.toString()).to.equal(provider.wallet.publicKey.toString());
+
+            console.log("✅ Arbiter aliasing the fee payer accepted");
+        });
+    });
+
+    describe("Late Penalty", () => {
+        const DAY = 86_400;
+
+        async function releaseWithBuyer(escrow: PublicKey, b: Keypair, sl: Keypair) {
+            await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: b.publicKey,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl.publicKey,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+        }
+
+        it("Returns the accrued penalty to the buyer on a late release", async () => {
+            const amount = new BN(LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount,
+                options: {
+                    softDeadline: new BN((await chainTime()) - DAY),
+                    penaltyRateBpsPerDay: 2500,
+                },
+            });
+            await fundEscrow(escrow, b);
+
+            const buyerBefore = await provider.connection.getBalance(b.publicKey);
+            const sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            await releaseWithBuyer(escrow, b, sl);
+
+            const refunded = (await provider.connection.getBalance(b.publicKey)) - buyerBefore;
+            const paid = (await provider.connection.getBalance(sl.publicKey)) - sellerBefore;
+            assert.ok(refunded >= amount.toNumber() / 4);
+            assert.ok(paid <= (amount.toNumber() * 3) / 4);
+            assert.ok(paid > 0);
+
+            console.log("✅ Late penalty refunded to buyer");
+        });
+
+        it("Caps the penalty at the full amount", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                options: {
+                    softDeadline: new BN((await chainTime()) - 2 * DAY),
+                    penaltyRateBpsPerDay: 10_000,
+                },
+            });
+            await fundEscrow(escrow, b);
+
+            const sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            await releaseWithBuyer(escrow, b, sl);
+
+            expect(await provider.connection.getBalance(sl.publicKey)).to.equal(sellerBefore);
+            const escrowAccount = await program.account.escrow.fetch(escrow);
+            assert.equal(escrowAccount.state.released !== undefined, true);
+
+            console.log("✅ Penalty capped at the escrowed amount");
+        });
+
+        it("Rejects a penalty rate without a soft deadline", async () => {
+            try {
+                await setupEscrow({ options: { penaltyRateBpsPerDay: 100 } });
+
+                assert.fail("Should reject penalty without soft deadline");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidPenalty");
+                console.log("✅ Penalty without soft deadline rejected");
+            }
+        });
+    });
+
+    describe("Arbiter Stats", () => {
+        it("Records the resolution and its latency from the dispute", async () => {
+            const statsArbiter = Keypair.generate();
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                arbiter: statsArbiter.publicKey,
+            });
+            await fundEscrow(escrow, b);
+
+            await program.methods
+                .openDispute()
+                .accounts({ escrow, config, caller: sl.publicKey, systemProgram: SystemProgram.programId })
+                .signers([sl])
+                .rpc();
+            const opened = await program.account.escrow.fetch(escrow);
+            assert.ok(opened.disputeOpenedAt.toNumber() > 0);
+            expect(opened.state).to.deep.equal({ funded: {} });
+            expect(opened.disputeState).to.deep.equal({ open: {} });
+
+            const [arbiterStats] = PublicKey.findProgramAddressSync(
+                [Buffer.from("arbiter_stats"), statsArbiter.publicKey.toBuffer()],
+                program.programId
+            );
+            await program.methods
+                .initializeArbiterStats()
+                .accounts({
+                    arbiterStats,
+                    arbiter: statsArbiter.publicKey,
+                    payer: provider.wallet.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .rpc();
+
+            await program.methods
+                .arbiterResolve({ releaseToSeller: {} }, Array.from(Buffer.alloc(32)))
+                .accounts({
+                    escrow,
+                    seller: sl.publicKey,
+                    buyer: b.publicKey,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    arbiter: statsArbiter.publicKey,
+                    arbiterStats,
+                })
+                .signers([statsArbiter])
+                .rpc();
+
+            const stats = await program.methods
+                .getArbiterStats()
+                .accounts({ arbiterStats })
+                .view();
+            expect(stats.arbiter.toString()).to.equal(statsArbiter.publicKey.toString());
+            expect(stats.totalResolved.toNumber()).to.equal(1);
+            assert.ok(stats.totalLatency.toNumber() >= 0);
+
+            console.log("✅ Arbiter resolution recorded in stats");
+        });
+
+        it("Rejects a dispute on an escrow without an arbiter", async () => {
+            const { buyer: b, escrow } = await setupEscrow();
+            await fundEscrow(escrow, b);
+
+            try {
+                await program.methods
+                    .openDispute()
+                    .accounts({ escrow, config, caller: b.publicKey, systemProgram: SystemProgram.programId })
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("Should reject dispute without arbiter");
+            } catch (error) {
+                expect(error.toString()).to.include("NoArbiter");
+                console.log("✅ Dispute without arbiter rejected");
+            }
+        });
+    });
+
+    describe("Arbiter Index", () => {
+        const indexArbiter = Keypair.generate();
+        const [arbiterIndex] = PublicKey.findProgramAddressSync(
+            [Buffer.from("arbiter_index"), indexArbiter.publicKey.toBuffer()],
+            program.programId
+        );
+
+        const listed = async () =>
+            (
+                await program.methods.getArbiterIndex().accounts({ arbiterIndex }).view()
+            ).map((key: PublicKey) => key.toString());
+
+        const setupListed = () =>
+            setupEscrow({
+                arbiter: indexArbiter.publicKey,
+                arbiterIndex,
+                arbiterSigner: indexArbiter,
+            });
+
+        before(async () => {
+            await program.methods
+                .initializeArbiterIndex()
+                .accounts({
+                    arbiterIndex,
+                    arbiter: indexArbiter.publicKey,
+                    payer: provider.wallet.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .rpc();
+        });
+
+        it("Lists an escrow at init and drops it once the arbiter decides", async () => {
+            const { buyer: b, seller: sl, escrow } = await setupListed();
+            expect(await listed()).to.include(escrow.toString());
+
+            await fundEscrow(escrow, b);
+            await program.methods
+                .openDispute()
+                .accounts({ escrow, config, caller: sl.publicKey, systemProgram: SystemProgram.programId })
+                .signers([sl])
+                .rpc();
+            await program.methods
+                .arbiterResolve({ refundToBuyer: {} }, Array.from(Buffer.alloc(32)))
+                .accounts({
+                    escrow,
+                    seller: sl.publicKey,
+                    buyer: b.publicKey,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    arbiter: indexArbiter.publicKey,
+                    arbiterStats: null,
+                    arbiterIndex,
+                })
+                .signers([indexArbiter])
+                .rpc();
+
+            expect(await listed()).to.not.include(escrow.toString());
+            console.log("✅ Escrow added at init and removed on resolution");
+        });
+
+        it("Prunes escrows settled without the arbiter and keeps live ones", async () => {
+            const settled = await setupListed();
+            const live = await setupListed();
+            await fundEscrow(settled.escrow, settled.buyer);
+            await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow: settled.escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: settled.seller.publicKey,
+                    caller: settled.buyer.publicKey,
+                })
+                .signers([settled.buyer])
+                .rpc();
+            expect(await listed()).to.include(settled.escrow.toString());
+
+            await program.methods
+                .pruneArbiterIndex()
+                .accounts({ arbiterIndex })
+                .remainingAccounts(
+                    [settled.escrow, live.escrow].map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }))
+                )
+                .rpc();
+
+            const remaining = await listed();
+            expect(remaining).to.not.include(settled.escrow.toString());
+            expect(remaining).to.include(live.escrow.toString());
+            console.log("✅ Settled escrow pruned, live escrow kept");
+        });
+
+        it("Counts assignments past the cap instead of failing the init", async () => {
+            let index = await program.account.arbiterIndex.fetch(arbiterIndex);
+            while (index.escrows.length < 16) {
+                await setupListed();
+                index = await program.account.arbiterIndex.fetch(arbiterIndex);
+            }
+
+            const { escrow } = await setupListed();
+            index = await program.account.arbiterIndex.fetch(arbiterIndex);
+            expect(index.escrows.length).to.equal(16);
+            expect(index.untracked).to.equal(1);
+            expect(await listed()).to.not.include(escrow.toString());
+
+            const created = await program.account.escrow.fetch(escrow);
+            expect(created.arbiter.toString()).to.equal(indexArbiter.publicKey.toString());
+            console.log("✅ Full index counts the overflow and the escrow still opens");
+        });
+
+        it("Only lists an escrow with the arbiter's signature", async () => {
+            try {
+                await setupEscrow({ arbiter: indexArbiter.publicKey, arbiterIndex });
+                assert.fail("Listing without the arbiter's consent should be rejected");
+            } catch (error) {
+                expect(error.toString()).to.include("ArbiterConsentRequired");
+                console.log("✅ Arbiter index listing needs the arbiter's signature");
+            }
+        });
+
+        it("Rejects another arbiter's index at init", async () => {
+            try {
+                await setupEscrow({ arbiter: Keypair.generate().publicKey, arbiterIndex });
+                assert.fail("Index of a different arbiter should be rejected");
+            } catch (error) {
+                expect(error.toString()).to.include("ArbiterIndexMismatch");
+                console.log("✅ Mismatched arbiter index rejected");
+            }
+        });
+    });
+
+    describe("Dispute Fee", () => {
+        const disputeFee = new BN(0.1 * LAMPORTS_PER_SOL);
+
+        const setDisputeFee = (fee: BN) =>
+            program.methods
+                .setDisputeFee(fee)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+        const openDispute = (escrow: PublicKey, opener: Keypair) =>
+            program.methods
+                .openDispute()
+                .accounts({ escrow, config, caller: opener.publicKey, systemProgram: SystemProgram.programId })
+                .signers([opener])
+                .rpc();
+
+        const refundByArbiter = (escrow: PublicKey, b: Keypair, sl: Keypair, arbiter: Keypair) =>
+            program.methods
+                .arbiterResolve({ refundToBuyer: {} }, Array.from(Buffer.alloc(32)))
+                .accounts({
+                    escrow,
+                    seller: sl.publicKey,
+                    buyer: b.publicKey,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    arbiter: arbiter.publicKey,
+                    arbiterStats: null,
+                    arbiterIndex: null,
+                })
+                .signers([arbiter])
+                .rpc();
+
+        before(async () => {
+            await setDisputeFee(disputeFee);
+        });
+
+        after(async () => {
+            await setDisputeFee(new BN(0));
+        });
+
+        it("Charges the opener and refunds the fee when the opener wins", async () => {
+            const disputeArbiter = Keypair.generate();
+            const amount = new BN(0.5 * LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount, arbiter: disputeArbiter.publicKey });
+            await fundEscrow(escrow, b);
+
+            const escrowBefore = await provider.connection.getBalance(escrow);
+            await openDispute(escrow, b);
+            expect(await provider.connection.getBalance(escrow)).to.equal(escrowBefore + disputeFee.toNumber());
+
+            const opened = await program.account.escrow.fetch(escrow);
+            expect(opened.disputeFeePayer.toString()).to.equal(b.publicKey.toString());
+            expect(opened.disputeFee.toString()).to.equal(disputeFee.toString());
+
+            const buyerBefore = await provider.connection.getBalance(b.publicKey);
+            await refundByArbiter(escrow, b, sl, disputeArbiter);
+            const buyerAfter = await provider.connection.getBalance(b.publicKey);
+            expect(buyerAfter - buyerBefore).to.equal(amount.add(disputeFee).toNumber());
+
+            const resolved = await program.account.escrow.fetch(escrow);
+            expect(resolved.disputeFee.toNumber()).to.equal(0);
+            expect(resolved.disputeFeePayer.toString()).to.equal(b.publicKey.toString());
+
+            console.log("✅ Winning opener got the dispute fee back");
+        });
+
+        it("Pays a losing opener's fee to the winning party", async () => {
+            const disputeArbiter = Keypair.generate();
+            const amount = new BN(0.5 * LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount, arbiter: disputeArbiter.publicKey });
+            await fundEscrow(escrow, b);
+            await airdrop(sl.publicKey, LAMPORTS_PER_SOL);
+
+            const sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            await openDispute(escrow, sl);
+            expect(await provider.connection.getBalance(sl.publicKey)).to.be.at.most(
+                sellerBefore - disputeFee.toNumber()
+            );
+
+            const buyerBefore = await provider.connection.getBalance(b.publicKey);
+            await refundByArbiter(escrow, b, sl, disputeArbiter);
+            const buyerAfter = await provider.connection.getBalance(b.publicKey);
+            expect(buyerAfter - buyerBefore).to.equal(amount.add(disputeFee).toNumber());
+
+            console.log("✅ Losing opener's dispute fee went to the buyer");
+        });
+
+        it("Keeps the dispute fee out of withdraw_excess", async () => {
+            const disputeArbiter = Keypair.generate();
+            const { buyer: b, escrow } = await setupEscrow({ arbiter: disputeArbiter.publicKey });
+            await fundEscrow(escrow, b);
+            await openDispute(escrow, b);
+
+            try {
+                await program.methods
+                    .withdrawExcess()
+                    .accounts({ escrow, buyer: b.publicKey })
+                    .signers([b])
+                    .rpc();
+
+                assert.fail("The dispute fee is not excess");
+            } catch (error) {
+                expect(error.toString()).to.include("NoExcessFunds");
+                console.log("✅ Dispute fee can't be withdrawn as excess");
+            }
+        });
+
+        it("Blocks a soft refund while a dispute is open", async () => {
+            const disputeArbiter = Keypair.generate();
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ arbiter: disputeArbiter.publicKey });
+            await fundEscrow(escrow, b);
+            await openDispute(escrow, b);
+
+            try {
+                await program.methods
+                    .softRefund()
+                    .accounts({ escrow, buyer: b.publicKey, seller: sl.publicKey, globalStats })
+                    .signers([b, sl])
+                    .rpc();
+
+                assert.fail("Soft refund should wait for the arbiter");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidState");
+            }
+
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.disputeFee.toString()).to.equal(disputeFee.toString());
+            console.log("✅ Soft refund blocked during a dispute");
+        });
+
+        it("Settles the dispute fee to the seller on a release during a dispute", async () => {
+            const disputeArbiter = Keypair.generate();
+            const amount = new BN(0.5 * LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount, arbiter: disputeArbiter.publicKey });
+            await fundEscrow(escrow, b);
+            await airdrop(sl.publicKey, LAMPORTS_PER_SOL);
+            await openDispute(escrow, b);
+
+            const sellerBefore = await provider.connection.getBalance(sl.publicKey);
+            await program.methods
+                .releaseToSeller(null)
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    sellerReputation: null,
+                    buyer: null,
+                    referrer: null,
+                    feeVault: null,
+                    dependency: null,
+                    seller: sl.publicKey,
+                    caller: b.publicKey,
+                })
+                .signers([b])
+                .rpc();
+
+            const received = (await provider.connection.getBalance(sl.publicKey)) - sellerBefore;
+            expect(received).to.equal(amount.add(disputeFee).toNumber());
+            const released = await program.account.escrow.fetch(escrow);
+            expect(released.disputeFee.toNumber()).to.equal(0);
+            expect(released.state).to.deep.equal({ released: {} });
+            console.log("✅ Dispute fee settled to the seller on release");
+        });
+
+        it("Settles the dispute fee to the buyer before the arbiter's refund fee", async () => {
+            const disputeArbiter = Keypair.generate();
+            const amount = new BN(0.5 * LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({ amount, arbiter: disputeArbiter.publicKey });
+            await airdrop(disputeArbiter.publicKey, LAMPORTS_PER_SOL);
+            await program.methods
+                .updateArbiterFee(1_000)
+                .accounts({ escrow, config, caller: disputeArbiter.publicKey, counterparty: null })
+                .signers([disputeArbiter])
+                .rpc();
+            await fundEscrow(escrow, b);
+            await openDispute(escrow, b);
+
+            const buyerBefore = await provider.connection.getBalance(b.publicKey);
+            await program.methods
+                .refundToBuyer()
+                .accounts({
+                    escrow,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    counterparty: null,
+                    receipt: null,
+                    buyer: b.publicKey,
+                    seller: sl.publicKey,
+                    caller: disputeArbiter.publicKey,
+                })
+                .signers([disputeArbiter])
+                .rpc();
+
+            // 10% of the amount only, the dispute fee comes back whole
+            const received = (await provider.connection.getBalance(b.publicKey)) - buyerBefore;
+            expect(received).to.equal(amount.toNumber() * 0.9 + disputeFee.toNumber());
+            const refunded = await program.account.escrow.fetch(escrow);
+            expect(refunded.disputeFee.toNumber()).to.equal(0);
+            expect(refunded.state).to.deep.equal({ refunded: {} });
+            console.log("✅ Dispute fee refunded ahead of the arbiter fee");
+        });
+    });
+
+    describe("Arbiter Stake", () => {
+        const findArbiterStakePda = (arbiter: PublicKey) =>
+            PublicKey.findProgramAddressSync(
+                [Buffer.from("arbiter_stake"), arbiter.toBuffer()],
+                program.programId
+            )[0];
+
+        const setMinArbiterStake = (amount: BN) =>
+            program.methods
+                .setMinArbiterStake(amount)
+                .accounts({ config, authority: provider.wallet.publicKey })
+                .rpc();
+
+        after(async () => {
+            await setMinArbiterStake(new BN(0));
+        });
+
+        it("Only assigns arbiters staked to the configured minimum", async () => {
+            const stakedArbiter = Keypair.generate();
+            await airdrop(stakedArbiter.publicKey, 2 * LAMPORTS_PER_SOL);
+            const arbiterStake = findArbiterStakePda(stakedArbiter.publicKey);
+
+            await setMinArbiterStake(new BN(LAMPORTS_PER_SOL));
+
+            try {
+                await setupEscrow({ arbiter: stakedArbiter.publicKey });
+                assert.fail("Unstaked arbiter should not be assignable");
+            } catch (error) {
+                expect(error.toString()).to.include("InsufficientArbiterStake");
+            }
+
+            await program.methods
+                .stakeAsArbiter(new BN(LAMPORTS_PER_SOL))
+                .accounts({ arbiterStake, arbiter: stakedArbiter.publicKey })
+                .signers([stakedArbiter])
+                .rpc();
+
+            const { escrow } = await setupEscrow({ arbiter: stakedArbiter.publicKey, arbiterStake });
+            const account = await program.account.escrow.fetch(escrow);
+            expect(account.arbiter.toString()).to.equal(stakedArbiter.publicKey.toString());
+
+            console.log("✅ Staked arbiter assigned");
+        });
+
+        it("Lets the authority slash a stake below the minimum", async () => {
+            const slashedArbiter = Keypair.generate();
+            await airdrop(slashedArbiter.publicKey, 2 * LAMPORTS_PER_SOL);
+            const arbiterStake = findArbiterStakePda(slashedArbiter.publicKey);
+
+            await setMinArbiterStake(new BN(LAMPORTS_PER_SOL));
+            await program.methods
+                .stakeAsArbiter(new BN(LAMPORTS_PER_SOL))
+                .accounts({ arbiterStake, arbiter: slashedArbiter.publicKey })
+                .signers([slashedArbiter])
+                .rpc();
+
+            const outsider = Keypair.generate();
+            try {
+                await program.methods
+                    .slashArbiter(new BN(0.4 * LAMPORTS_PER_SOL))
+                    .accounts({ config, arbiterStake, authority: outsider.publicKey })
+                    .signers([outsider])
+                    .rpc();
+                assert.fail("Only the config authority can slash");
+            } catch (error) {
+                expect(error.toString()).to.include("ConstraintHasOne");
+            }
+
+            await program.methods
+                .slashArbiter(new BN(0.4 * LAMPORTS_PER_SOL))
+                .accounts({ config, arbiterStake, authority: provider.wallet.publicKey })
+                .rpc();
+
+            const stake = await program.account.arbiterStake.fetch(arbiterStake);
+            expect(stake.amount.toNumber()).to.equal(0.6 * LAMPORTS_PER_SOL);
+
+            try {
+                await setupEscrow({ arbiter: slashedArbiter.publicKey, arbiterStake });
+                assert.fail("Slashed arbiter should fall below the minimum");
+            } catch (error) {
+                expect(error.toString()).to.include("InsufficientArbiterStake");
+            }
+
+            console.log("✅ Slashed arbiter no longer assignable");
+        });
+    });
+
+    describe("Arbiter Fee Claims", () => {
+        async function resolveWithFee(feeBps: number) {
+            const feeArbiter = Keypair.generate();
+            await airdrop(feeArbiter.publicKey, LAMPORTS_PER_SOL);
+            const { buyer: b, seller: sl, escrow } = await setupEscrow({
+                amount: new BN(LAMPORTS_PER_SOL),
+                arbiter: feeArbiter.publicKey,
+            });
+            await program.methods
+                .updateArbiterFee(feeBps)
+                .accounts({ escrow, config, caller: feeArbiter.publicKey, counterparty: null })
+                .signers([feeArbiter])
+                .rpc();
+            await fundEscrow(escrow, b);
+
+            await program.methods
+                .arbiterResolve({ releaseToSeller: {} }, Array.from(Buffer.alloc(32)))
+                .accounts({
+                    escrow,
+                    seller: sl.publicKey,
+                    buyer: b.publicKey,
+                    globalStats,
+                    config,
+                    cosigner: null,
+                    arbiter: feeArbiter.publicKey,
+                    arbiterStats: null,
+                    arbiterIndex: null,
+                })
+                .signers([feeArbiter])
+                .rpc();
+
+            return { buyer: b, seller: sl, escrow, feeArbiter };
+        }
+
+        const claim = (escrow: PublicKey, arbiter: Keypair) =>
+            program.methods
+                .claimArbiterFee()
+                .accounts({ escrow, arbiter: arbiter.publicKey })
+                .signers([arbiter])
+                .rpc();
+
+        it("Accrues the fee on resolution and pays it on claim", async () => {
+            const { seller: sl, escrow, feeArbiter } = await resolveWithFee(250);
+
+            let account = await program.account.escrow.fetch(escrow);
+            expect(account.state).to.deep.equal({ released: {} });
+            expect(account.accruedArbiterFee.toNumber()).to.equal(0.025 * LAMPORTS_PER_SOL);
+            expect(await provider.connection.getBalance(sl.publicKey)).to.equal(0.975 * LAMPORTS_PER_SOL);
+
+            const before = await provider.connection.getBalance(feeArbiter.publicKey);
+            await claim(escrow, feeArbiter);
+            const after = await provider.connection.getBalance(feeArbiter.publicKey);
+
+            expect(after - before).to.be.at.least(0.025 * LAMPORTS_PER_SOL - 10_000);
+            account = await program.account.escrow.fetch(escrow);
+            expect(account.accruedArbiterFee.toNumber()).to.equal(0);
+
+            try {
+                await claim(escrow, feeArbiter);
+                assert.fail("Should not claim twice");
+            } catch (error) {
+                expect(error.toString()).to.include("NoFeeAccrued");
+            }
+
+            console.log("✅ Arbiter fee accrued and claimed");
+        });
+
+        it("Only lets the arbiter claim", async () => {
+            const { seller: sl, escrow } = await resolveWithFee(100);
+            await airdrop(sl.publicKey, LAMPORTS_PER_SOL);
+
+            try {
+                await claim(escrow, sl);
+                assert.fail("Seller should not claim the arbiter fee");
+            } catch (error) {
+                expect(error.toString()).to.include("Unauthorized");
+                console.log("✅ Non-arbiter claim rejected");
+            }
+        });
+
+        it("Keeps the escrow open until the fee is claimed", async () => {
+            const { buyer: b, escrow, feeArbiter } = await resolveWithFee(100);
+            const closeBatch = () =>
+                program.methods
+                    .closeEscrowsBatch()
+                    .accounts({ caller: provider.wallet.publicKey })
+                    .remainingAccounts([
+                        { pubkey: escrow, isSigner: false, isWritable: true },
+                        { pubkey: b.publicKey, isSigner: false, isWritable: true },
+                    ])
+                    .rpc();
+
+            try {
+                await closeBatch();
+                assert.fail("Should not close with an unclaimed fee");
+            } catch (error) {
+                expect(error.toString()).to.include("ArbiterFeeUnclaimed");
+            }
+
+            await claim(escrow, feeArbiter);
+            await closeBatch();
+            expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+
+            console.log("✅ Escrow closable once the fee is claimed");
+        });
     });
 
     describe("Edge Cases", () => {